@@ -0,0 +1,115 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr, Path, Type};
+
+/// Derives `HasName` from a field called `name`. Use
+/// `#[has_name(field = "id")]` to read another field, or
+/// `#[has_name(with = "path::to::fn")]` to call `fn(&Self) -> String`.
+#[proc_macro_derive(HasName, attributes(has_name))]
+pub fn derive_has_name(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(compile_error).into()
+}
+
+/// Like `syn::Error::into_compile_error`, which expands to
+/// `::core::compile_error!` and so breaks in crates where `core` names this
+/// library rather than the standard one.
+fn compile_error(err: syn::Error) -> TokenStream2 {
+    err.into_iter()
+        .map(|err| {
+            let message = err.to_string();
+            quote_spanned!(err.span()=> ::std::compile_error!(#message);)
+        })
+        .collect()
+}
+
+enum Source {
+    Field(Ident),
+    With(Path),
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let body = match parse_source(input)? {
+        Source::With(path) => quote! {
+            fn name(&self) -> ::std::string::String {
+                #path(self)
+            }
+        },
+        Source::Field(field) => {
+            let ty = find_field(input, &field)?;
+            let name_ref = is_string(ty).then(|| {
+                quote! {
+                    fn name_ref(&self) -> ::std::borrow::Cow<'_, str> {
+                        ::std::borrow::Cow::Borrowed(::std::string::String::as_str(&self.#field))
+                    }
+                }
+            });
+            quote! {
+                fn name(&self) -> ::std::string::String {
+                    ::std::string::ToString::to_string(&self.#field)
+                }
+
+                #name_ref
+            }
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::core::HasName for #ident #ty_generics #where_clause {
+            #body
+        }
+    })
+}
+
+fn parse_source(input: &DeriveInput) -> syn::Result<Source> {
+    let mut source = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("has_name")) {
+        attr.parse_nested_meta(|meta| {
+            if source.is_some() {
+                return Err(meta.error("only one of `field` or `with` may be given"));
+            }
+            let value: LitStr = meta.value()?.parse()?;
+            if meta.path.is_ident("field") {
+                source = Some(Source::Field(value.parse()?));
+            } else if meta.path.is_ident("with") {
+                source = Some(Source::With(value.parse()?));
+            } else {
+                return Err(meta.error("expected `field` or `with`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(source.unwrap_or_else(|| Source::Field(Ident::new("name", input.ident.span()))))
+}
+
+fn find_field<'a>(input: &'a DeriveInput, field: &Ident) -> syn::Result<&'a Type> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`HasName` can only be derived for structs; use #[has_name(with = \"...\")] otherwise",
+        ));
+    };
+    let found = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .find(|f| f.ident.as_ref() == Some(field)),
+        _ => None,
+    };
+    found.map(|f| &f.ty).ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            format!(
+                "no field named `{field}`; add #[has_name(field = \"...\")] or \
+                 #[has_name(with = \"...\")]"
+            ),
+        )
+    })
+}
+
+fn is_string(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("String"))
+}
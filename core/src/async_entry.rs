@@ -0,0 +1,174 @@
+//! An entry for async code, enabled by the `tokio` feature.
+
+use std::fmt;
+use std::sync::Arc;
+
+use tokio::sync::{Mutex, MutexGuard, OwnedMutexGuard};
+
+use crate::entry::{Entry, HasName};
+
+/// A shared value behind a `tokio::sync::Mutex`, so its guard can be held
+/// across an `.await` and waiting for it yields to the executor.
+///
+/// Unlike `Entry`, it keeps no metadata, listeners or freeze state.
+pub struct AsyncEntry<T>(Arc<Mutex<T>>);
+
+impl<T> Clone for AsyncEntry<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AsyncEntry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AsyncEntry").field(&self.0).finish()
+    }
+}
+
+impl<T> AsyncEntry<T> {
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(Mutex::new(value)))
+    }
+
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().await
+    }
+
+    /// A guard that owns a handle to the entry, so it can be moved into a
+    /// spawned task.
+    pub async fn lock_owned(&self) -> OwnedMutexGuard<T> {
+        Arc::clone(&self.0).lock_owned().await
+    }
+
+    pub async fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&*self.lock().await)
+    }
+
+    pub async fn mutate<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut *self.lock().await)
+    }
+
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Reclaims the value if this is the last handle, otherwise hands the
+    /// entry back.
+    pub fn try_unwrap(self) -> Result<T, Self> {
+        Arc::try_unwrap(self.0).map(Mutex::into_inner).map_err(Self)
+    }
+}
+
+impl<T> AsyncEntry<T>
+where
+    T: HasName + Clone,
+{
+    /// Moves the value into a new `Entry` if this is the last handle,
+    /// otherwise clones it. Either way the other handles keep sharing the
+    /// async value, not the returned entry.
+    pub async fn into_sync(self) -> Entry<T> {
+        match self.try_unwrap() {
+            Ok(value) => Entry::new(value),
+            Err(shared) => Entry::new(shared.lock().await.clone()),
+        }
+    }
+}
+
+impl<T> Entry<T>
+where
+    T: HasName + Clone,
+{
+    /// Moves the value into a new `AsyncEntry` if this is the last handle,
+    /// otherwise clones it. The entry's metadata and listeners do not carry
+    /// over.
+    pub fn into_async(self) -> AsyncEntry<T> {
+        match self.try_unwrap() {
+            Ok(value) => AsyncEntry::new(value),
+            Err(shared) => AsyncEntry::new(shared.read()),
+        }
+    }
+}
+
+impl<T> From<T> for AsyncEntry<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn mock(name: &str, value: i32) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            value,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_guard_held_across_await() {
+        let entry = AsyncEntry::new(mock("alpha", 1));
+        let mut guard = entry.lock().await;
+
+        let waiter = {
+            let entry = entry.clone();
+            tokio::spawn(async move { entry.mutate(|v| v.value * 10).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        guard.value = 2;
+        drop(guard);
+        assert_eq!(waiter.await.unwrap(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_owned_guard_moves_into_task() {
+        let entry = AsyncEntry::new(mock("alpha", 1));
+        let guard = entry.lock_owned().await;
+
+        let holder = tokio::spawn(async move {
+            let mut guard = guard;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            guard.value += 1;
+        });
+        assert_eq!(entry.with(|v| v.value).await, 2);
+        holder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_conversions() {
+        let unique = Entry::new(mock("alpha", 1)).into_async();
+        assert_eq!(unique.try_unwrap().unwrap(), mock("alpha", 1));
+
+        let entry = Entry::new(mock("beta", 2));
+        let converted = entry.clone().into_async();
+        converted.mutate(|v| v.value = 20).await;
+        assert_eq!(entry.read().value, 2);
+
+        let kept = converted.clone();
+        let back = converted.into_sync().await;
+        kept.mutate(|v| v.value = 200).await;
+        assert_eq!(back.read().value, 20);
+    }
+}
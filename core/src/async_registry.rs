@@ -0,0 +1,220 @@
+//! A registry for async code, enabled by the `tokio` feature.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use crate::async_entry::AsyncEntry;
+use crate::entry::HasName;
+use crate::registry::NamedRegistry;
+
+/// A registry whose map and values sit behind `tokio::sync` locks, so waiting
+/// on a contended name yields to the executor instead of blocking a worker
+/// thread.
+///
+/// The map lock is never held while a value is locked: `mutate` looks the
+/// entry up, releases the map and only then waits for the value.
+#[derive(Debug)]
+pub struct AsyncNamedRegistry<T>(Arc<RwLock<HashMap<String, AsyncEntry<T>>>>);
+
+impl<T> Clone for AsyncNamedRegistry<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> AsyncNamedRegistry<T>
+where
+    T: HasName,
+{
+    pub fn new() -> Self {
+        Self(Arc::default())
+    }
+
+    pub async fn insert(&self, value: T) -> Option<AsyncEntry<T>> {
+        let name = value.name();
+        self.0.write().await.insert(name, AsyncEntry::new(value))
+    }
+
+    pub async fn get(&self, name: &str) -> Option<AsyncEntry<T>> {
+        self.0.read().await.get(name).cloned()
+    }
+
+    pub async fn remove(&self, name: &str) -> Option<AsyncEntry<T>> {
+        self.0.write().await.remove(name)
+    }
+
+    pub async fn contains(&self, name: &str) -> bool {
+        self.0.read().await.contains_key(name)
+    }
+
+    pub async fn len(&self) -> usize {
+        self.0.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.0.read().await.is_empty()
+    }
+
+    pub async fn keys(&self) -> Vec<String> {
+        self.0.read().await.keys().cloned().collect()
+    }
+
+    pub async fn with<F, R>(&self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        Some(self.get(name).await?.with(f).await)
+    }
+
+    /// Runs `f` on the named value, re-keying the entry if `f` renamed it.
+    /// The entry keeps its old key until the map is locked again afterwards,
+    /// and stays put if it was removed or replaced meanwhile.
+    pub async fn mutate<F, R>(&self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let entry = self.get(name).await?;
+        let (result, new_name) = entry.mutate(|value| (f(value), value.name())).await;
+
+        if new_name != name {
+            let mut map = self.0.write().await;
+            if map.get(name).is_some_and(|current| current.ptr_eq(&entry)) {
+                let moved = map.remove(name).expect("checked above");
+                map.insert(new_name, moved);
+            }
+        }
+        Some(result)
+    }
+}
+
+impl<T> AsyncNamedRegistry<T>
+where
+    T: HasName + Clone,
+{
+    /// Copies the values of a sync registry as of one `snapshot`.
+    pub fn from_sync(registry: &NamedRegistry<T>) -> Self {
+        let map = registry
+            .snapshot()
+            .iter()
+            .map(|(name, value)| (name.to_string(), AsyncEntry::new(value.clone())))
+            .collect();
+        Self(Arc::new(RwLock::new(map)))
+    }
+
+    /// Copies the values into a new sync registry. The map stays read-locked
+    /// throughout, so no entry is added or removed midway, but each value is
+    /// copied under its own lock at a different moment.
+    pub async fn to_sync(&self) -> NamedRegistry<T> {
+        let map = self.0.read().await;
+        let mut values = Vec::with_capacity(map.len());
+        for entry in map.values() {
+            values.push(entry.with(T::clone).await);
+        }
+        NamedRegistry::from_entries(values)
+    }
+}
+
+impl<T> Default for AsyncNamedRegistry<T>
+where
+    T: HasName,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn mock(name: &str, value: i32) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            value,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_basics() {
+        let reg = AsyncNamedRegistry::new();
+        assert!(reg.insert(mock("alpha", 1)).await.is_none());
+        assert!(reg.insert(mock("beta", 2)).await.is_none());
+
+        assert_eq!(reg.mutate("alpha", |v| v.value += 1).await, Some(()));
+        assert_eq!(reg.with("alpha", |v| v.value).await, Some(2));
+        assert_eq!(reg.mutate("missing", |v| v.value += 1).await, None);
+        assert_eq!(reg.len().await, 2);
+
+        assert!(reg.remove("beta").await.is_some());
+        assert!(!reg.contains("beta").await);
+        assert_eq!(reg.keys().await, vec!["alpha".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_mutate_rekeys_renamed_entry() {
+        let reg = AsyncNamedRegistry::new();
+        reg.insert(mock("alpha", 1)).await;
+
+        reg.mutate("alpha", |v| v.name = "omega".into()).await;
+
+        assert!(!reg.contains("alpha").await);
+        assert_eq!(reg.with("omega", |v| v.value).await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_sync_round_trip() {
+        let sync = NamedRegistry::from_entries(vec![mock("alpha", 1), mock("beta", 2)]);
+
+        let reg = AsyncNamedRegistry::from_sync(&sync);
+        reg.mutate("alpha", |v| v.value = 10).await;
+        let back = reg.to_sync().await;
+
+        assert_eq!(sync.get_cloned("alpha").unwrap().value, 1);
+        assert_eq!(back.get_cloned("alpha").unwrap().value, 10);
+        assert_eq!(back.get_cloned("beta").unwrap().value, 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_tasks() {
+        let reg = AsyncNamedRegistry::new();
+        reg.insert(mock("shared", 0)).await;
+
+        let tasks: Vec<_> = (0..32)
+            .map(|t| {
+                let reg = reg.clone();
+                tokio::spawn(async move {
+                    let own = format!("task-{t}");
+                    reg.insert(mock(&own, 0)).await;
+                    for _ in 0..100 {
+                        reg.mutate(&own, |v| v.value += 1).await;
+                        reg.mutate("shared", |v| v.value += 1).await;
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(reg.with("shared", |v| v.value).await, Some(32 * 100));
+        for t in 0..32 {
+            assert_eq!(reg.with(&format!("task-{t}"), |v| v.value).await, Some(100));
+        }
+        assert_eq!(reg.len().await, 33);
+    }
+}
@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Enables the audit log in `RegistryOptions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditConfig {
+    /// Records kept before the oldest are evicted.
+    pub capacity: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOp {
+    Insert,
+    Mutate,
+    Remove,
+}
+
+/// One audited write. `name` is the entry's name once the write is done, so
+/// a renaming mutation is recorded under the new name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub name: String,
+    pub op: AuditOp,
+    pub timestamp: SystemTime,
+    /// Set by `with_actor` or the registry's `*_as` methods.
+    pub actor: Option<String>,
+}
+
+thread_local! {
+    static ACTOR: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `actor` recorded on every audited write this thread makes,
+/// restoring the previous actor afterwards, even if `f` panics.
+pub fn with_actor<R>(actor: &str, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Arc<str>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            ACTOR.with(|current| *current.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(ACTOR.with(|current| current.borrow_mut().replace(actor.into())));
+    f()
+}
+
+/// The actor set by the innermost `with_actor` on this thread.
+pub fn current_actor() -> Option<String> {
+    ACTOR.with(|current| current.borrow().as_deref().map(String::from))
+}
+
+/// A ring buffer of the most recent records.
+pub(crate) struct AuditLog {
+    records: VecDeque<AuditRecord>,
+    capacity: usize,
+}
+
+impl AuditLog {
+    pub(crate) fn new(config: AuditConfig) -> Self {
+        Self {
+            records: VecDeque::with_capacity(config.capacity),
+            capacity: config.capacity,
+        }
+    }
+
+    pub(crate) fn push(&mut self, record: AuditRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    pub(crate) fn records(&self) -> impl Iterator<Item = &AuditRecord> {
+        self.records.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    fn record(name: &str) -> AuditRecord {
+        AuditRecord {
+            name: name.into(),
+            op: AuditOp::Insert,
+            timestamp: SystemTime::UNIX_EPOCH,
+            actor: current_actor(),
+        }
+    }
+
+    #[rstest]
+    fn test_log_evicts_oldest_first() {
+        let mut log = AuditLog::new(AuditConfig { capacity: 2 });
+        for name in ["a", "b", "c"] {
+            log.push(record(name));
+        }
+
+        let names: Vec<_> = log.records().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["b", "c"]);
+    }
+
+    #[rstest]
+    fn test_with_actor_nests_and_restores() {
+        assert_eq!(current_actor(), None);
+        with_actor("alice", || {
+            with_actor("bob", || {
+                assert_eq!(current_actor().as_deref(), Some("bob"))
+            });
+            assert_eq!(current_actor().as_deref(), Some("alice"));
+        });
+        assert_eq!(current_actor(), None);
+    }
+}
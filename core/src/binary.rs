@@ -0,0 +1,248 @@
+//! Compact snapshot files, enabled by the `binary` feature.
+//!
+//! A file is the magic bytes, a format version, the payload's length and
+//! FNV-1a checksum (both little-endian `u64`), then the payload: the entry
+//! count followed by each value, bincode-encoded.
+
+use std::fs::{self, File};
+use std::hash::BuildHasher;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::entry::HasName;
+use crate::error::SnapshotError;
+use crate::registry::{MergePolicy, NamedRegistry};
+
+const MAGIC: &[u8; 4] = b"NREG";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8 + 8;
+
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn corrupt(err: bincode::Error) -> SnapshotError {
+    SnapshotError::Corrupt(err.to_string())
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + Serialize,
+    S: BuildHasher + Default,
+{
+    /// Writes every value in the default namespace to `path`. The file is
+    /// written beside `path` and renamed over it, so readers never see a
+    /// partial snapshot.
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        let values = self.values();
+        let mut payload = Vec::new();
+        bincode::serialize_into(&mut payload, &(values.len() as u64)).map_err(io::Error::other)?;
+        for entry in &values {
+            bincode::serialize_into(&mut payload, &*entry.lock()).map_err(io::Error::other)?;
+        }
+
+        let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+        tmp_name.push(".tmp");
+        let tmp = path.with_file_name(tmp_name);
+        let mut file = BufWriter::new(File::create(&tmp)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&[VERSION])?;
+        file.write_all(&(payload.len() as u64).to_le_bytes())?;
+        file.write_all(&checksum(&payload).to_le_bytes())?;
+        file.write_all(&payload)?;
+        file.into_inner()
+            .map_err(io::IntoInnerError::into_error)?
+            .sync_all()?;
+        fs::rename(&tmp, path)
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + DeserializeOwned,
+    S: BuildHasher + Default,
+{
+    /// Reads a registry written by `save_to`.
+    pub fn load_from(path: &Path) -> Result<Self, SnapshotError> {
+        let bytes = fs::read(path)?;
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let header = bytes
+            .get(..HEADER_LEN)
+            .ok_or_else(|| SnapshotError::Corrupt("truncated header".into()))?;
+        let version = header[MAGIC.len()];
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let word = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap());
+        let (len, sum) = (word(MAGIC.len() + 1), word(MAGIC.len() + 9));
+
+        let payload = &bytes[HEADER_LEN..];
+        if payload.len() as u64 != len {
+            return Err(SnapshotError::Corrupt(format!(
+                "expected {len} payload bytes, found {}",
+                payload.len()
+            )));
+        }
+        if checksum(payload) != sum {
+            return Err(SnapshotError::Corrupt("checksum mismatch".into()));
+        }
+
+        let mut reader = payload;
+        let count: u64 = bincode::deserialize_from(&mut reader).map_err(corrupt)?;
+        let registry = Self::with_hasher(S::default());
+        for _ in 0..count {
+            let value: T = bincode::deserialize_from(&mut reader).map_err(corrupt)?;
+            let name = value.name();
+            if registry.contains(name.as_str()) {
+                return Err(SnapshotError::Corrupt(format!("duplicate name `{name}`")));
+            }
+            registry
+                .insert(value)
+                .map_err(|err| SnapshotError::Corrupt(err.to_string()))?;
+        }
+        if !reader.is_empty() {
+            return Err(SnapshotError::Corrupt("trailing bytes".into()));
+        }
+        Ok(registry)
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + DeserializeOwned + Clone,
+    S: BuildHasher + Default,
+{
+    /// Merges a snapshot into this registry as `merge` would. Nothing is
+    /// merged if the file fails to load.
+    pub fn load_into(&self, path: &Path, policy: MergePolicy<T>) -> Result<(), SnapshotError> {
+        let loaded = Self::load_from(path)?;
+        self.merge(&loaded, policy);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use serde::Deserialize;
+    use std::path::PathBuf;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn mock(name: &str, value: i32) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            value,
+        }
+    }
+
+    // a fresh directory per test, so parallel tests do not collide
+    fn scratch(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("core-binary-{}-{test}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn saved(test: &str) -> PathBuf {
+        let path = scratch(test).join("registry.snap");
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+        reg.save_to(&path).unwrap();
+        path
+    }
+
+    #[rstest]
+    fn test_snapshot_round_trip() {
+        let path = saved("round-trip");
+        let files: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap().collect();
+        assert_eq!(files.len(), 1, "the temporary file is renamed away");
+
+        let back = NamedRegistry::<InnerMock>::load_from(&path).unwrap();
+        assert_eq!(back.len(), 2);
+        assert_eq!(back.with("beta", |v| v.value), Some(2));
+
+        back.insert(mock("gamma", 3)).unwrap();
+        back.save_to(&path).unwrap();
+        let again = NamedRegistry::<InnerMock>::load_from(&path).unwrap();
+        assert_eq!(again.len(), 3);
+    }
+
+    #[rstest]
+    fn test_corrupt_snapshots_are_detected() {
+        let path = saved("corrupt");
+        let bytes = fs::read(&path).unwrap();
+        let load = |bytes: &[u8]| {
+            fs::write(&path, bytes).unwrap();
+            NamedRegistry::<InnerMock>::load_from(&path).unwrap_err()
+        };
+
+        let mut flipped = bytes.clone();
+        *flipped.last_mut().unwrap() ^= 0xff;
+        assert!(
+            matches!(load(&flipped), SnapshotError::Corrupt(reason) if reason == "checksum mismatch")
+        );
+        assert!(matches!(
+            load(&bytes[..bytes.len() - 1]),
+            SnapshotError::Corrupt(_)
+        ));
+        assert!(matches!(
+            load(&bytes[..HEADER_LEN - 1]),
+            SnapshotError::Corrupt(_)
+        ));
+        assert!(matches!(
+            load(b"{\"alpha\": 1}"),
+            SnapshotError::NotASnapshot
+        ));
+        assert!(matches!(
+            NamedRegistry::<InnerMock>::load_from(&path.with_extension("missing")),
+            Err(SnapshotError::Io(err)) if err.kind() == io::ErrorKind::NotFound
+        ));
+    }
+
+    #[rstest]
+    fn test_other_format_versions_are_rejected() {
+        let path = saved("version");
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[MAGIC.len()] = VERSION + 1;
+        fs::write(&path, bytes).unwrap();
+
+        let err = NamedRegistry::<InnerMock>::load_from(&path).unwrap_err();
+        assert!(matches!(err, SnapshotError::UnsupportedVersion(v) if v == VERSION + 1));
+        assert_eq!(err.to_string(), "unsupported snapshot format version 2");
+    }
+
+    #[rstest]
+    fn test_load_into_merges_with_live_entries() {
+        let path = saved("merge");
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 10)).unwrap();
+        reg.insert(mock("delta", 4)).unwrap();
+
+        reg.load_into(&path, MergePolicy::KeepExisting).unwrap();
+
+        assert_eq!(reg.len(), 3);
+        assert_eq!(reg.with("alpha", |v| v.value), Some(10));
+        assert_eq!(reg.with("beta", |v| v.value), Some(2));
+    }
+}
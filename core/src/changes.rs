@@ -0,0 +1,112 @@
+//! Per-subscriber change streams, enabled by the `tokio` feature.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+
+use crate::registry::RegistryEvent;
+use crate::sync::{Mutex, MutexExt};
+
+/// Events `NamedRegistry::changes` buffers per stream before it lags.
+pub const CHANGES_DEPTH: usize = 256;
+
+/// The changes a registry reports to its `subscribe` receivers, as a
+/// `Stream`. Once `depth` events are waiting, later ones are dropped and
+/// counted until the consumer has drained the buffer, which then reads
+/// a single `RegistryEvent::Lagged` where the dropped events would have
+/// been. The stream ends when the registry is dropped.
+pub struct ChangeStream(Arc<Queue>);
+
+struct Queue {
+    state: Mutex<QueueState>,
+    depth: usize,
+}
+
+#[derive(Default)]
+struct QueueState {
+    events: VecDeque<RegistryEvent>,
+    missed: u64,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+impl Queue {
+    fn push(&self, event: &RegistryEvent) {
+        let mut state = self.state.acquire();
+        if state.missed > 0 || state.events.len() == self.depth {
+            state.missed += 1;
+        } else {
+            state.events.push_back(event.clone());
+        }
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn close(&self) {
+        let mut state = self.state.acquire();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Stream for ChangeStream {
+    type Item = RegistryEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<RegistryEvent>> {
+        let mut state = self.0.state.acquire();
+        if let Some(event) = state.events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        if state.missed > 0 {
+            return Poll::Ready(Some(RegistryEvent::Lagged(std::mem::take(
+                &mut state.missed,
+            ))));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// The registry's side: weak handles to its streams, closed on drop.
+#[derive(Default)]
+pub(crate) struct ChangeStreams(Mutex<Vec<Weak<Queue>>>);
+
+impl ChangeStreams {
+    pub(crate) fn open(&self, depth: usize) -> ChangeStream {
+        assert!(depth > 0, "a change stream needs room for an event");
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState::default()),
+            depth,
+        });
+        self.0.acquire().push(Arc::downgrade(&queue));
+        ChangeStream(queue)
+    }
+
+    pub(crate) fn publish(&self, events: &[RegistryEvent]) {
+        let mut queues = self.0.acquire();
+        queues.retain(|queue| match queue.upgrade() {
+            Some(queue) => {
+                events.iter().for_each(|event| queue.push(event));
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+impl Drop for ChangeStreams {
+    fn drop(&mut self) {
+        for queue in self.0.acquire().iter().filter_map(Weak::upgrade) {
+            queue.close();
+        }
+    }
+}
@@ -1,108 +1,1677 @@
-use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::{Duration, Instant, SystemTime};
 
-#[derive(Debug, Clone)]
-pub struct Entry<T: Clone>(Arc<Mutex<T>>);
+use crate::clock::{self, Clock};
+use crate::error::{CasError, Frozen, LockTimeout, PoisonedEntry};
+use crate::sync::{
+    self, Mutex, MutexExt, MutexGuard, RwLock, RwLockExt, RwLockReadGuard, RwLockWriteGuard,
+};
 
-impl<T: Clone> Entry<T>
+pub struct Entry<T> {
+    inner: Arc<Mutex<T>>,
+    state: Arc<EntryState<T>>,
+}
+
+/// Bookkeeping shared by every clone of an entry, kept outside the value's
+/// mutex so it can be read without locking the value.
+struct EntryState<T> {
+    name: RwLock<Arc<str>>,
+    version: AtomicU64,
+    clock: Arc<dyn Clock>,
+    created_at: SystemTime,
+    last_modified: RwLock<SystemTime>,
+    expiry: RwLock<Option<Expiry>>,
+    // tick of the last registry lookup, for LRU eviction
+    last_used: AtomicU64,
+    meta: RwLock<HashMap<String, String>>,
+    frozen: AtomicBool,
+    listeners: RwLock<Vec<(SubscriptionId, Listener<T>)>>,
+    next_subscription: AtomicU64,
+    // set by the first `on_change`, which is where `T: Clone` is known
+    snapshot: OnceLock<fn(&T) -> T>,
+}
+
+type Listener<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+#[derive(Clone, Copy)]
+struct Expiry {
+    deadline: SystemTime,
+    ttl: Duration,
+}
+
+/// Identifies an `on_change` callback for `unsubscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+impl SubscriptionId {
+    pub(crate) fn next(counter: &AtomicU64) -> Self {
+        Self(counter.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl<T> EntryState<T> {
+    fn name_is(&self, name: &str) -> bool {
+        **self.name.shared() == *name
+    }
+
+    fn new(name: Arc<str>, clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        Self {
+            name: RwLock::new(name),
+            version: AtomicU64::new(0),
+            clock,
+            created_at: now,
+            last_modified: RwLock::new(now),
+            expiry: RwLock::new(None),
+            last_used: AtomicU64::new(0),
+            meta: RwLock::new(HashMap::new()),
+            frozen: AtomicBool::new(false),
+            listeners: RwLock::new(Vec::new()),
+            next_subscription: AtomicU64::new(0),
+            snapshot: OnceLock::new(),
+        }
+    }
+
+    /// A copy of `value` for the listeners, if there are any.
+    fn snapshot(&self, value: &T) -> Option<T> {
+        let clone = self.snapshot.get()?;
+        let listeners = self.listeners.shared();
+        (!listeners.is_empty()).then(|| clone(value))
+    }
+
+    fn notify(&self, value: &T) {
+        let listeners: Vec<_> = self
+            .listeners
+            .shared()
+            .iter()
+            .map(|(_, listener)| Arc::clone(listener))
+            .collect();
+        for listener in listeners {
+            listener(value);
+        }
+    }
+}
+
+impl<T> Clone for Entry<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<T> Entry<T> {
+    /// Compares against the cached name without allocating.
+    pub(crate) fn name_is(&self, name: &str) -> bool {
+        self.state.name_is(name)
+    }
+
+    pub(crate) fn mark_used(&self, tick: u64) {
+        self.state.last_used.store(tick, Ordering::Relaxed);
+    }
+
+    pub(crate) fn last_used(&self) -> u64 {
+        self.state.last_used.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn cached_name(&self) -> Arc<str> {
+        let name = self.state.name.shared();
+        Arc::clone(&name)
+    }
+
+    /// Formats the value with `fmt`, or `<locked>` if it is held elsewhere so
+    /// that logging never blocks.
+    pub(crate) fn fmt_value(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        fmt: impl FnOnce(&T, &mut fmt::Formatter<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        match self.inner.try_acquire() {
+            Some(guard) => fmt(&guard, f),
+            None => f.write_str("<locked>"),
+        }
+    }
+}
+
+/// Prints `Entry("name", value)`, with `<locked>` in place of a value that is
+/// currently locked.
+impl<T: fmt::Debug> fmt::Debug for Entry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Entry")
+            .field(&&*self.cached_name())
+            .field(&DebugValue(self))
+            .finish()
+    }
+}
+
+/// Displays the value, or `<locked>` like `Debug`.
+impl<T: fmt::Display> fmt::Display for Entry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_value(f, fmt::Display::fmt)
+    }
+}
+
+/// Debug-formats only an entry's value.
+pub(crate) struct DebugValue<'a, T>(pub(crate) &'a Entry<T>);
+
+impl<T: fmt::Debug> fmt::Debug for DebugValue<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_value(f, fmt::Debug::fmt)
+    }
+}
+
+/// Compares the values, unlike `ptr_eq`. An entry equals itself without
+/// locking, and two entries are locked in address order so that concurrent
+/// `a == b` and `b == a` cannot deadlock.
+impl<T> PartialEq for Entry<T>
+where
+    T: HasName + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        if self.ptr_eq(other) {
+            return true;
+        }
+        let (this, other) = self.lock_pair(other);
+        *this == *other
+    }
+}
+
+impl<T> Eq for Entry<T> where T: HasName + Eq {}
+
+impl<T> Entry<T>
 where
     T: HasName,
 {
     pub fn new(inner: T) -> Self {
-        Self(Arc::new(Mutex::new(inner)))
+        Self::with_clock(inner, clock::system())
+    }
+
+    /// Like `new`, but timestamps come from `clock`.
+    pub fn with_clock(inner: T, clock: Arc<dyn Clock>) -> Self {
+        let name = Arc::from(&*inner.name_ref());
+        Self::named(inner, name, clock)
+    }
+
+    /// Builds an entry whose cached name is `name`, which must be `inner`'s
+    /// name; the registry passes the `Arc` it keys the entry under.
+    pub(crate) fn named(inner: T, name: Arc<str>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+            state: Arc::new(EntryState::new(name, clock)),
+        }
     }
 
+    // wraps a value other holders already share
+    fn adopt(inner: Arc<Mutex<T>>) -> Self {
+        let name = inner.acquire().name();
+        Self {
+            inner,
+            state: Arc::new(EntryState::new(name.into(), clock::system())),
+        }
+    }
+
+    /// Panics if the entry is frozen; see `checked_update`.
     pub fn update(&self, inner: &mut T) {
+        self.assert_thawed();
         let mut guard = self.lock();
         std::mem::swap(&mut *guard, inner);
     }
 
-    pub fn mutate<F>(&self, f: F)
+    /// Panics if the entry is frozen; see `checked_replace`.
+    pub fn replace(&self, value: T) -> T {
+        self.assert_thawed();
+        std::mem::replace(&mut *self.lock(), value)
+    }
+
+    /// Panics if the entry is frozen.
+    pub fn set(&self, value: T) {
+        self.assert_thawed();
+        *self.lock() = value;
+    }
+
+    /// Panics if the entry is frozen; see `checked_mutate`.
+    pub fn mutate<F, R>(&self, f: F) -> R
     where
-        F: FnOnce(&mut T),
+        F: FnOnce(&mut T) -> R,
     {
+        self.assert_thawed();
         f(&mut self.lock())
     }
 
-    pub fn arc(&self) -> Arc<Mutex<T>> {
-        Arc::clone(&self.0)
+    pub fn checked_update(&self, inner: &mut T) -> Result<(), Frozen> {
+        self.checked_mutate(|value| std::mem::swap(value, inner))
+    }
+
+    pub fn checked_replace(&self, value: T) -> Result<T, Frozen> {
+        self.checked_mutate(|current| std::mem::replace(current, value))
+    }
+
+    /// Like `mutate`, but fails instead of panicking on a frozen entry.
+    pub fn checked_mutate<F, R>(&self, f: F) -> Result<R, Frozen>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut guard = self.lock();
+        if self.is_frozen() {
+            return Err(Frozen(self.name()));
+        }
+        Ok(f(&mut guard))
+    }
+
+    /// Makes the entry read-only until `unfreeze` is called with the returned
+    /// token. Waits for the current lock holder, so a write already under way
+    /// finishes first; calling it while holding this entry's guard deadlocks.
+    pub fn freeze(&self) -> FreezeToken {
+        let _guard = self.inner.acquire();
+        self.state.frozen.store(true, Ordering::Release);
+        FreezeToken::new()
+    }
+
+    pub fn unfreeze(&self, _token: &FreezeToken) {
+        self.state.frozen.store(false, Ordering::Release);
+    }
+
+    /// Removes an `on_change` listener, returning whether it was registered.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut listeners = self.state.listeners.exclusive();
+        let before = listeners.len();
+        listeners.retain(|(other, _)| *other != id);
+        listeners.len() != before
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.state.frozen.load(Ordering::Acquire)
+    }
+
+    fn assert_thawed(&self) {
+        if self.is_frozen() {
+            panic!("{}", Frozen(self.name()));
+        }
+    }
+
+    /// Runs `f` with shared access to the value.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.lock())
+    }
+
+    /// Whether both handles share one value. This is identity, not value
+    /// equality, and never locks.
+    pub fn ptr_eq(&self, other: &Entry<T>) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Identity of the shared value, for indexes keyed by entry.
+    pub(crate) fn addr(&self) -> usize {
+        Arc::as_ptr(&self.inner) as usize
+    }
+
+    /// The shared value without the entry's bookkeeping.
+    pub fn arc(&self) -> SharedValue<T> {
+        SharedValue(Arc::clone(&self.inner))
+    }
+
+    pub fn weak(&self) -> WeakEntry<T> {
+        WeakEntry {
+            inner: Arc::downgrade(&self.inner),
+            state: Arc::downgrade(&self.state),
+        }
+    }
+
+    /// Live handles to the value: this entry, its clones, and any `arc`
+    /// handed out. Other threads may change it at any moment, so it is only
+    /// a hint.
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+
+    /// `WeakEntry` handles to the value; as racy as `strong_count`.
+    pub fn weak_count(&self) -> usize {
+        Arc::weak_count(&self.inner)
+    }
+
+    /// Counts writes: each guard that handed out mutable access bumps it once
+    /// when dropped, so `mutate`, `update`, `set` and `replace` add exactly
+    /// one. Shared by all clones.
+    pub fn version(&self) -> u64 {
+        self.state.version.load(Ordering::Acquire)
+    }
+
+    pub fn created_at(&self) -> SystemTime {
+        self.state.created_at
+    }
+
+    /// When a guard last handed out mutable access; `created_at` until then.
+    pub fn last_modified(&self) -> SystemTime {
+        *self.state.last_modified.shared()
+    }
+
+    /// When a registry stops handing out this entry, if it was inserted with
+    /// a TTL. Handles already held keep working past it.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.state.expiry.shared().map(|expiry| expiry.deadline)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at()
+            .is_some_and(|deadline| self.state.clock.now() >= deadline)
+    }
+
+    /// Expires the entry `ttl` from now.
+    pub(crate) fn set_ttl(&self, ttl: Duration) {
+        let deadline = self.state.clock.now() + ttl;
+        *self.state.expiry.exclusive() = Some(Expiry { deadline, ttl });
+    }
+
+    /// Restarts the TTL from now; false if the entry has none.
+    pub(crate) fn refresh_ttl(&self) -> bool {
+        let mut expiry = self.state.expiry.exclusive();
+        match &mut *expiry {
+            Some(expiry) => {
+                expiry.deadline = self.state.clock.now() + expiry.ttl;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Attaches `value` under `key`, returning the previous one. Metadata
+    /// lives beside the value, so it survives `replace` and never waits on the
+    /// value's lock.
+    pub fn set_meta(&self, key: &str, value: String) -> Option<String> {
+        self.meta_mut().insert(key.to_string(), value)
+    }
+
+    pub fn meta(&self, key: &str) -> Option<String> {
+        self.meta_ref().get(key).cloned()
+    }
+
+    pub fn remove_meta(&self, key: &str) -> Option<String> {
+        self.meta_mut().remove(key)
+    }
+
+    /// All metadata as `(key, value)` pairs, sorted by key.
+    pub fn tags(&self) -> Vec<(String, String)> {
+        let mut tags: Vec<_> = self
+            .meta_ref()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        tags.sort();
+        tags
+    }
+
+    fn meta_ref(&self) -> RwLockReadGuard<'_, HashMap<String, String>> {
+        self.state.meta.shared()
+    }
+
+    fn meta_mut(&self) -> RwLockWriteGuard<'_, HashMap<String, String>> {
+        self.state.meta.exclusive()
+    }
+
+    /// Applies `f` only if nothing has written to the entry since it was at
+    /// `expected`, returning the new version. The check and the write happen
+    /// under one lock, so of several writers holding the same version exactly
+    /// one succeeds; the rest get the current version back to retry from.
+    pub fn compare_and_mutate<F>(&self, expected: u64, f: F) -> Result<u64, CasError>
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut guard = self.lock();
+        if self.is_frozen() {
+            return Err(CasError::Frozen(self.name()));
+        }
+        let current = self.version();
+        if current != expected {
+            return Err(CasError::Stale { current });
+        }
+        f(&mut guard);
+        drop(guard);
+        Ok(expected + 1)
+    }
+
+    /// Locks the value. Writing through the guard refreshes the cached name,
+    /// version and modification time when the guard is dropped. A poisoned
+    /// mutex is recovered rather than propagated; use `try_access` to detect
+    /// it.
+    pub fn lock(&self) -> EntryGuard<'_, T> {
+        self.guard(self.inner.acquire())
+    }
+
+    /// Locks two distinct entries in address order, the order every lock of
+    /// two entries in the crate takes, so none of them can deadlock against
+    /// another. The guards come back in argument order.
+    pub(crate) fn lock_pair<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> (EntryGuard<'a, T>, EntryGuard<'a, T>) {
+        if self.addr() < other.addr() {
+            let first = self.lock();
+            (first, other.lock())
+        } else {
+            let second = other.lock();
+            (self.lock(), second)
+        }
+    }
+
+    /// Locks the value behind a guard that owns this handle, so it can be
+    /// returned or stored without borrowing an `Entry`. Like any std mutex
+    /// guard it cannot be sent to another thread; see `scope_owned`.
+    pub fn lock_owned(self) -> OwnedEntryGuard<T>
+    where
+        T: 'static,
+    {
+        let guard = self.lock();
+        // SAFETY: the guard borrows the mutex and state behind `self`'s `Arc`s,
+        // which do not move with `self` and which the owned guard keeps alive.
+        // `guard` is declared before `entry`, so it is dropped first.
+        let guard =
+            unsafe { std::mem::transmute::<EntryGuard<'_, T>, EntryGuard<'static, T>>(guard) };
+        OwnedEntryGuard { guard, entry: self }
+    }
+
+    /// Packages `f` with this handle into a `'static` job, e.g. for
+    /// `thread::spawn`. The entry is locked when the job runs, on whichever
+    /// thread that is.
+    pub fn scope_owned<F, R>(self, f: F) -> impl FnOnce() -> R + Send + 'static
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut T) -> R + Send + 'static,
+    {
+        move || self.mutate(f)
+    }
+
+    /// Locks the value and projects the guard onto a part of it, e.g. one
+    /// field. The lock is held until the returned guard is dropped.
+    pub fn map_guard<U, F>(&self, f: F) -> MappedEntryGuard<'_, T, U>
+    where
+        U: ?Sized,
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        self.lock().map(f)
+    }
+
+    /// Locks the value only if that does not require waiting.
+    pub fn try_lock(&self) -> Option<EntryGuard<'_, T>> {
+        self.inner.try_acquire().map(|guard| self.guard(guard))
+    }
+
+    /// Applies `f` unless the value is currently locked elsewhere.
+    pub fn try_mutate<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        self.try_lock().map(|mut guard| f(&mut guard))
+    }
+
+    /// Waits at most `timeout` for the lock. The std build polls with a short
+    /// backoff, since `std::sync::Mutex` has no timed acquisition.
+    pub fn lock_timeout(&self, timeout: Duration) -> Result<EntryGuard<'_, T>, LockTimeout> {
+        let start = Instant::now();
+        match self.inner.acquire_for(timeout) {
+            Some(guard) => Ok(self.guard(guard)),
+            None => Err(LockTimeout {
+                name: self.name(),
+                waited: start.elapsed(),
+            }),
+        }
+    }
+
+    pub fn try_access(&self) -> Result<EntryGuard<'_, T>, PoisonedEntry> {
+        self.inner
+            .acquire_unpoisoned()
+            .map(|guard| self.guard(guard))
+            .ok_or_else(|| PoisonedEntry(self.name()))
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.poisoned()
+    }
+
+    pub fn clear_poison(&self) {
+        self.inner.unpoison();
+    }
+
+    fn guard<'a>(&'a self, guard: MutexGuard<'a, T>) -> EntryGuard<'a, T> {
+        EntryGuard {
+            guard: ManuallyDrop::new(guard),
+            state: &self.state,
+            dirty: false,
+        }
     }
 
-    pub fn weak(&self) -> Weak<Mutex<T>> {
-        Arc::downgrade(&self.0)
+    /// Reclaims the value if this is the last strong handle, otherwise hands
+    /// the entry back. Weak handles do not count, and a poisoned value is
+    /// recovered.
+    pub fn try_unwrap(self) -> Result<T, Self> {
+        let state = self.state;
+        Arc::try_unwrap(self.inner)
+            .map(sync::into_inner)
+            .map_err(|inner| Self { inner, state })
     }
 
-    pub fn lock(&self) -> MutexGuard<'_, T> {
-        self.0.lock().unwrap()
+    /// Like `try_unwrap`, but drops the handle when it is not the last one.
+    pub fn into_inner(self) -> Option<T> {
+        Arc::into_inner(self.inner).map(sync::into_inner)
     }
 }
 
-pub trait HasName {
-    fn name(&self) -> String;
+impl<T> Default for Entry<T>
+where
+    T: HasName + Default,
+{
+    fn default() -> Self {
+        Self::new(T::default())
+    }
 }
 
-impl<T> HasName for Entry<T>
+impl<T> From<T> for Entry<T>
 where
-    T: HasName + Clone,
+    T: HasName,
 {
-    fn name(&self) -> String {
-        self.0.try_lock().unwrap_or(self.lock()).name()
+    fn from(value: T) -> Self {
+        Self::new(value)
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use rstest::rstest;
+/// Adopts existing shared state without re-wrapping: the entry and the other
+/// holders of the value see each other's writes. Writes made through a
+/// `SharedValue` bypass the entry's bookkeeping, so they do not refresh its
+/// cached name or bump its version.
+impl<T> From<SharedValue<T>> for Entry<T>
+where
+    T: HasName,
+{
+    fn from(SharedValue(inner): SharedValue<T>) -> Self {
+        Self::adopt(inner)
+    }
+}
 
-    #[derive(Debug, Clone, PartialEq)]
-    struct InnerMock {
-        name: String,
-        value: i32,
+/// Adopts a raw `Arc<Mutex<T>>` shared with code outside the registry, as
+/// `From<SharedValue<T>>` does.
+#[cfg(not(feature = "parking_lot"))]
+impl<T> From<Arc<std::sync::Mutex<T>>> for Entry<T>
+where
+    T: HasName,
+{
+    fn from(inner: Arc<std::sync::Mutex<T>>) -> Self {
+        Self::adopt(inner)
     }
+}
 
-    impl HasName for InnerMock {
-        fn name(&self) -> String {
-            self.name.clone()
+/// Adopts a raw `Arc<parking_lot::Mutex<T>>`, the `parking_lot` build's
+/// counterpart of `From<Arc<std::sync::Mutex<T>>>`.
+#[cfg(feature = "parking_lot")]
+impl<T> From<Arc<parking_lot::Mutex<T>>> for Entry<T>
+where
+    T: HasName,
+{
+    fn from(inner: Arc<parking_lot::Mutex<T>>) -> Self {
+        Self::adopt(inner)
+    }
+}
+
+/// A bare shared value, as handed out by `Entry::arc`. Its API is the same
+/// whichever lock backend the crate is built with.
+pub struct SharedValue<T>(Arc<Mutex<T>>);
+
+impl<T> SharedValue<T> {
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(Mutex::new(value)))
+    }
+
+    /// Locks the value, recovering it from a poisoned lock.
+    pub fn lock(&self) -> ValueGuard<'_, T> {
+        ValueGuard(self.0.acquire())
+    }
+
+    /// Whether both handles share one value.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T> Clone for SharedValue<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedValue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedValue").field(&self.0).finish()
+    }
+}
+
+/// A lock on a `SharedValue`.
+pub struct ValueGuard<'a, T>(MutexGuard<'a, T>);
+
+impl<T> Deref for ValueGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ValueGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Returned by `freeze` and required by `unfreeze`, so that thawing an entry
+/// takes deliberate effort. Any token unfreezes any entry.
+#[derive(Debug)]
+#[must_use = "without the token the freeze cannot be undone"]
+pub struct FreezeToken(());
+
+impl FreezeToken {
+    pub(crate) fn new() -> Self {
+        Self(())
+    }
+}
+
+/// A non-owning handle to an entry. It does not keep the value alive and
+/// does not count towards `try_unwrap`.
+#[derive(Debug)]
+pub struct WeakEntry<T> {
+    inner: Weak<Mutex<T>>,
+    state: Weak<EntryState<T>>,
+}
+
+impl<T> Clone for WeakEntry<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Weak::clone(&self.inner),
+            state: Weak::clone(&self.state),
         }
     }
+}
 
-    #[rstest]
-    fn test_new_creates_entry() {
-        let data = InnerMock {
+impl<T> WeakEntry<T> {
+    pub fn upgrade(&self) -> Option<Entry<T>> {
+        Some(Entry {
+            inner: self.inner.upgrade()?,
+            state: self.state.upgrade()?,
+        })
+    }
+
+    /// Number of live `Entry` handles.
+    pub fn strong_count(&self) -> usize {
+        self.inner.strong_count()
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.strong_count() > 0
+    }
+
+    /// The bare weak pointer to the value. There is no `from_raw`: the
+    /// entry's cached name, version and metadata cannot be recovered from it.
+    pub fn into_raw(self) -> Weak<Mutex<T>> {
+        self.inner
+    }
+}
+
+impl<T> Entry<T>
+where
+    T: HasName + Clone,
+{
+    /// Clones the current value, holding the lock only for the copy.
+    pub fn read(&self) -> T {
+        self.lock().clone()
+    }
+
+    /// Calls `f` with a copy of the value after every write. Listeners run
+    /// once the lock is released, in subscription order, so they may use the
+    /// entry themselves; writes from other threads can interleave, so the copy
+    /// may already be stale.
+    pub fn on_change<F>(&self, f: F) -> SubscriptionId
+    where
+        F: Fn(&T) + Send + Sync + 'static,
+    {
+        self.state.snapshot.get_or_init(|| T::clone);
+        let id = SubscriptionId::next(&self.state.next_subscription);
+        self.state.listeners.exclusive().push((id, Arc::new(f)));
+        id
+    }
+}
+
+pub struct EntryGuard<'a, T: HasName> {
+    // dropped by hand so listeners run after the mutex is released
+    guard: ManuallyDrop<MutexGuard<'a, T>>,
+    state: &'a EntryState<T>,
+    dirty: bool,
+}
+
+impl<'a, T: HasName> EntryGuard<'a, T> {
+    pub fn map<U, F>(mut self, f: F) -> MappedEntryGuard<'a, T, U>
+    where
+        U: ?Sized,
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let value = NonNull::from(f(&mut self));
+        MappedEntryGuard {
+            value,
+            guard: self,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: HasName> Deref for EntryGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: HasName> DerefMut for EntryGuard<'_, T> {
+    /// Panics if the entry is frozen, which also poisons it; the checked
+    /// methods on `Entry` avoid that.
+    fn deref_mut(&mut self) -> &mut T {
+        assert!(
+            !self.state.frozen.load(Ordering::Acquire),
+            "entry `{}` is frozen",
+            self.guard.name()
+        );
+        self.dirty = true;
+        &mut self.guard
+    }
+}
+
+impl<T: HasName> EntryGuard<'_, T> {
+    /// Updates the entry's bookkeeping after a write, returning a snapshot
+    /// for the `on_change` listeners.
+    fn record_write(&mut self) -> Option<T> {
+        self.state.version.fetch_add(1, Ordering::AcqRel);
+
+        // calling into `T::name`, the clock or `T::clone` while unwinding
+        // risks a double panic
+        if std::thread::panicking() {
+            return None;
+        }
+        let now = self.state.clock.now();
+        *self.state.last_modified.exclusive() = now;
+
+        let name = self.guard.name_ref();
+        if !self.state.name_is(&name) {
+            *self.state.name.exclusive() = Arc::from(&*name);
+        }
+        drop(name);
+
+        self.state.snapshot(&self.guard)
+    }
+}
+
+impl<T: HasName> Drop for EntryGuard<'_, T> {
+    fn drop(&mut self) {
+        let snapshot = if self.dirty {
+            self.record_write()
+        } else {
+            None
+        };
+        // SAFETY: `guard` is dropped exactly once, here, and not used after.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+
+        if let Some(value) = snapshot {
+            self.state.notify(&value);
+        }
+    }
+}
+
+/// An `EntryGuard` that keeps its entry alive, returned by `lock_owned`.
+pub struct OwnedEntryGuard<T: HasName + 'static> {
+    // borrows from `entry`, so it must stay the first field
+    guard: EntryGuard<'static, T>,
+    entry: Entry<T>,
+}
+
+impl<T: HasName + 'static> OwnedEntryGuard<T> {
+    pub fn entry(&self) -> &Entry<T> {
+        &self.entry
+    }
+}
+
+impl<T: HasName + 'static> Deref for OwnedEntryGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: HasName + 'static> DerefMut for OwnedEntryGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+/// An `EntryGuard` projected onto part of the value. It keeps the entry
+/// locked for as long as it lives.
+pub struct MappedEntryGuard<'a, T: HasName, U: ?Sized> {
+    // points into the value owned by the mutex `guard` keeps locked
+    value: NonNull<U>,
+    guard: EntryGuard<'a, T>,
+    // `NonNull` is covariant; mutable access must keep `U` invariant
+    _marker: PhantomData<&'a mut U>,
+}
+
+impl<'a, T: HasName, U: ?Sized> MappedEntryGuard<'a, T, U> {
+    pub fn map<V, F>(mut self, f: F) -> MappedEntryGuard<'a, T, V>
+    where
+        V: ?Sized,
+        F: FnOnce(&mut U) -> &mut V,
+    {
+        let value = NonNull::from(f(&mut self));
+        MappedEntryGuard {
+            value,
+            guard: self.guard,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: HasName, U: ?Sized> Deref for MappedEntryGuard<'_, T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        // SAFETY: `value` was derived from the locked value, which outlives
+        // `guard`, and `&self` prevents any aliasing `&mut`.
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<T: HasName, U: ?Sized> DerefMut for MappedEntryGuard<'_, T, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        // SAFETY: as in `deref`; `&mut self` guarantees exclusivity.
+        unsafe { self.value.as_mut() }
+    }
+}
+
+pub trait HasName {
+    fn name(&self) -> String;
+
+    /// The name without forcing an allocation; the registry uses this on its
+    /// hot paths. The default falls back to `name`, so override it when the
+    /// name is stored as a field:
+    ///
+    /// ```
+    /// # use std::borrow::Cow;
+    /// # use core::HasName;
+    /// struct Service {
+    ///     name: String,
+    /// }
+    ///
+    /// impl HasName for Service {
+    ///     fn name(&self) -> String {
+    ///         self.name.clone()
+    ///     }
+    ///
+    ///     fn name_ref(&self) -> Cow<'_, str> {
+    ///         Cow::Borrowed(&self.name)
+    ///     }
+    /// }
+    /// ```
+    fn name_ref(&self) -> Cow<'_, str> {
+        Cow::Owned(self.name())
+    }
+}
+
+/// The key a value is registered under in a `NamedRegistry<T, K>`. Every
+/// `HasName` type is keyed by its name; implement this for other key types,
+/// such as numeric ids or `(tenant, name)` tuples.
+pub trait HasKey<K: Eq + Hash + Clone> {
+    fn key(&self) -> K;
+
+    /// The key for this value when its name is already allocated as `name`.
+    /// Defaults to `key`; keys that can share the name's allocation override
+    /// it.
+    fn key_from_name(&self, name: &Arc<str>) -> K {
+        let _ = name;
+        self.key()
+    }
+}
+
+impl<T: HasName + ?Sized> HasKey<String> for T {
+    fn key(&self) -> String {
+        self.name()
+    }
+}
+
+/// The key of the default `NamedRegistry<T>`.
+impl<T: HasName + ?Sized> HasKey<Arc<str>> for T {
+    fn key(&self) -> Arc<str> {
+        Arc::from(&*self.name_ref())
+    }
+
+    fn key_from_name(&self, name: &Arc<str>) -> Arc<str> {
+        Arc::clone(name)
+    }
+}
+
+pub trait SetName {
+    fn set_name(&mut self, name: &str);
+}
+
+impl<T: HasName + ?Sized> HasName for &T {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn name_ref(&self) -> Cow<'_, str> {
+        (**self).name_ref()
+    }
+}
+
+/// Lets trait objects such as `Box<dyn Plugin>` be registered when the trait
+/// has `HasName` as a supertrait.
+impl<T: HasName + ?Sized> HasName for Box<T> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn name_ref(&self) -> Cow<'_, str> {
+        (**self).name_ref()
+    }
+}
+
+impl<T: HasName + ?Sized> HasName for Arc<T> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn name_ref(&self) -> Cow<'_, str> {
+        (**self).name_ref()
+    }
+}
+
+impl<T: HasName + ?Sized> HasName for Rc<T> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn name_ref(&self) -> Cow<'_, str> {
+        (**self).name_ref()
+    }
+}
+
+/// Pairs an arbitrary value with a name, which is the first element.
+impl<T> HasName for (String, T) {
+    fn name(&self) -> String {
+        self.0.clone()
+    }
+
+    fn name_ref(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.0)
+    }
+}
+
+/// Served from the entry's cached name, so it never blocks on the value and is
+/// safe to call while the same thread holds the entry's lock.
+impl<T> HasName for Entry<T>
+where
+    T: HasName,
+{
+    fn name(&self) -> String {
+        self.cached_name().to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::thread;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    #[rstest]
+    fn test_new_creates_entry() {
+        let data = InnerMock {
+            name: "alpha".into(),
+            value: 10,
+        };
+        let entry = Entry::new(data.clone());
+
+        let locked = entry.lock();
+        assert_eq!(*locked, data);
+    }
+
+    #[rstest]
+    fn test_mutate() {
+        let entry = Entry::new(InnerMock {
+            name: "bar".into(),
+            value: 5,
+        });
+
+        entry.mutate(|v| v.value += 10);
+
+        let locked = entry.lock();
+        assert_eq!(locked.value, 15);
+    }
+
+    #[rstest]
+    fn test_multiple_mutations() {
+        let entry = Entry::new(InnerMock {
+            name: "baz".into(),
+            value: 0,
+        });
+
+        entry.mutate(|v| v.value += 1);
+        entry.mutate(|v| v.value += 2);
+        entry.mutate(|v| v.value += 3);
+
+        assert_eq!(entry.lock().value, 6);
+    }
+
+    #[rstest]
+    fn test_mutate_returns_value() {
+        let entry = Entry::new(InnerMock {
+            name: "baz".into(),
+            value: 2,
+        });
+
+        let doubled = entry.mutate(|v| {
+            v.value *= 2;
+            v.value
+        });
+
+        assert_eq!(doubled, 4);
+        assert_eq!(entry.lock().value, 4);
+    }
+
+    #[rstest]
+    fn test_name_while_locked() {
+        let entry = Entry::new(InnerMock {
+            name: "iota".into(),
+            value: 0,
+        });
+
+        let guard = entry.lock();
+        assert_eq!(entry.name(), "iota");
+        drop(guard);
+    }
+
+    #[rstest]
+    fn test_name_follows_mutation() {
+        let entry = Entry::new(InnerMock {
+            name: "iota".into(),
+            value: 0,
+        });
+        let clone = entry.clone();
+
+        entry.mutate(|v| v.name = "kappa".into());
+        assert_eq!(clone.name(), "kappa");
+
+        entry.lock().name = "lambda".into();
+        assert_eq!(clone.name(), "lambda");
+    }
+
+    #[rstest]
+    fn test_name_follows_update() {
+        let entry = Entry::new(InnerMock {
+            name: "iota".into(),
+            value: 0,
+        });
+
+        entry.update(&mut InnerMock {
+            name: "mu".into(),
+            value: 1,
+        });
+
+        assert_eq!(entry.name(), "mu");
+    }
+
+    #[rstest]
+    #[cfg(not(feature = "parking_lot"))]
+    fn test_poisoned_entry_recovers() {
+        use std::thread;
+
+        let entry = Entry::new(InnerMock {
+            name: "nu".into(),
+            value: 1,
+        });
+
+        let clone = entry.clone();
+        let result = thread::spawn(move || {
+            clone.mutate(|v| {
+                v.value = 2;
+                panic!("closure failure");
+            })
+        })
+        .join();
+
+        assert!(result.is_err());
+        assert!(entry.is_poisoned());
+        assert_eq!(entry.try_access().err(), Some(PoisonedEntry("nu".into())));
+        entry.mutate(|v| v.value += 1);
+        assert_eq!(entry.lock().value, 3);
+    }
+
+    #[rstest]
+    fn test_try_access_healthy_entry() {
+        let entry = Entry::new(InnerMock {
+            name: "nu".into(),
+            value: 1,
+        });
+
+        assert!(!entry.is_poisoned());
+        assert_eq!(entry.try_access().unwrap().value, 1);
+    }
+
+    #[rstest]
+    fn test_read_is_detached() {
+        let entry = Entry::new(InnerMock {
+            name: "xi".into(),
+            value: 1,
+        });
+
+        let snapshot = entry.read();
+        entry.mutate(|v| v.value = 2);
+
+        assert_eq!(snapshot.value, 1);
+        assert_eq!(entry.read().value, 2);
+    }
+
+    #[rstest]
+    fn test_with_returns_derived_value() {
+        let entry = Entry::new(InnerMock {
+            name: "omicron".into(),
+            value: 21,
+        });
+
+        let doubled = entry.with(|v| v.value * 2);
+
+        assert_eq!(doubled, 42);
+        assert_eq!(entry.lock().value, 21);
+    }
+
+    #[rstest]
+    fn test_replace_returns_old_value() {
+        let entry = Entry::new(InnerMock {
+            name: "pi".into(),
+            value: 1,
+        });
+        let clone = entry.clone();
+
+        let old = entry.replace(InnerMock {
+            name: "pi".into(),
+            value: 2,
+        });
+
+        assert_eq!(old.value, 1);
+        assert_eq!(clone.lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_set_overwrites() {
+        let entry = Entry::new(InnerMock {
+            name: "pi".into(),
+            value: 1,
+        });
+        let clone = entry.clone();
+
+        entry.set(InnerMock {
+            name: "rho".into(),
+            value: 3,
+        });
+
+        assert_eq!(clone.lock().value, 3);
+        assert_eq!(clone.name(), "rho");
+    }
+
+    #[rstest]
+    fn test_try_unwrap_sole_owner() {
+        let entry = Entry::new(InnerMock {
+            name: "sigma".into(),
+            value: 1,
+        });
+        let weak = entry.weak();
+
+        assert_eq!(entry.try_unwrap().unwrap().value, 1);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[rstest]
+    fn test_try_unwrap_shared_owner() {
+        let entry = Entry::new(InnerMock {
+            name: "sigma".into(),
+            value: 1,
+        });
+        let clone = entry.clone();
+
+        let entry = entry.try_unwrap().unwrap_err();
+        entry.mutate(|v| v.value = 2);
+
+        assert_eq!(clone.lock().value, 2);
+        drop(entry);
+        assert_eq!(clone.try_unwrap().unwrap().value, 2);
+    }
+
+    #[rstest]
+    #[cfg(not(feature = "parking_lot"))]
+    fn test_try_unwrap_poisoned() {
+        use std::thread;
+
+        let entry = Entry::new(InnerMock {
+            name: "sigma".into(),
+            value: 1,
+        });
+        let clone = entry.clone();
+        let _ = thread::spawn(move || {
+            clone.mutate(|v| {
+                v.value = 5;
+                panic!("poison");
+            })
+        })
+        .join();
+
+        assert!(entry.is_poisoned());
+        assert_eq!(entry.try_unwrap().unwrap().value, 5);
+    }
+
+    #[rstest]
+    fn test_into_inner() {
+        let entry = Entry::new(InnerMock {
+            name: "sigma".into(),
+            value: 1,
+        });
+        let clone = entry.clone();
+
+        assert!(entry.into_inner().is_none());
+        assert_eq!(clone.into_inner().unwrap().value, 1);
+    }
+
+    #[rstest]
+    fn test_try_lock_does_not_block() {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let entry = Entry::new(InnerMock {
+            name: "tau".into(),
+            value: 1,
+        });
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+
+        let holder = {
+            let entry = entry.clone();
+            thread::spawn(move || {
+                let _guard = entry.lock();
+                locked_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+            })
+        };
+
+        locked_rx.recv().unwrap();
+        assert!(entry.try_lock().is_none());
+        assert!(entry.try_mutate(|v| v.value += 1).is_none());
+
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+
+        assert_eq!(entry.try_mutate(|v| v.value + 1), Some(2));
+        assert_eq!(entry.try_lock().unwrap().value, 1);
+    }
+
+    #[rstest]
+    #[case(Duration::from_millis(200), Duration::from_millis(20), false)]
+    #[case(Duration::from_millis(5), Duration::from_millis(500), true)]
+    fn test_lock_timeout(
+        #[case] hold: Duration,
+        #[case] timeout: Duration,
+        #[case] acquired: bool,
+    ) {
+        use std::sync::mpsc;
+
+        let entry = Entry::new(InnerMock {
+            name: "upsilon".into(),
+            value: 1,
+        });
+        let (locked_tx, locked_rx) = mpsc::channel();
+
+        let holder = {
+            let entry = entry.clone();
+            thread::spawn(move || {
+                let _guard = entry.lock();
+                locked_tx.send(()).unwrap();
+                thread::sleep(hold);
+            })
+        };
+
+        locked_rx.recv().unwrap();
+        let result = entry.lock_timeout(timeout).map(|guard| guard.value);
+        holder.join().unwrap();
+
+        if acquired {
+            assert_eq!(result, Ok(1));
+        } else {
+            let err = result.unwrap_err();
+            assert_eq!(err.name, "upsilon");
+            assert!(err.waited >= timeout);
+        }
+    }
+
+    #[rstest]
+    fn test_map_guard_projects_field() {
+        let entry = Entry::new(InnerMock {
+            name: "phi".into(),
+            value: 1,
+        });
+
+        {
+            let mut value = entry.map_guard(|v| &mut v.value);
+            *value += 41;
+            assert!(entry.try_lock().is_none());
+        }
+
+        assert!(entry.try_lock().is_some());
+        assert_eq!(entry.lock().value, 42);
+    }
+
+    #[rstest]
+    fn test_map_guard_nested_projection() {
+        #[derive(Debug)]
+        struct Outer {
+            name: String,
+            inner: (i32, Vec<i32>),
+        }
+
+        impl HasName for Outer {
+            fn name(&self) -> String {
+                self.name.clone()
+            }
+        }
+
+        let entry = Entry::new(Outer {
+            name: "chi".into(),
+            inner: (0, vec![1, 2]),
+        });
+
+        let mut items = entry.map_guard(|v| &mut v.inner).map(|inner| &mut inner.1);
+        items.push(3);
+        let mut slice = items.map(|items| items.as_mut_slice());
+        slice[0] = 10;
+        drop(slice);
+
+        assert_eq!(entry.lock().inner.1, vec![10, 2, 3]);
+    }
+
+    #[rstest]
+    fn test_map_guard_renames_refresh_cache() {
+        let entry = Entry::new(InnerMock {
+            name: "psi".into(),
+            value: 0,
+        });
+
+        *entry.map_guard(|v| &mut v.name) = "omega".into();
+
+        assert_eq!(entry.name(), "omega");
+    }
+
+    #[rstest]
+    fn test_version_counts_mutations() {
+        let entry = Entry::new(InnerMock {
+            name: "alpha".into(),
+            value: 0,
+        });
+        assert_eq!(entry.version(), 0);
+
+        entry.mutate(|v| v.value += 1);
+        assert_eq!(entry.version(), 1);
+
+        entry.update(&mut InnerMock {
+            name: "alpha".into(),
+            value: 2,
+        });
+        entry.set(InnerMock {
+            name: "alpha".into(),
+            value: 3,
+        });
+        entry.replace(InnerMock {
+            name: "alpha".into(),
+            value: 4,
+        });
+        entry.lock().value = 5;
+        assert_eq!(entry.version(), 5);
+    }
+
+    #[rstest]
+    fn test_version_ignores_reads() {
+        let entry = Entry::new(InnerMock {
+            name: "alpha".into(),
+            value: 0,
+        });
+
+        let _ = entry.lock().value;
+        entry.with(|v| v.value);
+        let _ = entry.try_lock();
+
+        assert_eq!(entry.version(), 0);
+    }
+
+    #[rstest]
+    fn test_version_shared_by_clones() {
+        let entry = Entry::new(InnerMock {
+            name: "alpha".into(),
+            value: 0,
+        });
+        let clone = entry.clone();
+
+        clone.mutate(|v| v.value += 1);
+
+        assert_eq!(entry.version(), 1);
+    }
+
+    #[derive(Debug)]
+    struct MockClock(Mutex<SystemTime>);
+
+    impl MockClock {
+        fn at(secs: u64) -> Arc<Self> {
+            Arc::new(Self(Mutex::new(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            )))
+        }
+
+        fn advance(&self, secs: u64) {
+            *self.0.acquire() += Duration::from_secs(secs);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> SystemTime {
+            *self.0.acquire()
+        }
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[rstest]
+    fn test_timestamps_follow_clock() {
+        let clock = MockClock::at(100);
+        let entry = Entry::with_clock(
+            InnerMock {
+                name: "alpha".into(),
+                value: 0,
+            },
+            clock.clone(),
+        );
+        assert_eq!(entry.created_at(), at(100));
+        assert_eq!(entry.last_modified(), at(100));
+
+        clock.advance(5);
+        entry.mutate(|v| v.value += 1);
+        assert_eq!(entry.created_at(), at(100));
+        assert_eq!(entry.last_modified(), at(105));
+
+        clock.advance(5);
+        entry.replace(InnerMock {
+            name: "alpha".into(),
+            value: 2,
+        });
+        assert_eq!(entry.last_modified(), at(110));
+    }
+
+    #[rstest]
+    fn test_timestamps_ignore_reads() {
+        let clock = MockClock::at(100);
+        let entry = Entry::with_clock(
+            InnerMock {
+                name: "alpha".into(),
+                value: 0,
+            },
+            clock.clone(),
+        );
+
+        clock.advance(60);
+        entry.with(|v| v.value);
+        let _ = entry.lock().value;
+
+        assert_eq!(entry.last_modified(), at(100));
+    }
+
+    #[rstest]
+    fn test_meta_survives_replace() {
+        let entry = Entry::new(InnerMock {
+            name: "alpha".into(),
+            value: 0,
+        });
+        assert_eq!(entry.set_meta("owner", "ops".into()), None);
+        assert_eq!(entry.set_meta("owner", "infra".into()), Some("ops".into()));
+        entry.set_meta("env", "prod".into());
+
+        entry.replace(InnerMock {
+            name: "alpha".into(),
+            value: 1,
+        });
+
+        assert_eq!(entry.meta("owner").as_deref(), Some("infra"));
+        assert_eq!(
+            entry.tags(),
+            vec![
+                ("env".to_string(), "prod".to_string()),
+                ("owner".to_string(), "infra".to_string()),
+            ]
+        );
+        assert_eq!(entry.remove_meta("env").as_deref(), Some("prod"));
+        assert_eq!(entry.meta("env"), None);
+    }
+
+    #[rstest]
+    fn test_meta_while_locked() {
+        let entry = Entry::new(InnerMock {
+            name: "alpha".into(),
+            value: 0,
+        });
+
+        let guard = entry.lock();
+        entry.set_meta("owner", "ops".into());
+        assert_eq!(entry.meta("owner").as_deref(), Some("ops"));
+        drop(guard);
+    }
+
+    #[rstest]
+    fn test_concurrent_meta_writes() {
+        use std::thread;
+
+        let entry = Entry::new(InnerMock {
             name: "alpha".into(),
-            value: 10,
-        };
-        let entry = Entry::new(data.clone());
+            value: 0,
+        });
 
-        let locked = entry.lock();
-        assert_eq!(*locked, data);
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let entry = entry.clone();
+                thread::spawn(move || {
+                    for j in 0..50 {
+                        entry.set_meta(&format!("t{i}-{j}"), j.to_string());
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(entry.tags().len(), 8 * 50);
+        assert_eq!(entry.meta("t3-49").as_deref(), Some("49"));
+        assert_eq!(entry.version(), 0);
     }
 
     #[rstest]
-    fn test_mutate() {
+    fn test_compare_and_mutate() {
         let entry = Entry::new(InnerMock {
-            name: "bar".into(),
-            value: 5,
+            name: "alpha".into(),
+            value: 0,
         });
 
-        entry.mutate(|v| v.value += 10);
-
-        let locked = entry.lock();
-        assert_eq!(locked.value, 15);
+        assert_eq!(entry.compare_and_mutate(0, |v| v.value = 1), Ok(1));
+        assert_eq!(
+            entry.compare_and_mutate(0, |v| v.value = 2),
+            Err(CasError::Stale { current: 1 })
+        );
+        assert_eq!(entry.lock().value, 1);
+        assert_eq!(entry.version(), 1);
     }
 
     #[rstest]
-    fn test_multiple_mutations() {
+    fn test_compare_and_mutate_one_winner_per_round() {
+        use std::sync::Barrier;
+        use std::thread;
+
         let entry = Entry::new(InnerMock {
-            name: "baz".into(),
+            name: "alpha".into(),
             value: 0,
         });
 
-        entry.mutate(|v| v.value += 1);
-        entry.mutate(|v| v.value += 2);
-        entry.mutate(|v| v.value += 3);
+        for round in 0..50 {
+            let barrier = Arc::new(Barrier::new(2));
+            let expected = entry.version();
+            let handles: Vec<_> = (0..2)
+                .map(|_| {
+                    let entry = entry.clone();
+                    let barrier = Arc::clone(&barrier);
+                    thread::spawn(move || {
+                        barrier.wait();
+                        entry.compare_and_mutate(expected, |v| v.value += 1)
+                    })
+                })
+                .collect();
+            let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
 
-        assert_eq!(entry.lock().value, 6);
+            assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+            assert!(results.contains(&Err(CasError::Stale {
+                current: expected + 1
+            })));
+            assert_eq!(entry.lock().value, round + 1);
+        }
     }
 
     #[rstest]
@@ -149,9 +1718,9 @@ mod test {
         let arc2 = entry.arc();
 
         // arcs should point to same obj
-        assert!(Arc::ptr_eq(&arc1, &arc2));
+        assert!(arc1.ptr_eq(&arc2));
 
-        let locked = arc1.lock().unwrap();
+        let locked = arc1.lock();
         assert_eq!(locked.value, 7);
     }
 
@@ -172,6 +1741,422 @@ mod test {
         assert!(weak_ref.upgrade().is_none());
     }
 
+    #[rstest]
+    fn test_ptr_eq() {
+        let value = InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        };
+        let entry = Entry::new(value.clone());
+        let twin = Entry::new(value);
+
+        assert!(entry.ptr_eq(&entry.clone()));
+        assert!(entry.ptr_eq(&entry.weak().upgrade().unwrap()));
+        assert!(!entry.ptr_eq(&twin));
+    }
+
+    #[rstest]
+    fn test_eq_compares_values() {
+        let value = InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        };
+        let entry = Entry::new(value.clone());
+        let twin = Entry::new(value);
+
+        assert_eq!(entry, entry);
+        assert_eq!(entry, twin);
+        assert_eq!(twin, entry);
+
+        twin.mutate(|v| v.value = 2);
+        assert_ne!(entry, twin);
+    }
+
+    #[rstest]
+    fn test_eq_with_itself_while_held_elsewhere() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+
+        let guard = entry.lock();
+        assert!(entry == entry.clone());
+        drop(guard);
+    }
+
+    #[rstest]
+    fn test_debug_shows_value() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+
+        assert_eq!(
+            format!("{entry:?}"),
+            r#"Entry("zeta", InnerMock { name: "zeta", value: 1 })"#
+        );
+    }
+
+    /// Locks `entry` on another thread until the returned sender fires.
+    fn hold_lock<T>(entry: &Entry<T>) -> (thread::JoinHandle<()>, std::sync::mpsc::Sender<()>)
+    where
+        T: HasName + Send + 'static,
+    {
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel();
+        let entry = entry.clone();
+        let holder = thread::spawn(move || {
+            let _guard = entry.lock();
+            locked_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+        });
+        locked_rx.recv().unwrap();
+        (holder, release_tx)
+    }
+
+    #[rstest]
+    fn test_debug_while_locked_elsewhere() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+
+        let (holder, release) = hold_lock(&entry);
+        assert_eq!(format!("{entry:?}"), r#"Entry("zeta", <locked>)"#);
+        release.send(()).unwrap();
+        holder.join().unwrap();
+    }
+
+    #[rstest]
+    fn test_display() {
+        struct Shown(&'static str);
+
+        impl HasName for Shown {
+            fn name(&self) -> String {
+                self.0.to_string()
+            }
+        }
+
+        impl fmt::Display for Shown {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "shown {}", self.0)
+            }
+        }
+
+        let entry = Entry::new(Shown("zeta"));
+        assert_eq!(entry.to_string(), "shown zeta");
+
+        let (holder, release) = hold_lock(&entry);
+        assert_eq!(entry.to_string(), "<locked>");
+        release.send(()).unwrap();
+        holder.join().unwrap();
+    }
+
+    #[rstest]
+    fn test_frozen_rejects_writes() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+        let token = entry.freeze();
+        assert!(entry.is_frozen());
+
+        let frozen = Err(Frozen("zeta".into()));
+        assert_eq!(entry.checked_mutate(|v| v.value = 2), frozen);
+        assert_eq!(
+            entry.checked_update(&mut InnerMock {
+                name: "zeta".into(),
+                value: 3,
+            }),
+            frozen
+        );
+        assert_eq!(
+            entry.compare_and_mutate(0, |v| v.value = 4),
+            Err(CasError::Frozen("zeta".into()))
+        );
+        assert_eq!(entry.version(), 0);
+        assert!(!entry.is_poisoned());
+
+        entry.unfreeze(&token);
+        entry.mutate(|v| v.value = 5);
+        assert_eq!(entry.lock().value, 5);
+    }
+
+    #[rstest]
+    fn test_frozen_reads() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+        let _token = entry.freeze();
+
+        assert_eq!(entry.with(|v| v.value), 1);
+        assert_eq!(entry.read().value, 1);
+        assert_eq!(entry.lock().value, 1);
+        assert_eq!(entry.name(), "zeta");
+    }
+
+    #[rstest]
+    #[should_panic(expected = "entry `zeta` is frozen")]
+    fn test_frozen_mutate_panics() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+        let _token = entry.freeze();
+
+        entry.mutate(|v| v.value = 2);
+    }
+
+    #[rstest]
+    fn test_on_change_order() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 0,
+        });
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        for tag in ["first", "second"] {
+            let seen = Arc::clone(&seen);
+            entry.on_change(move |v| seen.acquire().push((tag, v.value)));
+        }
+        entry.mutate(|v| v.value = 1);
+        entry.set(InnerMock {
+            name: "zeta".into(),
+            value: 2,
+        });
+
+        assert_eq!(
+            *seen.acquire(),
+            vec![("first", 1), ("second", 1), ("first", 2), ("second", 2)]
+        );
+    }
+
+    #[rstest]
+    fn test_on_change_skips_reads() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 0,
+        });
+        let calls = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&calls);
+        entry.on_change(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        entry.with(|v| v.value);
+        entry.read();
+        let _ = entry.lock().value;
+
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+    }
+
+    #[rstest]
+    fn test_unsubscribe() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 0,
+        });
+        let calls = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&calls);
+        let id = entry.on_change(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        entry.mutate(|v| v.value = 1);
+        assert!(entry.unsubscribe(id));
+        assert!(!entry.unsubscribe(id));
+        entry.mutate(|v| v.value = 2);
+
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[rstest]
+    fn test_on_change_reads_entry() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 0,
+        });
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let (handle, sink) = (entry.clone(), Arc::clone(&seen));
+        entry.on_change(move |v| sink.acquire().push((v.value, handle.read().value)));
+
+        entry.mutate(|v| v.value = 7);
+
+        assert_eq!(*seen.acquire(), vec![(7, 7)]);
+    }
+
+    #[rstest]
+    fn test_lock_owned_outlives_handle() {
+        struct Holder {
+            guard: OwnedEntryGuard<InnerMock>,
+        }
+
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+        let mut holder = Holder {
+            guard: entry.clone().lock_owned(),
+        };
+        assert!(entry.try_lock().is_none());
+
+        holder.guard.value = 2;
+        assert!(holder.guard.entry().ptr_eq(&entry));
+        drop(holder);
+
+        assert_eq!(entry.lock().value, 2);
+        assert_eq!(entry.version(), 1);
+    }
+
+    #[rstest]
+    fn test_lock_owned_last_handle() {
+        let guard = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        })
+        .lock_owned();
+
+        assert_eq!(guard.value, 1);
+        assert_eq!(guard.entry().name(), "zeta");
+    }
+
+    #[rstest]
+    fn test_scope_owned_across_threads() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+
+        let job = entry.clone().scope_owned(|v| {
+            v.value += 1;
+            v.value
+        });
+        let result = thread::spawn(job).join().unwrap();
+
+        assert_eq!(result, 2);
+        assert_eq!(entry.lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_default() {
+        #[derive(Default)]
+        struct Named(String);
+
+        impl HasName for Named {
+            fn name(&self) -> String {
+                self.0.clone()
+            }
+        }
+
+        let entry: Entry<Named> = Entry::default();
+        assert_eq!(entry.name(), "");
+    }
+
+    #[rstest]
+    fn test_from_value() {
+        let entry: Entry<_> = InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        }
+        .into();
+
+        assert_eq!(entry.name(), "zeta");
+        assert_eq!(entry.lock().value, 1);
+    }
+
+    #[rstest]
+    fn test_from_shared_arc() {
+        let shared = SharedValue::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+        let other = shared.clone();
+
+        let entry = Entry::from(shared);
+        assert_eq!(entry.name(), "zeta");
+
+        entry.mutate(|v| v.value = 2);
+        assert_eq!(other.lock().value, 2);
+        other.lock().value = 3;
+        assert_eq!(entry.lock().value, 3);
+        assert!(entry.arc().ptr_eq(&other));
+    }
+
+    #[cfg(not(feature = "parking_lot"))]
+    #[rstest]
+    fn test_from_std_arc() {
+        let arc = Arc::new(std::sync::Mutex::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        }));
+        let other = Arc::clone(&arc);
+
+        let entry = Entry::from(arc);
+        assert_eq!(entry.name(), "zeta");
+
+        entry.mutate(|v| v.value = 2);
+        assert_eq!(other.lock().unwrap().value, 2);
+        other.lock().unwrap().value = 3;
+        assert_eq!(entry.lock().value, 3);
+    }
+
+    #[rstest]
+    fn test_name_ref_default_allocates() {
+        let value = InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        };
+
+        assert!(matches!(value.name_ref(), Cow::Owned(name) if name == "zeta"));
+    }
+
+    #[rstest]
+    fn test_name_ref_borrowed() {
+        struct Borrowing {
+            name: String,
+        }
+
+        impl HasName for Borrowing {
+            fn name(&self) -> String {
+                self.name.clone()
+            }
+
+            fn name_ref(&self) -> Cow<'_, str> {
+                Cow::Borrowed(&self.name)
+            }
+        }
+
+        let entry = Entry::new(Borrowing {
+            name: "zeta".into(),
+        });
+        assert!(matches!(entry.lock().name_ref(), Cow::Borrowed("zeta")));
+
+        entry.mutate(|v| v.name = "eta".into());
+        assert_eq!(entry.name(), "eta");
+        assert!(entry.name_is("eta"));
+    }
+
+    #[rstest]
+    fn test_weak_entry_counts() {
+        let entry = Entry::new(InnerMock {
+            name: "zeta".into(),
+            value: 1,
+        });
+        let clone = entry.clone();
+        let weak = entry.weak();
+        assert_eq!(weak.strong_count(), 2);
+
+        drop(entry);
+        assert!(weak.is_alive());
+        weak.upgrade().unwrap().mutate(|v| v.value += 1);
+        assert_eq!(clone.version(), 1);
+
+        drop(clone);
+        assert!(!weak.is_alive());
+        assert!(weak.into_raw().upgrade().is_none());
+    }
+
     #[rstest]
     fn test_concurrent_mutation() {
         use std::thread;
@@ -210,4 +2195,48 @@ mod test {
 
         assert_eq!(entry.lock().value, 100);
     }
+
+    #[rstest]
+    fn test_wrappers_forward_name() {
+        let mock = InnerMock {
+            name: "alpha".into(),
+            value: 1,
+        };
+
+        assert_eq!(HasName::name(&&mock), "alpha");
+        assert_eq!(Box::new(mock.clone()).name(), "alpha");
+        assert_eq!(Arc::new(mock.clone()).name_ref(), "alpha");
+        assert_eq!(Rc::new(mock).name(), "alpha");
+    }
+
+    #[rstest]
+    fn test_tuple_is_named_by_first_element() {
+        let entry = Entry::new(("alpha".to_string(), 5));
+
+        entry.mutate(|(_, value)| *value += 1);
+
+        assert_eq!(entry.name(), "alpha");
+        assert!(matches!(
+            ("beta".to_string(), ()).name_ref(),
+            Cow::Borrowed("beta")
+        ));
+        assert_eq!(entry.read(), ("alpha".to_string(), 6));
+    }
+
+    #[rstest]
+    fn test_handle_counts() {
+        let entry = Entry::new(InnerMock {
+            name: "alpha".into(),
+            value: 1,
+        });
+        assert_eq!((entry.strong_count(), entry.weak_count()), (1, 0));
+
+        let clone = entry.clone();
+        let arc = entry.arc();
+        let weak = entry.weak();
+        assert_eq!((entry.strong_count(), entry.weak_count()), (3, 1));
+
+        drop((clone, arc, weak));
+        assert_eq!((entry.strong_count(), entry.weak_count()), (1, 0));
+    }
 }
@@ -0,0 +1,492 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use crate::entry::{Entry, HasName};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TakeError {
+    NotFound(String),
+    Shared(String),
+    Sealed,
+}
+
+impl fmt::Display for TakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "no entry named `{name}`"),
+            Self::Shared(name) => write!(f, "entry `{name}` is still shared"),
+            Self::Sealed => Sealed.fmt(f),
+        }
+    }
+}
+
+impl Error for TakeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertError {
+    InvalidName {
+        name: String,
+        reason: String,
+    },
+    /// A bounded registry with `EvictionPolicy::RejectNew` is at capacity.
+    Full {
+        name: String,
+        capacity: usize,
+    },
+    Sealed,
+    /// A factory asked for `name` built a value keyed elsewhere, `found`.
+    Mismatch {
+        name: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName { name, reason } => write!(f, "invalid name `{name}`: {reason}"),
+            Self::Full { name, capacity } => {
+                write!(
+                    f,
+                    "cannot insert `{name}`: registry is full ({capacity} entries)"
+                )
+            }
+            Self::Sealed => Sealed.fmt(f),
+            Self::Mismatch { name, found } => {
+                write!(f, "cannot insert `{found}` under `{name}`")
+            }
+        }
+    }
+}
+
+impl Error for InsertError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameError {
+    NotFound(String),
+    Occupied(String),
+    Rejected(InsertError),
+    Sealed,
+    Frozen(String),
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "no entry named `{name}`"),
+            Self::Occupied(name) => write!(f, "an entry named `{name}` already exists"),
+            Self::Rejected(err) => err.fmt(f),
+            Self::Sealed => Sealed.fmt(f),
+            Self::Frozen(name) => write!(f, "entry `{name}` is frozen"),
+        }
+    }
+}
+
+impl Error for RenameError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MutateError {
+    NotFound(String),
+    Renamed { from: String, to: String },
+    Frozen(String),
+}
+
+impl fmt::Display for MutateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "no entry named `{name}`"),
+            Self::Renamed { from, to } => {
+                write!(
+                    f,
+                    "mutation renamed `{from}` to `{to}`, which is not allowed"
+                )
+            }
+            Self::Frozen(name) => write!(f, "entry `{name}` is frozen"),
+        }
+    }
+}
+
+impl Error for MutateError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CasError {
+    NotFound(String),
+    /// The entry was written since the expected version was read.
+    Stale {
+        current: u64,
+    },
+    Frozen(String),
+}
+
+impl fmt::Display for CasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "no entry named `{name}`"),
+            Self::Stale { current } => write!(f, "entry is at version {current}"),
+            Self::Frozen(name) => write!(f, "entry `{name}` is frozen"),
+        }
+    }
+}
+
+impl Error for CasError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frozen(pub String);
+
+impl fmt::Display for Frozen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "entry `{}` is frozen", self.0)
+    }
+}
+
+impl Error for Frozen {}
+
+/// Returned by writes to a registry after `NamedRegistry::seal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sealed;
+
+impl fmt::Display for Sealed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the registry is sealed")
+    }
+}
+
+impl Error for Sealed {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotFound(pub String);
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no entry named `{}`", self.0)
+    }
+}
+
+impl Error for NotFound {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateName(pub String);
+
+impl fmt::Display for DuplicateName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate entry name `{}`", self.0)
+    }
+}
+
+impl Error for DuplicateName {}
+
+/// Why `insert_many` inserted nothing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertManyError {
+    Duplicate(DuplicateName),
+    Rejected(InsertError),
+}
+
+impl From<InsertError> for InsertManyError {
+    fn from(err: InsertError) -> Self {
+        Self::Rejected(err)
+    }
+}
+
+impl fmt::Display for InsertManyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Duplicate(err) => err.fmt(f),
+            Self::Rejected(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for InsertManyError {}
+
+#[derive(Debug)]
+pub struct OccupiedError<T> {
+    pub entry: Entry<T>,
+    pub value: T,
+}
+
+impl<T> fmt::Display for OccupiedError<T>
+where
+    T: HasName,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an entry named `{}` already exists", self.value.name())
+    }
+}
+
+impl<T> Error for OccupiedError<T> where T: HasName + fmt::Debug {}
+
+/// Why `replace` handed its value back.
+#[derive(Debug)]
+pub enum ReplaceError<T> {
+    /// The value's name keys a different entry than the one being replaced.
+    Mismatch { name: String, value: T },
+    /// Nothing is registered under the name and the registry is sealed.
+    Sealed(T),
+    /// The registered entry is frozen.
+    Frozen(T),
+    /// Nothing is registered under the name and a full `RejectNew` registry
+    /// refused the value.
+    Full { capacity: usize, value: T },
+}
+
+impl<T> fmt::Display for ReplaceError<T>
+where
+    T: HasName,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mismatch { name, value } => {
+                write!(f, "cannot replace `{name}` with `{}`", value.name())
+            }
+            Self::Sealed(_) => Sealed.fmt(f),
+            Self::Frozen(value) => Frozen(value.name()).fmt(f),
+            Self::Full { capacity, value } => InsertError::Full {
+                name: value.name(),
+                capacity: *capacity,
+            }
+            .fmt(f),
+        }
+    }
+}
+
+impl<T> Error for ReplaceError<T> where T: HasName + fmt::Debug {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoisonedEntry(pub String);
+
+impl fmt::Display for PoisonedEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "entry `{}` is poisoned", self.0)
+    }
+}
+
+impl Error for PoisonedEntry {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoisonedRegistry;
+
+impl fmt::Display for PoisonedRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "registry map lock is poisoned")
+    }
+}
+
+impl Error for PoisonedRegistry {}
+
+#[derive(Debug)]
+pub enum TryInsertError<T> {
+    Occupied(OccupiedError<T>),
+    Rejected(InsertError),
+}
+
+impl<T> From<InsertError> for TryInsertError<T> {
+    fn from(err: InsertError) -> Self {
+        Self::Rejected(err)
+    }
+}
+
+impl<T> fmt::Display for TryInsertError<T>
+where
+    T: HasName,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Occupied(err) => err.fmt(f),
+            Self::Rejected(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<T> Error for TryInsertError<T> where T: HasName + fmt::Debug {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairError {
+    NotFound(String),
+    /// Both names resolve to one entry, which cannot be borrowed twice.
+    SameEntry(String),
+}
+
+impl fmt::Display for PairError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "no entry named `{name}`"),
+            Self::SameEntry(name) => write!(f, "both sides of the pair are `{name}`"),
+        }
+    }
+}
+
+impl Error for PairError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertIdError {
+    DuplicateId(u64),
+    Rejected(InsertError),
+}
+
+impl From<InsertError> for InsertIdError {
+    fn from(err: InsertError) -> Self {
+        Self::Rejected(err)
+    }
+}
+
+impl fmt::Display for InsertIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateId(id) => write!(f, "id {id} is already in use"),
+            Self::Rejected(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for InsertIdError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockTimeout {
+    pub name: String,
+    pub waited: Duration,
+}
+
+impl fmt::Display for LockTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "timed out after {:?} waiting for entry `{}`",
+            self.waited, self.name
+        )
+    }
+}
+
+impl Error for LockTimeout {}
+
+/// Why `NamedRegistry::load_from` could not read a snapshot file.
+#[cfg(feature = "binary")]
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    /// The file does not start with the snapshot header.
+    NotASnapshot,
+    /// Written by a format version this build cannot read.
+    UnsupportedVersion(u8),
+    /// Truncated, altered, or holding values that do not decode.
+    Corrupt(String),
+}
+
+#[cfg(feature = "binary")]
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => err.fmt(f),
+            Self::NotASnapshot => f.write_str("not a registry snapshot"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot format version {version}")
+            }
+            Self::Corrupt(reason) => write!(f, "corrupt snapshot: {reason}"),
+        }
+    }
+}
+
+#[cfg(feature = "binary")]
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "binary")]
+impl From<std::io::Error> for SnapshotError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// A patch operation that no longer fits the registry it is applied to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchConflict {
+    /// An added name already holds a different value.
+    Exists(String),
+    /// A changed name is gone.
+    Missing(String),
+    /// The local value no longer matches the patch's base.
+    Diverged(String),
+}
+
+impl PatchConflict {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Exists(name) | Self::Missing(name) | Self::Diverged(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for PatchConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exists(name) => write!(f, "an entry named `{name}` already exists"),
+            Self::Missing(name) => write!(f, "no entry named `{name}`"),
+            Self::Diverged(name) => write!(f, "entry `{name}` changed since the patch was made"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// Raised under `ConflictPolicy::Fail`; nothing was applied.
+    Conflicts(Vec<PatchConflict>),
+    Sealed,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Conflicts(conflicts) => {
+                write!(f, "patch has {} conflicts", conflicts.len())?;
+                if let Some(first) = conflicts.first() {
+                    write!(f, ", the first: {first}")?;
+                }
+                Ok(())
+            }
+            Self::Sealed => Sealed.fmt(f),
+        }
+    }
+}
+
+impl Error for PatchError {}
+
+/// Why one definition file, or one document in it, was not loaded.
+#[cfg(any(feature = "toml", feature = "yaml"))]
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Parse(String),
+    /// The name is already registered, possibly by an earlier file.
+    Duplicate(String),
+    Rejected(InsertError),
+}
+
+#[cfg(any(feature = "toml", feature = "yaml"))]
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => err.fmt(f),
+            Self::Parse(reason) => write!(f, "invalid definition: {reason}"),
+            Self::Duplicate(name) => write!(f, "an entry named `{name}` already exists"),
+            Self::Rejected(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(any(feature = "toml", feature = "yaml"))]
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Rejected(err) => Some(err),
+            _ => None,
+        }
+    }
+}
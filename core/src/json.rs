@@ -0,0 +1,146 @@
+//! JSON helpers over the `serde` impls, enabled by the `json` feature.
+
+use std::hash::BuildHasher;
+use std::io;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::entry::HasName;
+use crate::registry::{MergePolicy, NamedRegistry};
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + Serialize,
+    S: BuildHasher + Default,
+{
+    pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    pub fn to_json_writer<W: io::Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer(writer, self)
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + DeserializeOwned,
+    S: BuildHasher + Default,
+{
+    /// Parses the map `to_json_string` writes. Errors name the offending key
+    /// where there is one.
+    pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + DeserializeOwned + Clone,
+    S: BuildHasher + Default,
+{
+    /// Merges the entries in `json` into this registry as `merge` would,
+    /// leaving entries it does not mention alone. Nothing is merged if `json`
+    /// fails to parse.
+    pub fn import_json(&self, json: &str, policy: MergePolicy<T>) -> Result<(), serde_json::Error> {
+        let incoming = Self::from_json_str(json)?;
+        self.merge(&incoming, policy);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registry::{ConflictPolicy, RegistryPatch};
+    use rstest::rstest;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn mock(name: &str, value: i32) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            value,
+        }
+    }
+
+    #[rstest]
+    fn test_json_round_trip() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+
+        let json = reg.to_json_string().unwrap();
+        let mut written = Vec::new();
+        reg.to_json_writer(&mut written).unwrap();
+        assert_eq!(written, json.as_bytes());
+
+        let back = NamedRegistry::<InnerMock>::from_json_str(&json).unwrap();
+        assert_eq!(back.with("alpha", |v| v.value), Some(1));
+    }
+
+    #[rstest]
+    #[case::keep_existing(MergePolicy::KeepExisting, 1)]
+    #[case::take_incoming(MergePolicy::TakeIncoming, 10)]
+    #[case::resolve(MergePolicy::Resolve(|a: &InnerMock, b| mock(&a.name, a.value + b.value)), 11)]
+    fn test_import_merges_into_live_registry(
+        #[case] policy: MergePolicy<InnerMock>,
+        #[case] alpha: i32,
+    ) {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+        let held = reg.get("alpha").unwrap();
+
+        reg.import_json(
+            r#"{"alpha":{"name":"alpha","value":10},"gamma":{"name":"gamma","value":3}}"#,
+            policy,
+        )
+        .unwrap();
+
+        assert_eq!(held.with(|v| v.value), alpha);
+        assert_eq!(reg.with("beta", |v| v.value), Some(2));
+        assert_eq!(reg.with("gamma", |v| v.value), Some(3));
+    }
+
+    #[rstest]
+    #[case::bad_value(r#"{"alpha":{"name":"alpha","value":"one"}}"#, "`alpha`: invalid type")]
+    #[case::truncated(r#"{"alpha":{"name":"alpha""#, "`alpha`: EOF while parsing")]
+    #[case::mismatch(r#"{"alpha":{"name":"beta","value":1}}"#, "key `alpha`")]
+    fn test_malformed_import_changes_nothing(#[case] json: &str, #[case] message: &str) {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+
+        let err = reg
+            .import_json(json, MergePolicy::TakeIncoming)
+            .unwrap_err();
+        assert!(err.to_string().starts_with(message), "{err}");
+        assert_eq!(reg.with("alpha", |v| v.value), Some(1));
+    }
+
+    #[rstest]
+    fn test_patch_round_trips() {
+        let base = NamedRegistry::from_entries(vec![mock("alpha", 1), mock("beta", 2)]);
+        let target = NamedRegistry::from_entries(vec![mock("alpha", 10), mock("gamma", 3)]);
+        let patch = base.diff_serializable(&target);
+
+        let json = serde_json::to_string(&patch).unwrap();
+        let decoded: RegistryPatch<InnerMock> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(decoded, patch);
+        base.apply_patch(decoded, ConflictPolicy::Fail).unwrap();
+        assert!(base.diff(&target).is_empty());
+    }
+}
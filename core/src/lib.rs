@@ -1,2 +1,73 @@
-mod entry;
-mod registry;
+#[cfg(feature = "tokio")]
+pub mod async_entry;
+#[cfg(feature = "tokio")]
+pub mod async_registry;
+pub mod audit;
+#[cfg(feature = "binary")]
+mod binary;
+#[cfg(feature = "tokio")]
+mod changes;
+pub mod clock;
+pub mod entry;
+pub mod error;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+mod load;
+pub mod registry;
+pub mod rw;
+#[cfg(feature = "serde")]
+mod serde_impls;
+pub mod sharded;
+#[cfg(feature = "snapshot-reads")]
+pub mod snapshot_reads;
+pub mod sync;
+#[cfg(feature = "binary")]
+mod wal;
+pub mod weak;
+
+#[cfg(feature = "derive")]
+pub use core_derive::HasName;
+
+#[cfg(feature = "tokio")]
+pub use async_entry::AsyncEntry;
+#[cfg(feature = "tokio")]
+pub use async_registry::AsyncNamedRegistry;
+pub use audit::{with_actor, AuditConfig, AuditOp, AuditRecord};
+#[cfg(feature = "tokio")]
+pub use changes::{ChangeStream, CHANGES_DEPTH};
+pub use clock::{Clock, SystemClock};
+pub use entry::{
+    Entry, EntryGuard, FreezeToken, HasKey, HasName, MappedEntryGuard, OwnedEntryGuard, SetName,
+    SharedValue, SubscriptionId, ValueGuard, WeakEntry,
+};
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub use error::LoadError;
+#[cfg(feature = "binary")]
+pub use error::SnapshotError;
+pub use error::{
+    CasError, DuplicateName, Frozen, InsertError, InsertIdError, InsertManyError, LockTimeout,
+    MutateError, NotFound, OccupiedError, PairError, PatchConflict, PatchError, PoisonedEntry,
+    PoisonedRegistry, RenameError, ReplaceError, Sealed, TakeError, TryInsertError,
+};
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub use load::{Format, LoadReport};
+#[cfg(feature = "ahash")]
+pub use registry::FastRegistry;
+#[cfg(feature = "tokio")]
+pub use registry::BROADCAST_CAPACITY;
+pub use registry::{
+    ApplyReport, ConflictPolicy, EvictionPolicy, InsertPolicy, KeyNormalizer, MergePolicy,
+    NameValidator, NamedRegistry, PatchChange, PoisonReport, RegistryDiff, RegistryEvent,
+    RegistryMetrics, RegistryOptions, RegistryPatch, RegistrySnapshot, RegistryStats,
+    TombstonePolicy, TryMutateOutcome, UpsertOutcome, DEFAULT_NAMESPACE, JOURNAL_CAPACITY,
+};
+pub use rw::{
+    RwEntry, RwNamedRegistry, RwRegistry, SharedRwValue, ValueReadGuard, ValueWriteGuard,
+};
+pub use sharded::{ShardedRegistry, DEFAULT_SHARDS};
+#[cfg(feature = "snapshot-reads")]
+pub use snapshot_reads::SnapshotRegistry;
+#[cfg(feature = "binary")]
+pub use wal::SyncPolicy;
+pub use weak::WeakRegistry;
@@ -0,0 +1,276 @@
+//! Loading entries from definition files, enabled by the `toml` and `yaml`
+//! features.
+
+use std::fs;
+use std::hash::BuildHasher;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+
+use crate::entry::HasName;
+use crate::error::{LoadError, TryInsertError};
+use crate::registry::NamedRegistry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One value per `.toml` file.
+    #[cfg(feature = "toml")]
+    Toml,
+    /// One value per `.yaml` or `.yml` file.
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl Format {
+    fn matches(self, path: &Path) -> bool {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        match self {
+            #[cfg(feature = "toml")]
+            Self::Toml => extension == Some("toml"),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => matches!(extension, Some("yaml" | "yml")),
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(self, text: &str) -> Result<T, LoadError> {
+        match self {
+            #[cfg(feature = "toml")]
+            Self::Toml => toml::from_str(text).map_err(|err| LoadError::Parse(err.to_string())),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => {
+                serde_yaml::from_str(text).map_err(|err| LoadError::Parse(err.to_string()))
+            }
+        }
+    }
+}
+
+/// The outcome of a load. A failure only skips the file or document it names.
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    pub loaded: usize,
+    pub failed: Vec<(PathBuf, LoadError)>,
+}
+
+impl LoadReport {
+    pub fn is_clean(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    fn record(&mut self, path: &Path, outcome: Result<(), LoadError>) {
+        match outcome {
+            Ok(()) => self.loaded += 1,
+            Err(err) => self.failed.push((path.to_path_buf(), err)),
+        }
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + DeserializeOwned,
+    S: BuildHasher + Default,
+{
+    /// Inserts the value defined in each file of `dir` with `format`'s
+    /// extension, in path order. Subdirectories are not visited. A name that
+    /// is already registered, by an earlier file or before the load, is
+    /// reported and left as it was.
+    ///
+    /// Only failing to list `dir` is an error; everything else ends up in the
+    /// report.
+    pub fn load_dir(&self, dir: &Path, format: Format) -> io::Result<LoadReport> {
+        let mut paths = Vec::new();
+        for item in fs::read_dir(dir)? {
+            let item = item?;
+            if item.file_type()?.is_file() && format.matches(&item.path()) {
+                paths.push(item.path());
+            }
+        }
+        paths.sort();
+
+        let mut report = LoadReport::default();
+        for path in paths {
+            let outcome = fs::read_to_string(&path)
+                .map_err(LoadError::Io)
+                .and_then(|text| format.parse(&text))
+                .and_then(|value| self.load_one(value));
+            report.record(&path, outcome);
+        }
+        Ok(report)
+    }
+
+    /// Inserts every document of a multi-document YAML file, each handled as
+    /// `load_dir` handles a file. Failures are reported under `path`; the
+    /// documents after a malformed one cannot be located and are not read.
+    #[cfg(feature = "yaml")]
+    pub fn load_yaml_documents(&self, path: &Path) -> io::Result<LoadReport> {
+        use serde::Deserialize;
+
+        let text = fs::read_to_string(path)?;
+        let mut report = LoadReport::default();
+        for document in serde_yaml::Deserializer::from_str(&text) {
+            // a syntax error ends the stream, a value that does not fit `T`
+            // only skips its own document
+            let document = match serde_yaml::Value::deserialize(document) {
+                Ok(document) => document,
+                Err(err) => {
+                    report.record(path, Err(LoadError::Parse(err.to_string())));
+                    break;
+                }
+            };
+            let parsed =
+                serde_yaml::from_value(document).map_err(|err| LoadError::Parse(err.to_string()));
+            report.record(path, parsed.and_then(|value| self.load_one(value)));
+        }
+        Ok(report)
+    }
+
+    fn load_one(&self, value: T) -> Result<(), LoadError> {
+        match self.try_insert(value) {
+            Ok(_) => Ok(()),
+            Err(TryInsertError::Occupied(err)) => Err(LoadError::Duplicate(err.value.name())),
+            Err(TryInsertError::Rejected(err)) => Err(LoadError::Rejected(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    // a fresh directory per test holding `files`, so parallel tests do not
+    // collide
+    fn fixture(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("core-load-{}-{test}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        for (file, text) in files {
+            fs::write(dir.join(file), text).unwrap();
+        }
+        dir
+    }
+
+    fn failures(report: &LoadReport) -> Vec<(String, String)> {
+        report
+            .failed
+            .iter()
+            .map(|(path, err)| {
+                let file = path.file_name().unwrap().to_string_lossy().into_owned();
+                let kind = match err {
+                    LoadError::Io(_) => "io",
+                    LoadError::Parse(_) => "parse",
+                    LoadError::Duplicate(name) => name,
+                    LoadError::Rejected(_) => "rejected",
+                };
+                (file, kind.to_string())
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "toml")]
+    #[rstest]
+    fn test_load_dir_toml() {
+        let dir = fixture(
+            "toml",
+            &[
+                ("alpha.toml", "name = \"alpha\"\nvalue = 1\n"),
+                ("beta.toml", "name = \"beta\"\nvalue = 2\n"),
+                ("broken.toml", "name = \"broken\"\nvalue = \n"),
+                ("copy.toml", "name = \"alpha\"\nvalue = 10\n"),
+                ("notes.txt", "not a definition"),
+                ("nested/gamma.toml", "name = \"gamma\"\nvalue = 3\n"),
+            ],
+        );
+        let reg = NamedRegistry::<InnerMock>::new();
+
+        let report = reg.load_dir(&dir, Format::Toml).unwrap();
+
+        assert_eq!(report.loaded, 2);
+        assert_eq!(
+            failures(&report),
+            [
+                ("broken.toml".into(), "parse".into()),
+                ("copy.toml".into(), "alpha".into())
+            ]
+        );
+        assert_eq!(reg.len(), 2);
+        assert!(!reg.contains("gamma"));
+        assert_eq!(reg.get_cloned("alpha").unwrap().value, 1);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[rstest]
+    fn test_load_dir_yaml() {
+        let dir = fixture(
+            "yaml",
+            &[
+                ("alpha.yaml", "name: alpha\nvalue: 1\n"),
+                ("beta.yml", "name: beta\nvalue: 2\n"),
+                ("broken.yaml", "name: broken\nvalue: [\n"),
+                ("copy.yml", "name: beta\nvalue: 20\n"),
+            ],
+        );
+        let reg = NamedRegistry::<InnerMock>::new();
+
+        let report = reg.load_dir(&dir, Format::Yaml).unwrap();
+
+        assert_eq!(report.loaded, 2);
+        assert_eq!(
+            failures(&report),
+            [
+                ("broken.yaml".into(), "parse".into()),
+                ("copy.yml".into(), "beta".into())
+            ]
+        );
+        assert_eq!(reg.get_cloned("beta").unwrap().value, 2);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[rstest]
+    fn test_load_yaml_documents() {
+        let text = "name: alpha\nvalue: 1\n---\nname: beta\nvalue: two\n---\nname: alpha\nvalue: 3\n---\nname: gamma\nvalue: 4\n---\nname: [\n---\nname: delta\nvalue: 5\n";
+        let dir = fixture("yaml-documents", &[("all.yaml", text)]);
+        let reg = NamedRegistry::<InnerMock>::new();
+
+        let report = reg.load_yaml_documents(&dir.join("all.yaml")).unwrap();
+
+        assert_eq!(report.loaded, 2);
+        assert_eq!(
+            failures(&report),
+            [
+                ("all.yaml".into(), "parse".into()),
+                ("all.yaml".into(), "alpha".into()),
+                ("all.yaml".into(), "parse".into()),
+            ]
+        );
+        assert!(reg.contains("gamma"));
+        assert!(!reg.contains("delta"));
+        assert!(!report.is_clean());
+    }
+
+    #[rstest]
+    fn test_missing_dir_is_an_error() {
+        let dir = std::env::temp_dir().join(format!("core-load-{}-missing", std::process::id()));
+        let reg = NamedRegistry::<InnerMock>::new();
+
+        #[cfg(feature = "toml")]
+        let format = Format::Toml;
+        #[cfg(not(feature = "toml"))]
+        let format = Format::Yaml;
+        assert!(reg.load_dir(&dir, format).is_err());
+    }
+}
@@ -1,201 +1,8371 @@
-use std::collections::HashMap;
-use std::hash::Hash;
-use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::borrow::{Borrow, Cow};
+use std::collections::{hash_map, HashMap, HashSet, VecDeque};
+use std::fmt;
+#[cfg(feature = "tokio")]
+use std::future::Future;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::ops::Deref;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+#[cfg(feature = "tokio")]
+use std::sync::OnceLock;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::entry::{Entry, HasName};
+#[cfg(feature = "tokio")]
+use tokio::sync::broadcast;
 
-#[derive(Debug, Clone)]
-pub struct NamedRegistry<T: Clone>(Arc<RwLock<HashMap<String, Entry<T>>>>);
+use crate::audit::{self, AuditConfig, AuditLog, AuditOp, AuditRecord};
+#[cfg(feature = "tokio")]
+use crate::changes::{ChangeStream, ChangeStreams, CHANGES_DEPTH};
+use crate::clock::{self, Clock};
+use crate::entry::{
+    DebugValue, Entry, FreezeToken, HasKey, HasName, SetName, SubscriptionId, WeakEntry,
+};
+use crate::error::{
+    CasError, DuplicateName, InsertError, InsertIdError, InsertManyError, LockTimeout, MutateError,
+    NotFound, OccupiedError, PairError, PatchConflict, PatchError, PoisonedRegistry, RenameError,
+    ReplaceError, Sealed, TakeError, TryInsertError,
+};
+use crate::sync::{
+    Condvar, CondvarExt, Mutex, MutexExt, RwLock, RwLockExt, RwLockReadGuard, RwLockWriteGuard,
+};
+#[cfg(feature = "binary")]
+use crate::wal::WalFile;
 
-impl<T> NamedRegistry<T>
+#[derive(Debug, Clone, Copy)]
+pub enum MergePolicy<T> {
+    KeepExisting,
+    TakeIncoming,
+    Resolve(fn(&T, &T) -> T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertPolicy {
+    Overwrite,
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryMutateOutcome<R> {
+    Applied(R),
+    Missing,
+    Busy,
+}
+
+/// A change reported to `NamedRegistry::subscribe` receivers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryEvent {
+    Inserted {
+        name: String,
+    },
+    Removed {
+        name: String,
+    },
+    Mutated {
+        name: String,
+    },
+    /// Stands in for this many events a `changes` stream had no room for.
+    /// Channels from `subscribe` and `subscribe_async` never carry it.
+    Lagged(u64),
+}
+
+impl RegistryEvent {
+    /// The entry the event is about; `None` for `Lagged`.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Inserted { name } | Self::Removed { name } | Self::Mutated { name } => Some(name),
+            Self::Lagged(_) => None,
+        }
+    }
+}
+
+/// How many events `NamedRegistry::subscribe_async` buffers before its
+/// slowest receivers start to lag.
+#[cfg(feature = "tokio")]
+pub const BROADCAST_CAPACITY: usize = 1024;
+
+/// What `insert` does when the name it registers has a tombstone left by
+/// `NamedRegistry::soft_remove`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TombstonePolicy {
+    /// Drop the tombstone; the removed entry can no longer be restored.
+    #[default]
+    Discard,
+    /// Keep it; `restore` succeeds once the name is free again.
+    Keep,
+}
+
+/// How many changed names `NamedRegistry::changed_since` can look back over.
+pub const JOURNAL_CAPACITY: usize = 1024;
+
+/// What `insert` does in a full `NamedRegistry::bounded` registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Evict the entry least recently inserted or looked up.
+    Lru,
+    /// Fail with `InsertError::Full`.
+    RejectNew,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertOutcome {
+    Inserted,
+    Updated,
+    /// The name was not registered and the registry is sealed, so the value
+    /// was dropped.
+    Sealed,
+    /// The registered entry is frozen, so the value was dropped.
+    Frozen,
+    /// The name was not registered and a full `RejectNew` registry refused
+    /// it, so the value was dropped.
+    Full,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoisonReport {
+    pub map_poisoned: bool,
+    pub poisoned_entries: Vec<String>,
+}
+
+impl PoisonReport {
+    pub fn is_healthy(&self) -> bool {
+        !self.map_poisoned && self.poisoned_entries.is_empty()
+    }
+}
+
+/// Differences from a registry to a desired one: `added` exists only in the
+/// desired registry, `removed` only in the current one, and `changed` holds
+/// `(name, current, desired)` for unequal values. Entries are sorted by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryDiff<T> {
+    pub added: Vec<(String, T)>,
+    pub removed: Vec<(String, T)>,
+    pub changed: Vec<(String, T, T)>,
+}
+
+impl<T> RegistryDiff<T> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A `RegistryDiff` meant to be sent elsewhere and applied with
+/// `NamedRegistry::apply_patch`. Values carry their own names, and removals
+/// and changes keep the value they were made against, so the receiver can
+/// tell when its copy has moved on. `Serialize`/`Deserialize` with the
+/// `serde` feature.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegistryPatch<T> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+    pub changed: Vec<PatchChange<T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatchChange<T> {
+    pub base: T,
+    pub value: T,
+}
+
+impl<T> RegistryPatch<T> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<T> From<RegistryDiff<T>> for RegistryPatch<T> {
+    fn from(diff: RegistryDiff<T>) -> Self {
+        Self {
+            added: diff.added.into_iter().map(|(_, value)| value).collect(),
+            removed: diff.removed.into_iter().map(|(_, value)| value).collect(),
+            changed: diff
+                .changed
+                .into_iter()
+                .map(|(_, base, value)| PatchChange { base, value })
+                .collect(),
+        }
+    }
+}
+
+/// What `apply_patch` does with an operation whose base no longer matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Apply nothing and return the conflicts.
+    Fail,
+    /// Apply everything else.
+    Skip,
+    /// Apply the patch's value anyway.
+    Force,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ApplyReport {
+    /// Entries written, inserted or removed.
+    pub applied: usize,
+    /// Skipped under `ConflictPolicy::Skip`, overwritten under `Force`.
+    pub conflicts: Vec<PatchConflict>,
+}
+
+/// A point-in-time copy of a registry's values, detached from the live
+/// registry: reads take no locks and later writes are not seen.
+#[derive(Clone)]
+pub struct RegistrySnapshot<T> {
+    // stored key -> (reported name, value)
+    entries: HashMap<Arc<str>, (String, T)>,
+    recovered: Vec<String>,
+    rules: KeyRules<Arc<str>>,
+}
+
+impl<T> RegistrySnapshot<T> {
+    pub fn get(&self, name: &str) -> Option<&T> {
+        let entry = match &self.rules.normalizer {
+            Some(normalize) => self.entries.get(&normalize(&Arc::from(name))),
+            None => self.entries.get(name),
+        };
+        entry.map(|(_, value)| value)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.entries
+            .values()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.values().map(|(name, _)| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Names of entries whose lock was poisoned when the snapshot was taken.
+    /// Their values were recovered as they stood, and may be mid-update.
+    pub fn recovered(&self) -> &[String] {
+        &self.recovered
+    }
+}
+
+/// Prints `RegistrySnapshot {"name": value, ..}` sorted by name.
+impl<T: fmt::Debug> fmt::Debug for RegistrySnapshot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pairs: Vec<(&str, &T)> = self.iter().collect();
+        pairs.sort_by_key(|(name, _)| *name);
+        f.write_str("RegistrySnapshot ")?;
+        f.debug_map().entries(pairs).finish()
+    }
+}
+
+/// Lock counters of a registry built with `collect_metrics`; all zero
+/// otherwise. `lock_wait` is the total time spent acquiring the map lock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegistryMetrics {
+    pub read_locks: u64,
+    pub write_locks: u64,
+    /// Entry locks that `try_mutate` or `mutate_timeout` failed to acquire.
+    pub try_lock_failures: u64,
+    pub lock_wait: Duration,
+}
+
+/// Operation counts since the registry was built or `reset_stats` last ran.
+/// Only `get`, `insert`, `remove` and `mutate` are counted; `hits` and
+/// `misses` cover the lookups of both `get` and `mutate`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegistryStats {
+    pub gets: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// Successful inserts, including those that overwrote an entry.
+    pub inserts: u64,
+    pub overwrites: u64,
+    /// Removals that found an entry.
+    pub removes: u64,
+    pub mutates: u64,
+}
+
+pub type NameValidator = fn(&str) -> Result<(), String>;
+
+/// Rewrites a name into the key it is stored and looked up under.
+pub type KeyNormalizer = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// The namespace the plain, non-`_in` methods operate on.
+pub const DEFAULT_NAMESPACE: &str = "";
+
+/// Construction-time settings for `NamedRegistry::new_with_options`.
+#[derive(Clone)]
+pub struct RegistryOptions {
+    /// Match names regardless of case. Keys are lowercased (Unicode-aware) on
+    /// insert and lookup; values and `keys` keep their original casing.
+    pub case_insensitive: bool,
+    /// Applied to every name on insert and lookup, so `get(" foo ")` finds
+    /// `"foo"`. `keys` returns the normalized forms. Values keep the name
+    /// they carry, and that is what the name validator checks.
+    pub key_normalizer: Option<KeyNormalizer>,
+    /// Splits `"namespace/name"` paths for `get_path`; namespaces may not
+    /// contain it. Defaults to `'/'`.
+    pub namespace_separator: char,
+    /// Count lock acquisitions and time spent waiting, read via `metrics`.
+    pub collect_metrics: bool,
+    /// Names the registry in `tracing` events and spans.
+    pub label: Option<String>,
+    /// Keep a log of recent inserts, mutations and removals, read via
+    /// `audit_log`.
+    pub audit: Option<AuditConfig>,
+    pub tombstones: TombstonePolicy,
+}
+
+impl Default for RegistryOptions {
+    fn default() -> Self {
+        Self {
+            case_insensitive: false,
+            key_normalizer: None,
+            namespace_separator: '/',
+            collect_metrics: false,
+            label: None,
+            audit: None,
+            tombstones: TombstonePolicy::default(),
+        }
+    }
+}
+
+impl fmt::Debug for RegistryOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegistryOptions")
+            .field("case_insensitive", &self.case_insensitive)
+            .field(
+                "key_normalizer",
+                &self.key_normalizer.as_ref().map(|_| ".."),
+            )
+            .field("namespace_separator", &self.namespace_separator)
+            .field("collect_metrics", &self.collect_metrics)
+            .field("label", &self.label)
+            .field("audit", &self.audit)
+            .field("tombstones", &self.tombstones)
+            .finish()
+    }
+}
+
+/// Entries keyed by name, or by any `K` the values implement `HasKey` for.
+/// Other key types get the core operations (`insert`, `get`, `mutate`,
+/// `remove`, ...); the name-based helpers such as `rename` and `merge` need
+/// the default `Arc<str>` keys, which share their allocation with the
+/// entry's cached name. Look them up by `&str`.
+///
+/// Keys are hashed with `S`, std's `RandomState` unless the registry was
+/// built with `with_hasher`.
+pub struct NamedRegistry<T, K = Arc<str>, S = RandomState>(Arc<RegistryInner<T, K, S>>);
+
+/// A name-keyed registry hashing with `ahash`, which is faster than SipHash
+/// but not hardened against adversarial keys.
+#[cfg(feature = "ahash")]
+pub type FastRegistry<T> = NamedRegistry<T, Arc<str>, ahash::RandomState>;
+
+// the registry's own key -> entry map
+type Map<T, K, S> = HashMap<K, Entry<T>, S>;
+
+// what an insert displaced: the entry it replaced, then the one it evicted
+type Placed<T> = (Option<Entry<T>>, Option<Entry<T>>);
+
+// an entry put into a free slot, then the one evicted to make room for it
+type PlacedNew<T> = (Entry<T>, Option<Entry<T>>);
+
+// entries of every non-default namespace, by namespace then key
+type Namespaces<T, K, S> = HashMap<String, HashMap<K, Entry<T>, S>>;
+
+pub(crate) struct RegistryInner<T, K, S> {
+    map: RwLock<HashMap<K, Entry<T>, S>>,
+    // entries outside `DEFAULT_NAMESPACE`, grouped so a namespace can be
+    // listed or dropped without scanning the others
+    namespaces: RwLock<Namespaces<T, K, S>>,
+    // locked after `map`, never before it
+    ids: RwLock<IdIndex<T>>,
+    validator: RwLock<Option<NameValidator>>,
+    clock: Arc<dyn Clock>,
+    rules: KeyRules<K>,
+    metrics: Option<LockCounters>,
+    stats: OpCounters,
+    label: Option<Arc<str>>,
+    audit: Option<Mutex<AuditLog>>,
+    bound: Option<Bound>,
+    // only set while holding the map's write lock, so a writer that checks
+    // it under that lock cannot race `seal`
+    sealed: AtomicBool,
+    // soft-removed entries and when they were removed; locked after `map`
+    tombstones: Mutex<HashMap<K, (Entry<T>, SystemTime)>>,
+    tombstone_policy: TombstonePolicy,
+    #[cfg(feature = "binary")]
+    wal: Mutex<Option<Arc<Mutex<WalFile>>>>,
+    generation: AtomicU64,
+    journal: Mutex<Journal>,
+    hooks: RwLock<Hooks<T>>,
+    next_hook: AtomicU64,
+    subscribers: Mutex<Vec<mpsc::Sender<RegistryEvent>>>,
+    #[cfg(feature = "tokio")]
+    broadcast: OnceLock<broadcast::Sender<RegistryEvent>>,
+    #[cfg(feature = "tokio")]
+    streams: ChangeStreams,
+    #[cfg(feature = "tokio")]
+    flights: Mutex<HashMap<K, Flight>>,
+    // `wait_for` callers; registrations only announce themselves while
+    // there are any
+    waiters: AtomicUsize,
+    arrival_lock: Mutex<()>,
+    arrival: Condvar,
+    #[cfg(feature = "tokio")]
+    arrival_async: tokio::sync::Notify,
+    // set once anything watches changes, so writes skip `notify` until then
+    observed: AtomicBool,
+}
+
+type Hook<T> = Arc<dyn Fn(&str, &Entry<T>) + Send + Sync>;
+type ReplaceHook<T> = Arc<dyn Fn(&str, &Entry<T>, &Entry<T>) + Send + Sync>;
+
+/// Lifecycle callbacks, each list in registration order.
+struct Hooks<T> {
+    insert: Vec<(SubscriptionId, Hook<T>)>,
+    remove: Vec<(SubscriptionId, Hook<T>)>,
+    replace: Vec<(SubscriptionId, ReplaceHook<T>)>,
+    mutate: Vec<(SubscriptionId, Hook<T>)>,
+}
+
+/// A change to the set of registered entries, reported to the hooks once
+/// the write that made it has released its locks.
+enum Change<T> {
+    Inserted(Entry<T>),
+    Removed(Entry<T>),
+    Replaced {
+        previous: Entry<T>,
+        current: Entry<T>,
+    },
+    // re-keyed from the old name to the entry's current one
+    Renamed(Arc<str>, Entry<T>),
+    // written in place; only subscribers hear of it
+    Mutated(Entry<T>),
+}
+
+#[derive(Default)]
+struct LockCounters {
+    read_locks: AtomicU64,
+    write_locks: AtomicU64,
+    try_lock_failures: AtomicU64,
+    wait_nanos: AtomicU64,
+}
+
+impl LockCounters {
+    fn timed<G>(&self, counter: &AtomicU64, acquire: impl FnOnce() -> G) -> G {
+        let start = Instant::now();
+        let guard = acquire();
+        let waited = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.wait_nanos.fetch_add(waited, Ordering::Relaxed);
+        guard
+    }
+}
+
+/// The most recently changed names, tagged with the generation that changed
+/// them.
+#[derive(Default)]
+struct Journal {
+    records: VecDeque<(u64, Arc<str>)>,
+    // the newest generation with a record evicted
+    evicted_through: u64,
+}
+
+struct Bound {
+    max_entries: usize,
+    policy: EvictionPolicy,
+    // a logical clock; entries remember the tick of their last use
+    tick: AtomicU64,
+}
+
+// counts a `wait_for` caller for as long as it waits, including one whose
+// future is dropped mid-wait
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl<'a> Waiting<'a> {
+    fn join(waiters: &'a AtomicUsize) -> Self {
+        waiters.fetch_add(1, Ordering::SeqCst);
+        Self(waiters)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Bound {
+    fn mark<T>(&self, entry: &Entry<T>) {
+        entry.mark_used(self.tick.fetch_add(1, Ordering::Relaxed));
+    }
+}
+
+/// Callers of `get_or_try_insert_with_async` take turns on one of these per
+/// missing name.
+#[cfg(feature = "tokio")]
+type Flight = Arc<tokio::sync::Mutex<()>>;
+
+// leaves the flight on drop, removing it once no caller is left, so a
+// cancelled or failed factory does not strand the name
+#[cfg(feature = "tokio")]
+struct Boarding<'a, K: Eq + Hash> {
+    flights: &'a Mutex<HashMap<K, Flight>>,
+    key: K,
+    flight: Flight,
+}
+
+#[cfg(feature = "tokio")]
+impl<K: Eq + Hash> Drop for Boarding<'_, K> {
+    fn drop(&mut self) {
+        let mut flights = self.flights.acquire();
+        // one handle in the map and ours: nobody else is waiting
+        let last = Arc::strong_count(&self.flight) <= 2;
+        if last
+            && flights
+                .get(&self.key)
+                .is_some_and(|f| Arc::ptr_eq(f, &self.flight))
+        {
+            flights.remove(&self.key);
+        }
+    }
+}
+
+#[derive(Default)]
+struct OpCounters {
+    gets: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    inserts: AtomicU64,
+    overwrites: AtomicU64,
+    removes: AtomicU64,
+    mutates: AtomicU64,
+}
+
+impl OpCounters {
+    fn bump(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn lookup<E>(&self, found: &Option<E>) {
+        Self::bump(match found {
+            Some(_) => &self.hits,
+            None => &self.misses,
+        });
+    }
+
+    fn all(&self) -> [&AtomicU64; 7] {
+        [
+            &self.gets,
+            &self.hits,
+            &self.misses,
+            &self.inserts,
+            &self.overwrites,
+            &self.removes,
+            &self.mutates,
+        ]
+    }
+}
+
+/// Stable ids for entries in the default namespace. Ids follow the entry, so
+/// renames need no bookkeeping, and are held weakly so the index does not
+/// count as a handle for `take`.
+struct IdIndex<T> {
+    by_id: HashMap<u64, WeakEntry<T>>,
+    by_entry: HashMap<usize, u64>,
+}
+
+impl<T: HasName> IdIndex<T> {
+    fn bind(&mut self, id: u64, entry: &Entry<T>) {
+        self.by_entry.insert(entry.addr(), id);
+        self.by_id.insert(id, entry.weak());
+    }
+
+    fn forget(&mut self, entry: &Entry<T>) {
+        if let Some(id) = self.id(entry) {
+            self.by_entry.remove(&entry.addr());
+            self.by_id.remove(&id);
+        }
+    }
+
+    fn entry(&self, id: u64) -> Option<Entry<T>> {
+        self.by_id.get(&id)?.upgrade()
+    }
+
+    // addresses can be reused once an entry is dropped, so confirm the id
+    // still points back at this entry
+    fn id(&self, entry: &Entry<T>) -> Option<u64> {
+        let id = *self.by_entry.get(&entry.addr())?;
+        self.entry(id)
+            .is_some_and(|bound| bound.ptr_eq(entry))
+            .then_some(id)
+    }
+
+    fn clear(&mut self) {
+        self.by_id.clear();
+        self.by_entry.clear();
+    }
+}
+
+// maps a key to the form it is stored and looked up under
+type Normalize<K> = Arc<dyn Fn(&K) -> K + Send + Sync>;
+
+/// A key as passed in, or its normalized form.
+enum Lookup<'q, Q: ?Sized, K> {
+    Borrowed(&'q Q),
+    Normalized(K),
+}
+
+impl<Q, K> Lookup<'_, Q, K>
 where
-    T: HasName + Clone,
+    Q: ToOwned + ?Sized,
+    Q::Owned: Into<K>,
+{
+    fn into_key(self) -> K {
+        match self {
+            Self::Borrowed(key) => key.to_owned().into(),
+            Self::Normalized(key) => key,
+        }
+    }
+}
+
+impl<Q: ?Sized, K: Borrow<Q>> Deref for Lookup<'_, Q, K> {
+    type Target = Q;
+
+    fn deref(&self) -> &Q {
+        match self {
+            Self::Borrowed(key) => key,
+            Self::Normalized(key) => key.borrow(),
+        }
+    }
+}
+
+/// How names become keys; carried over to registries split off this one.
+struct KeyRules<K> {
+    normalizer: Option<Normalize<K>>,
+    // report keys in the casing of the value's own name
+    show_names: bool,
+    separator: char,
+}
+
+impl<K> Clone for KeyRules<K> {
+    fn clone(&self) -> Self {
+        Self {
+            normalizer: self.normalizer.clone(),
+            show_names: self.show_names,
+            separator: self.separator,
+        }
+    }
+}
+
+impl<K> Default for KeyRules<K> {
+    fn default() -> Self {
+        Self {
+            normalizer: None,
+            show_names: false,
+            separator: RegistryOptions::default().namespace_separator,
+        }
+    }
+}
+
+impl<T, K, S> Clone for NamedRegistry<T, K, S> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+/// Prints `NamedRegistry {"name": value, ..}` sorted by key. The map lock is
+/// released before any value is formatted, and values locked elsewhere show
+/// as `<locked>`.
+impl<T: fmt::Debug, K: fmt::Debug + Ord + Clone, S> fmt::Debug for NamedRegistry<T, K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pairs: Vec<_> = self
+            .0
+            .map
+            .shared()
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.clone()))
+            .collect();
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+
+        f.write_str("NamedRegistry ")?;
+        f.debug_map()
+            .entries(pairs.iter().map(|(key, entry)| (key, DebugValue(entry))))
+            .finish()
+    }
+}
+
+/// Equal when both hold the same keys with equal values. Each side is
+/// snapshotted first, so the map locks are never held together.
+impl<T, K, S> PartialEq for NamedRegistry<T, K, S>
+where
+    T: HasName + HasKey<K> + PartialEq,
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return true;
+        }
+        let ours = self.snapshot_pairs();
+        let theirs: HashMap<_, _> = other.snapshot_pairs().into_iter().collect();
+        ours.len() == theirs.len()
+            && ours
+                .iter()
+                .all(|(key, entry)| theirs.get(key).is_some_and(|other| entry == other))
+    }
+}
+
+impl<T, K, S> Eq for NamedRegistry<T, K, S>
+where
+    T: HasName + HasKey<K> + Eq,
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+}
+
+impl<T, K> NamedRegistry<T, K>
+where
+    T: HasName + HasKey<K>,
+    K: Eq + Hash + Clone,
+{
+    /// An empty registry keyed by `K`; `new` is the shorthand for name
+    /// keys.
+    pub fn keyed() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<T, K, S> NamedRegistry<T, K, S>
+where
+    T: HasName + HasKey<K>,
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Default,
 {
-    fn new() -> Self {
-        Self(Arc::new(RwLock::new(HashMap::new())))
+    /// An empty registry whose keys are hashed with `hasher`.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self::from_map(
+            HashMap::with_hasher(hasher),
+            clock::system(),
+            KeyRules::default(),
+        )
+    }
+
+    /// Registers `value` under its key in a fresh entry. An existing entry with
+    /// the same key is replaced and returned; handles to it keep the old value.
+    /// The name validator, if any, checks the value's name.
+    pub fn insert(&self, value: T) -> Result<Option<Entry<T>>, InsertError> {
+        self.insert_expiring(value, None)
+    }
+
+    /// Like `insert`, but `get` and `contains` stop finding the entry once
+    /// `ttl` has passed on the registry's clock. `len`, `keys` and iteration
+    /// still see it until `purge_expired` drops it; `touch` restarts the TTL.
+    pub fn insert_with_ttl(
+        &self,
+        value: T,
+        ttl: Duration,
+    ) -> Result<Option<Entry<T>>, InsertError> {
+        self.insert_expiring(value, Some(ttl))
+    }
+
+    fn insert_expiring(
+        &self,
+        value: T,
+        ttl: Option<Duration>,
+    ) -> Result<Option<Entry<T>>, InsertError> {
+        if let Err(err) = self.validate(&value.name_ref()) {
+            self.trace("insert", Some(&value.name_ref()), "rejected");
+            return Err(err);
+        }
+        let (key, entry) = self.keyed_entry(value);
+        if let Some(ttl) = ttl {
+            entry.set_ttl(ttl);
+        }
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return Err(InsertError::Sealed);
+        }
+        let placed = self.place(&mut map, key, &entry);
+        drop(map);
+        let (previous, evicted) = placed.inspect_err(|_| {
+            self.trace("insert", Some(&entry.cached_name()), "rejected");
+        })?;
+        let mut changes = Vec::with_capacity(2);
+        self.settle(entry, previous.clone(), evicted, &mut changes);
+        self.notify(changes);
+        Ok(previous)
+    }
+
+    // Puts `entry` under `key` the way `insert` does, making room for it under
+    // the capacity bound and forgetting the ids of whatever it pushed out. The
+    // caller holds the write lock and has checked the seal.
+    fn place(
+        &self,
+        map: &mut Map<T, K, S>,
+        key: K,
+        entry: &Entry<T>,
+    ) -> Result<Placed<T>, InsertError> {
+        let evicted = match &self.0.bound {
+            Some(bound) if map.len() >= bound.max_entries && !map.contains_key(&key) => {
+                match bound.policy {
+                    EvictionPolicy::Lru => map
+                        .iter()
+                        .min_by_key(|(_, entry)| entry.last_used())
+                        .map(|(key, _)| key.clone())
+                        .and_then(|key| map.remove(&key)),
+                    EvictionPolicy::RejectNew => {
+                        return Err(InsertError::Full {
+                            name: entry.name(),
+                            capacity: bound.max_entries,
+                        });
+                    }
+                }
+            }
+            _ => None,
+        };
+        if let Some(bound) = &self.0.bound {
+            bound.mark(entry);
+        }
+        if self.0.tombstone_policy == TombstonePolicy::Discard {
+            self.0.tombstones.acquire().remove(&key);
+        }
+        let previous = map.insert(key, entry.clone());
+        let mut ids = self.ids_mut();
+        previous
+            .iter()
+            .chain(&evicted)
+            .for_each(|old| ids.forget(old));
+        Ok((previous, evicted))
+    }
+
+    // The bookkeeping of a placed entry once the lock is released: stats,
+    // tracing and audit, plus its changes in the order they happened.
+    fn settle(
+        &self,
+        entry: Entry<T>,
+        previous: Option<Entry<T>>,
+        evicted: Option<Entry<T>>,
+        changes: &mut Vec<Change<T>>,
+    ) {
+        changes.extend(evicted.map(Change::Removed));
+        let stats = &self.0.stats;
+        OpCounters::bump(&stats.inserts);
+        if previous.is_some() {
+            OpCounters::bump(&stats.overwrites);
+        }
+        let outcome = match previous {
+            Some(_) => "overwrote",
+            None => "inserted",
+        };
+        self.trace("insert", Some(&entry.cached_name()), outcome);
+        self.record_audit(AuditOp::Insert, &entry.cached_name());
+        changes.push(match previous {
+            Some(previous) => Change::Replaced {
+                previous,
+                current: entry,
+            },
+            None => Change::Inserted(entry),
+        });
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<Entry<T>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        let found = self.find(key);
+        OpCounters::bump(&self.0.stats.gets);
+        self.0.stats.lookup(&found);
+        found
+    }
+
+    // `get` without touching the stats, for lookups made on a caller's behalf
+    fn find<Q>(&self, key: &Q) -> Option<Entry<T>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        let entry = self.live(&self.rlock(), key).cloned()?;
+        if let Some(bound) = &self.0.bound {
+            bound.mark(&entry);
+        }
+        Some(entry)
+    }
+
+    // the entry under `key` in `map`, unless its TTL has run out
+    fn live<'m, Q>(&self, map: &'m Map<T, K, S>, key: &Q) -> Option<&'m Entry<T>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        map.get(&*self.lookup(key))
+            .filter(|entry| !entry.is_expired())
+    }
+
+    /// Restarts the TTL of the entry under `key`. False if the key is missing
+    /// or expired, or the entry was inserted without a TTL.
+    pub fn touch<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        self.find(key).is_some_and(|entry| entry.refresh_ttl())
+    }
+
+    /// Removes every expired entry and returns their names.
+    pub fn purge_expired(&self) -> Vec<String> {
+        let purged: Vec<_> = {
+            let mut map = self.lock();
+            if self.is_sealed() {
+                return Vec::new();
+            }
+            let purged: Vec<_> = map
+                .extract_if(|_, entry| entry.is_expired())
+                .map(|(_, entry)| entry)
+                .collect();
+            let mut ids = self.ids_mut();
+            purged.iter().for_each(|entry| ids.forget(entry));
+            purged
+        };
+        self.notify_removed(&purged);
+        purged.iter().map(Entry::name).collect()
+    }
+
+    /// Removes the entry under `key`. Does nothing once the registry is
+    /// sealed; `try_remove` reports that instead.
+    pub fn remove<Q>(&self, key: &Q) -> Option<Entry<T>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        self.try_remove(key).ok().flatten()
+    }
+
+    pub fn try_remove<Q>(&self, key: &Q) -> Result<Option<Entry<T>>, Sealed>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        let removed = {
+            let mut map = self.lock();
+            if self.is_sealed() {
+                return Err(Sealed);
+            }
+            let removed = map.remove(&*self.lookup(key));
+            if let Some(removed) = &removed {
+                self.ids_mut().forget(removed);
+            }
+            removed
+        };
+        match &removed {
+            Some(removed) => {
+                OpCounters::bump(&self.0.stats.removes);
+                self.trace("remove", Some(&removed.cached_name()), "removed");
+                self.record_audit(AuditOp::Remove, &removed.cached_name());
+            }
+            None => self.trace("remove", None, "missing"),
+        }
+        self.notify(removed.clone().map(Change::Removed));
+        Ok(removed)
+    }
+
+    /// Removes every entry. Does nothing once the registry is sealed;
+    /// `try_clear` reports that instead.
+    pub fn clear(&self) -> Vec<Entry<T>> {
+        self.try_clear().unwrap_or_default()
+    }
+
+    pub fn try_clear(&self) -> Result<Vec<Entry<T>>, Sealed> {
+        let removed: Vec<_> = {
+            let mut map = self.lock();
+            if self.is_sealed() {
+                return Err(Sealed);
+            }
+            self.ids_mut().clear();
+            map.drain().map(|(_, entry)| entry).collect()
+        };
+        self.notify_removed(&removed);
+        Ok(removed)
+    }
+
+    /// Stops entries from being registered or unregistered for the rest of
+    /// the registry's life, in every namespace. Writes that return a
+    /// `Result`, such as `insert`, `rename`, `take` and `try_remove`, fail
+    /// with a sealed error; the rest, such as `remove`, `drain`, `retain` and
+    /// `Extend`, do nothing. Reads and mutating values in place still work,
+    /// so `upsert` and `merge` still update registered names.
+    pub fn seal(&self) {
+        let _map = self.lock();
+        let _namespaces = self.0.namespaces.exclusive();
+        self.0.sealed.store(true, Ordering::Release);
+    }
+
+    /// Seals the registry and freezes every entry in it, so values can no
+    /// longer be written either, not even through handles taken earlier.
+    pub fn seal_deep(&self) {
+        let map = self.lock();
+        let namespaces = self.0.namespaces.exclusive();
+        self.0.sealed.store(true, Ordering::Release);
+        let namespaced = namespaces.values().flat_map(HashMap::values);
+        for entry in map.values().chain(namespaced) {
+            // never thawed, so the token is not kept
+            drop(entry.freeze());
+        }
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.0.sealed.load(Ordering::Acquire)
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        self.live(&self.rlock(), key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rlock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rlock().is_empty()
+    }
+
+    /// The registered keys, in the casing the values use when keys are
+    /// normalized.
+    pub fn keys(&self) -> Vec<K> {
+        if !self.0.rules.show_names {
+            return self.rlock().keys().cloned().collect();
+        }
+        self.values()
+            .iter()
+            .map(|entry| entry.with(HasKey::key))
+            .collect()
+    }
+
+    pub fn values(&self) -> Vec<Entry<T>> {
+        self.rlock().values().cloned().collect()
+    }
+
+    /// Iterates over a snapshot of `(key, entry)` pairs taken under the read
+    /// lock. Entries inserted or removed afterwards are not reflected.
+    pub fn iter(&self) -> impl Iterator<Item = (K, Entry<T>)> {
+        let normalized = self.0.rules.show_names;
+        self.snapshot_pairs().into_iter().map(move |(key, entry)| {
+            let key = if normalized {
+                entry.with(HasKey::key)
+            } else {
+                key
+            };
+            (key, entry)
+        })
+    }
+
+    /// Runs `f` with shared access to the value under `key`. The map lock is
+    /// released before `f` runs.
+    pub fn with<Q, F, R>(&self, key: &Q, f: F) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+        F: FnOnce(&T) -> R,
+    {
+        self.find(key).map(|entry| entry.with(f))
+    }
+
+    /// The cheapest read: runs `f` on the value under `key` without cloning
+    /// the entry handle. Both the map's read lock and the entry's lock are
+    /// held while `f` runs, so `f` must not call back into the registry or
+    /// lock the entry again; writers wait until it returns. Use `with` when
+    /// `f` does more than a quick read.
+    pub fn get_with<Q, F, R>(&self, key: &Q, f: F) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+        F: FnOnce(&T) -> R,
+    {
+        let key = self.lookup(key);
+        self.rlock()
+            .get(&*key)
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| entry.with(f))
+    }
+
+    /// Applies `f` to the entry under `key` and returns its result, or `None`
+    /// if the key is missing. If `f` changes the value's key, the entry is
+    /// re-keyed accordingly, unless the new key is taken or the registry is
+    /// sealed; then it stays under `key`, listed by `mismatched_names`, and
+    /// `mutate_strict` is the way to have the name restored instead.
+    pub fn mutate<Q, F, R>(&self, key: &Q, f: F) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+        F: FnOnce(&mut T) -> R,
+    {
+        let key = self.lookup(key);
+        let found = self.find(&*key);
+        OpCounters::bump(&self.0.stats.mutates);
+        self.0.stats.lookup(&found);
+        let Some(entry) = found else {
+            self.trace("mutate", None, "missing");
+            return None;
+        };
+        let old_name = entry.cached_name();
+        let (result, new_key) = entry.mutate(|value| (f(value), value.key()));
+
+        self.record_audit(AuditOp::Mutate, &entry.cached_name());
+        let new_key = self.stored_key(new_key);
+        if new_key.borrow() != &*key {
+            self.trace("mutate", Some(&entry.cached_name()), "renamed");
+            self.rekey(&*key, new_key, &entry, old_name);
+        } else {
+            self.trace("mutate", Some(&old_name), "mutated");
+            self.notify([Change::Mutated(entry)]);
+        }
+        Some(result)
+    }
+
+    /// Installs a check that `insert`, `try_insert` and `rename` run against
+    /// every new name. Lookups and other insertion paths are unaffected.
+    pub fn set_name_validator(&self, validator: NameValidator) {
+        *self.0.validator.exclusive() = Some(validator);
+    }
+
+    pub fn clear_name_validator(&self) {
+        *self.0.validator.exclusive() = None;
+    }
+
+    fn validate(&self, name: &str) -> Result<(), InsertError> {
+        if self.is_sealed() {
+            return Err(InsertError::Sealed);
+        }
+        let validator = *self.0.validator.shared();
+        match validator {
+            Some(validate) => validate(name).map_err(|reason| InsertError::InvalidName {
+                name: name.to_string(),
+                reason,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn from_map(map: HashMap<K, Entry<T>, S>, clock: Arc<dyn Clock>, rules: KeyRules<K>) -> Self {
+        Self(Arc::new(RegistryInner {
+            map: RwLock::new(map),
+            namespaces: RwLock::new(HashMap::new()),
+            ids: RwLock::new(IdIndex {
+                by_id: HashMap::new(),
+                by_entry: HashMap::new(),
+            }),
+            validator: RwLock::new(None),
+            clock,
+            rules,
+            metrics: None,
+            stats: OpCounters::default(),
+            label: None,
+            audit: None,
+            bound: None,
+            sealed: AtomicBool::new(false),
+            tombstones: Mutex::new(HashMap::new()),
+            tombstone_policy: TombstonePolicy::default(),
+            #[cfg(feature = "binary")]
+            wal: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            journal: Mutex::new(Journal::default()),
+            hooks: RwLock::new(Hooks {
+                insert: Vec::new(),
+                remove: Vec::new(),
+                replace: Vec::new(),
+                mutate: Vec::new(),
+            }),
+            next_hook: AtomicU64::new(0),
+            subscribers: Mutex::new(Vec::new()),
+            #[cfg(feature = "tokio")]
+            broadcast: OnceLock::new(),
+            #[cfg(feature = "tokio")]
+            streams: ChangeStreams::default(),
+            #[cfg(feature = "tokio")]
+            flights: Mutex::new(HashMap::new()),
+            waiters: AtomicUsize::new(0),
+            arrival_lock: Mutex::new(()),
+            arrival: Condvar::new(),
+            #[cfg(feature = "tokio")]
+            arrival_async: tokio::sync::Notify::new(),
+            observed: AtomicBool::new(false),
+        }))
+    }
+
+    /// The form `key` is stored under.
+    fn stored_key(&self, key: K) -> K {
+        match &self.0.rules.normalizer {
+            Some(normalize) => normalize(&key),
+            None => key,
+        }
+    }
+
+    /// The form to look `key` up under, borrowed unless keys are normalized.
+    fn lookup<'q, Q>(&self, key: &'q Q) -> Lookup<'q, Q, K>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        match &self.0.rules.normalizer {
+            Some(normalize) => Lookup::Normalized(normalize(&key.to_owned().into())),
+            None => Lookup::Borrowed(key),
+        }
+    }
+
+    /// A fresh entry for `value` and the key to store it under. Unless keys
+    /// are normalized, name keys share the entry's cached name.
+    fn keyed_entry(&self, value: T) -> (K, Entry<T>) {
+        let name = Arc::from(&*value.name_ref());
+        let key = self.stored_key(value.key_from_name(&name));
+        (key, Entry::named(value, name, Arc::clone(&self.0.clock)))
+    }
+
+    fn rekey<Q>(&self, old: &Q, new: K, entry: &Entry<T>, old_name: Arc<str>)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let moved = {
+            let mut map = self.lock();
+            if !map.get(old).is_some_and(|current| current.ptr_eq(entry)) {
+                return;
+            }
+            // nothing is unregistered to make room, so a sealed registry or
+            // a taken key leaves the entry where it was
+            let moved = !self.is_sealed() && !map.contains_key::<K>(&new);
+            if moved {
+                let entry = map.remove(old).expect("checked above");
+                map.insert(new, entry);
+            }
+            moved
+        };
+        if moved {
+            self.notify([Change::Renamed(old_name, entry.clone())]);
+        } else {
+            self.trace("mutate", Some(&old_name), "kept");
+            self.notify([Change::Mutated(entry.clone())]);
+        }
+    }
+
+    /// Calls `f` after an entry is registered under a name that was free.
+    /// Hooks run in registration order once the write has released the
+    /// registry's locks, so they may call back into the registry. They see
+    /// the default namespace only, and not writes made through `lock`.
+    pub fn on_insert<F>(&self, f: F) -> SubscriptionId
+    where
+        F: Fn(&str, &Entry<T>) + Send + Sync + 'static,
+    {
+        let id = SubscriptionId::next(&self.0.next_hook);
+        self.0.hooks.exclusive().insert.push((id, Arc::new(f)));
+        self.0.observed.store(true, Ordering::Release);
+        id
+    }
+
+    /// Calls `f` after an entry leaves the registry, as `on_insert` does. A
+    /// rename is reported as a removal under the old name followed by an
+    /// insertion under the new one.
+    pub fn on_remove<F>(&self, f: F) -> SubscriptionId
+    where
+        F: Fn(&str, &Entry<T>) + Send + Sync + 'static,
+    {
+        let id = SubscriptionId::next(&self.0.next_hook);
+        self.0.hooks.exclusive().remove.push((id, Arc::new(f)));
+        self.0.observed.store(true, Ordering::Release);
+        id
+    }
+
+    /// Calls `f` with the previous and the new entry after an insert
+    /// overwrites a name, as `on_insert` does. Writing a value in place, as
+    /// `upsert` does, keeps the entry and is not a replacement.
+    pub fn on_replace<F>(&self, f: F) -> SubscriptionId
+    where
+        F: Fn(&str, &Entry<T>, &Entry<T>) + Send + Sync + 'static,
+    {
+        let id = SubscriptionId::next(&self.0.next_hook);
+        self.0.hooks.exclusive().replace.push((id, Arc::new(f)));
+        self.0.observed.store(true, Ordering::Release);
+        id
+    }
+
+    /// Calls `f` after a registry method writes a value in place, for each
+    /// change `subscribe` reports as `Mutated`, as `on_insert` does.
+    pub fn on_mutate<F>(&self, f: F) -> SubscriptionId
+    where
+        F: Fn(&str, &Entry<T>) + Send + Sync + 'static,
+    {
+        let id = SubscriptionId::next(&self.0.next_hook);
+        self.0.hooks.exclusive().mutate.push((id, Arc::new(f)));
+        self.0.observed.store(true, Ordering::Release);
+        id
+    }
+
+    /// Deregisters a hook; false if `id` was not registered.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut hooks = self.0.hooks.exclusive();
+        let count = |hooks: &Hooks<T>| {
+            hooks.insert.len() + hooks.remove.len() + hooks.replace.len() + hooks.mutate.len()
+        };
+        let before = count(&hooks);
+        hooks.insert.retain(|(other, _)| *other != id);
+        hooks.remove.retain(|(other, _)| *other != id);
+        hooks.replace.retain(|(other, _)| *other != id);
+        hooks.mutate.retain(|(other, _)| *other != id);
+        before != count(&hooks)
+    }
+
+    /// A channel of every later change to the registry, as the hooks see
+    /// them: an overwrite arrives as `Inserted`, a rename as `Removed` then
+    /// `Inserted`. `mutate`, `update`, `replace` and the other registry
+    /// methods that write a value in place send `Mutated`; writes through an
+    /// entry handle are not seen. Events are sent once the write has released
+    /// the registry's locks. Dropping the receiver unsubscribes it.
+    pub fn subscribe(&self) -> mpsc::Receiver<RegistryEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.0.subscribers.acquire().push(sender);
+        self.0.observed.store(true, Ordering::Release);
+        receiver
+    }
+
+    /// Like `subscribe`, but every receiver shares one broadcast channel of
+    /// `BROADCAST_CAPACITY` events, created on the first call. A receiver that
+    /// falls further behind gets `RecvError::Lagged` and skips ahead; writers
+    /// never wait for it.
+    #[cfg(feature = "tokio")]
+    pub fn subscribe_async(&self) -> broadcast::Receiver<RegistryEvent> {
+        let receiver = self
+            .0
+            .broadcast
+            .get_or_init(|| broadcast::channel(BROADCAST_CAPACITY).0)
+            .subscribe();
+        self.0.observed.store(true, Ordering::Release);
+        receiver
+    }
+
+    /// A stream of the events `subscribe` sends, buffering up to
+    /// `CHANGES_DEPTH` of them.
+    #[cfg(feature = "tokio")]
+    pub fn changes(&self) -> ChangeStream {
+        self.changes_with_depth(CHANGES_DEPTH)
+    }
+
+    /// Like `changes`, buffering up to `depth` events.
+    ///
+    /// # Panics
+    ///
+    /// If `depth` is zero.
+    #[cfg(feature = "tokio")]
+    pub fn changes_with_depth(&self, depth: usize) -> ChangeStream {
+        let stream = self.0.streams.open(depth);
+        self.0.observed.store(true, Ordering::Release);
+        stream
+    }
+
+    /// Runs the hooks and informs subscribers of `changes`. Must be called
+    /// with no registry lock held.
+    fn notify(&self, changes: impl IntoIterator<Item = Change<T>>) {
+        let changes = changes.into_iter().inspect(|change| {
+            self.advance(change);
+            self.announce(change);
+        });
+        if !self.0.observed.load(Ordering::Acquire) {
+            changes.for_each(drop);
+            return;
+        }
+        let hooks = self.0.hooks.shared();
+        let insert: Vec<_> = hooks.insert.iter().map(|(_, f)| Arc::clone(f)).collect();
+        let remove: Vec<_> = hooks.remove.iter().map(|(_, f)| Arc::clone(f)).collect();
+        let replace: Vec<_> = hooks.replace.iter().map(|(_, f)| Arc::clone(f)).collect();
+        let mutate: Vec<_> = hooks.mutate.iter().map(|(_, f)| Arc::clone(f)).collect();
+        drop(hooks);
+
+        // each change is published before its hooks run, so events that
+        // hooks cause arrive after the event that triggered them
+        for change in changes {
+            match change {
+                Change::Inserted(entry) => {
+                    let name = entry.cached_name();
+                    self.publish([RegistryEvent::Inserted {
+                        name: name.to_string(),
+                    }]);
+                    insert.iter().for_each(|f| f(&name, &entry));
+                }
+                Change::Removed(entry) => {
+                    let name = entry.cached_name();
+                    self.publish([RegistryEvent::Removed {
+                        name: name.to_string(),
+                    }]);
+                    remove.iter().for_each(|f| f(&name, &entry));
+                }
+                Change::Replaced { previous, current } => {
+                    let name = current.cached_name();
+                    self.publish([RegistryEvent::Inserted {
+                        name: name.to_string(),
+                    }]);
+                    replace.iter().for_each(|f| f(&name, &previous, &current));
+                }
+                Change::Renamed(old_name, entry) => {
+                    let name = entry.cached_name();
+                    self.publish([
+                        RegistryEvent::Removed {
+                            name: old_name.to_string(),
+                        },
+                        RegistryEvent::Inserted {
+                            name: name.to_string(),
+                        },
+                    ]);
+                    remove.iter().for_each(|f| f(&old_name, &entry));
+                    insert.iter().for_each(|f| f(&name, &entry));
+                }
+                Change::Mutated(entry) => {
+                    let name = entry.cached_name();
+                    self.publish([RegistryEvent::Mutated {
+                        name: name.to_string(),
+                    }]);
+                    mutate.iter().for_each(|f| f(&name, &entry));
+                }
+            }
+        }
+    }
+
+    // wakes `wait_for` callers when a name may have appeared
+    fn announce(&self, change: &Change<T>) {
+        if self.0.waiters.load(Ordering::SeqCst) == 0
+            || matches!(change, Change::Removed(_) | Change::Mutated(_))
+        {
+            return;
+        }
+        // taken so a waiter between its check and its wait cannot miss this
+        drop(self.0.arrival_lock.acquire());
+        self.0.arrival.notify_all();
+        #[cfg(feature = "tokio")]
+        self.0.arrival_async.notify_waiters();
+    }
+
+    // the generation is bumped under the journal lock, so journal order and
+    // generation order agree
+    fn advance(&self, change: &Change<T>) {
+        let (first, second) = match change {
+            Change::Inserted(entry) | Change::Removed(entry) | Change::Mutated(entry) => {
+                (entry.cached_name(), None)
+            }
+            Change::Replaced { current, .. } => (current.cached_name(), None),
+            Change::Renamed(old_name, entry) => (Arc::clone(old_name), Some(entry.cached_name())),
+        };
+        let mut journal = self.0.journal.acquire();
+        let generation = self.0.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        for name in std::iter::once(first).chain(second) {
+            if journal.records.len() == JOURNAL_CAPACITY {
+                if let Some((evicted, _)) = journal.records.pop_front() {
+                    journal.evicted_through = evicted;
+                }
+            }
+            journal.records.push_back((generation, name));
+        }
+    }
+
+    /// Counts the changes reported to hooks and subscribers: inserts,
+    /// removals, renames and mutations made through the registry.
+    pub fn generation(&self) -> u64 {
+        self.0.generation.load(Ordering::Acquire)
+    }
+
+    /// The distinct names changed after `generation`, in the order they were
+    /// first changed, or `None` if more changes than the journal holds have
+    /// happened since. Costs a single atomic load when nothing changed.
+    pub fn changed_since(&self, generation: u64) -> Option<Vec<String>> {
+        if self.generation() <= generation {
+            return Some(Vec::new());
+        }
+        let journal = self.0.journal.acquire();
+        if journal.evicted_through > generation {
+            return None;
+        }
+        let mut seen = HashSet::new();
+        Some(
+            journal
+                .records
+                .iter()
+                .filter(|(changed, _)| *changed > generation)
+                .filter(|(_, name)| seen.insert(Arc::clone(name)))
+                .map(|(_, name)| name.to_string())
+                .collect(),
+        )
+    }
+
+    // senders whose receiver is gone are dropped here rather than failing
+    // the write
+    fn publish<const N: usize>(&self, events: [RegistryEvent; N]) {
+        // fails only when no receiver is left, which is not the writer's
+        // problem
+        #[cfg(feature = "tokio")]
+        if let Some(sender) = self.0.broadcast.get() {
+            for event in &events {
+                let _ = sender.send(event.clone());
+            }
+        }
+        #[cfg(feature = "tokio")]
+        self.0.streams.publish(&events);
+        let mut subscribers = self.0.subscribers.acquire();
+        if subscribers.is_empty() {
+            return;
+        }
+        subscribers.retain(|sender| {
+            events
+                .iter()
+                .all(|event| sender.send(event.clone()).is_ok())
+        });
+    }
+
+    fn notify_removed<'e>(&self, removed: impl IntoIterator<Item = &'e Entry<T>>)
+    where
+        T: 'e,
+    {
+        self.notify(removed.into_iter().cloned().map(Change::Removed));
+    }
+
+    fn snapshot_pairs(&self) -> Vec<(K, Entry<T>)> {
+        self.rlock()
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.clone()))
+            .collect()
+    }
+
+    pub(crate) fn rlock(&self) -> RwLockReadGuard<'_, HashMap<K, Entry<T>, S>> {
+        #[cfg(feature = "tracing")]
+        let _span = self.lock_span("shared");
+        match &self.0.metrics {
+            Some(metrics) => metrics.timed(&metrics.read_locks, || self.0.map.shared()),
+            None => self.0.map.shared(),
+        }
+    }
+
+    fn ids(&self) -> RwLockReadGuard<'_, IdIndex<T>> {
+        self.0.ids.shared()
+    }
+
+    fn ids_mut(&self) -> RwLockWriteGuard<'_, IdIndex<T>> {
+        self.0.ids.exclusive()
+    }
+
+    /// Write access to the underlying map. A poisoned lock is recovered rather
+    /// than propagated; use `try_access` to detect it.
+    pub fn lock(&self) -> RwLockWriteGuard<'_, HashMap<K, Entry<T>, S>> {
+        #[cfg(feature = "tracing")]
+        let _span = self.lock_span("exclusive");
+        match &self.0.metrics {
+            Some(metrics) => metrics.timed(&metrics.write_locks, || self.0.map.exclusive()),
+            None => self.0.map.exclusive(),
+        }
+    }
+
+    pub fn metrics(&self) -> RegistryMetrics {
+        let Some(metrics) = &self.0.metrics else {
+            return RegistryMetrics::default();
+        };
+        RegistryMetrics {
+            read_locks: metrics.read_locks.load(Ordering::Relaxed),
+            write_locks: metrics.write_locks.load(Ordering::Relaxed),
+            try_lock_failures: metrics.try_lock_failures.load(Ordering::Relaxed),
+            lock_wait: Duration::from_nanos(metrics.wait_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    pub fn stats(&self) -> RegistryStats {
+        let [gets, hits, misses, inserts, overwrites, removes, mutates] = self
+            .0
+            .stats
+            .all()
+            .map(|counter| counter.load(Ordering::Relaxed));
+        RegistryStats {
+            gets,
+            hits,
+            misses,
+            inserts,
+            overwrites,
+            removes,
+            mutates,
+        }
+    }
+
+    /// Zeroes the counters reported by `stats`. Operations racing with the
+    /// reset may be counted either side of it.
+    pub fn reset_stats(&self) {
+        for counter in self.0.stats.all() {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// `insert` with `actor` recorded in the audit log.
+    pub fn insert_as(&self, actor: &str, value: T) -> Result<Option<Entry<T>>, InsertError> {
+        audit::with_actor(actor, || self.insert(value))
+    }
+
+    /// `mutate` with `actor` recorded in the audit log.
+    pub fn mutate_as<Q, F, R>(&self, actor: &str, key: &Q, f: F) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+        F: FnOnce(&mut T) -> R,
+    {
+        audit::with_actor(actor, || self.mutate(key, f))
+    }
+
+    /// `remove` with `actor` recorded in the audit log.
+    pub fn remove_as<Q>(&self, actor: &str, key: &Q) -> Option<Entry<T>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        audit::with_actor(actor, || self.remove(key))
+    }
+
+    /// The retained audit records, oldest first; empty unless the registry
+    /// was built with `RegistryOptions::audit`. Only `insert`, `mutate` and
+    /// `remove` are recorded.
+    pub fn audit_log(&self) -> Vec<AuditRecord> {
+        match &self.0.audit {
+            Some(log) => log.acquire().records().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The retained audit records for `name`, oldest first.
+    pub fn audit_for(&self, name: &str) -> Vec<AuditRecord> {
+        match &self.0.audit {
+            Some(log) => log
+                .acquire()
+                .records()
+                .filter(|record| record.name == name)
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn record_audit(&self, op: AuditOp, name: &str) {
+        if let Some(log) = &self.0.audit {
+            let record = AuditRecord {
+                name: name.to_string(),
+                op,
+                timestamp: self.0.clock.now(),
+                actor: audit::current_actor(),
+            };
+            log.acquire().push(record);
+        }
+    }
+
+    #[cfg(feature = "binary")]
+    pub(crate) fn wal(&self) -> &Mutex<Option<Arc<Mutex<WalFile>>>> {
+        &self.0.wal
+    }
+
+    // reads the registry back without keeping it alive, for the log's hooks
+    #[cfg(feature = "binary")]
+    pub(crate) fn registered_by(&self) -> impl Fn(&str) -> Option<Option<Entry<T>>> + Send + Sync
+    where
+        K: Borrow<str> + From<String>,
+        RegistryInner<T, K, S>: Send + Sync,
+    {
+        let inner = Arc::downgrade(&self.0);
+        move |name| {
+            let reg = Self(inner.upgrade()?);
+            let entry = reg.live(&reg.rlock(), name).cloned();
+            Some(entry)
+        }
+    }
+
+    /// The label given in `RegistryOptions`.
+    pub fn label(&self) -> Option<&str> {
+        self.0.label.as_deref()
+    }
+
+    // covers only the wait for the map lock, not the time it is held
+    #[cfg(feature = "tracing")]
+    fn lock_span(&self, mode: &'static str) -> tracing::span::EnteredSpan {
+        tracing::trace_span!("registry_lock", registry = self.label(), mode).entered()
+    }
+
+    /// Records the outcome of a registry operation; a no-op without the
+    /// `tracing` feature.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn trace(&self, op: &'static str, name: Option<&str>, outcome: &'static str) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(registry = self.label(), op, name, outcome);
+    }
+
+    fn count_try_lock_failure(&self) {
+        if let Some(metrics) = &self.0.metrics {
+            metrics.try_lock_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> NamedRegistry<T>
+where
+    T: HasName,
+{
+    pub fn new() -> Self {
+        Self::from_map(HashMap::new(), clock::system(), KeyRules::default())
+    }
+
+    pub fn new_with_options(options: RegistryOptions) -> Self {
+        let normalizer: Option<Normalize<Arc<str>>> =
+            match (options.key_normalizer, options.case_insensitive) {
+                (Some(normalize), true) => Some(Arc::new(move |name: &Arc<str>| {
+                    normalize(name).to_lowercase().into()
+                })),
+                (Some(normalize), false) => {
+                    Some(Arc::new(move |name: &Arc<str>| normalize(name).into()))
+                }
+                (None, true) => Some(Arc::new(|name: &Arc<str>| name.to_lowercase().into())),
+                (None, false) => None,
+            };
+        let rules = KeyRules {
+            normalizer,
+            show_names: options.case_insensitive,
+            separator: options.namespace_separator,
+        };
+        let mut registry = Self::from_map(HashMap::new(), clock::system(), rules);
+        let inner = Arc::get_mut(&mut registry.0).expect("registry is not shared yet");
+        if options.collect_metrics {
+            inner.metrics = Some(LockCounters::default());
+        }
+        inner.label = options.label.map(Arc::from);
+        inner.tombstone_policy = options.tombstones;
+        inner.audit = options
+            .audit
+            .map(|config| Mutex::new(AuditLog::new(config)));
+        registry
+    }
+
+    /// A registry holding at most `max_entries` entries. Lookups through
+    /// `get`, `with`, `mutate` and the like count as uses; `insert` and
+    /// `insert_with_ttl` enforce the bound, while other insertion paths may
+    /// exceed it. Evicted entries are reported to `on_remove` hooks and
+    /// subscribers, and handles to them keep working.
+    ///
+    /// # Panics
+    ///
+    /// If `max_entries` is zero.
+    pub fn bounded(max_entries: usize, policy: EvictionPolicy) -> Self {
+        assert!(
+            max_entries > 0,
+            "a bounded registry needs room for an entry"
+        );
+        let mut registry = Self::with_capacity(max_entries);
+        Arc::get_mut(&mut registry.0)
+            .expect("registry is not shared yet")
+            .bound = Some(Bound {
+            max_entries,
+            policy,
+            tick: AtomicU64::new(0),
+        });
+        registry
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_map(
+            HashMap::with_capacity(capacity),
+            clock::system(),
+            KeyRules::default(),
+        )
+    }
+
+    /// An empty registry whose entries take their timestamps from `clock`.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self::from_map(HashMap::new(), clock, KeyRules::default())
+    }
+
+    pub fn from_entries<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        entries.into_iter().collect()
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName,
+    S: BuildHasher + Default,
+{
+    /// Inserts all `values` under a single write lock, each as `insert` would.
+    /// With `Overwrite` the names that replaced an entry are returned; with
+    /// `Abort` the first duplicate, in the registry or within `values`, fails
+    /// the call. A name the validator rejects, a seal, or a `RejectNew` bound
+    /// too small for the new names also fails it; a failed call inserts
+    /// nothing.
+    pub fn insert_many<I>(
+        &self,
+        values: I,
+        policy: InsertPolicy,
+    ) -> Result<Vec<String>, InsertManyError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let named: Vec<(Arc<str>, Entry<T>)> = values
+            .into_iter()
+            .map(|value| self.keyed_entry(value))
+            .collect();
+        if let Some(err) = named
+            .iter()
+            .find_map(|(_, entry)| self.validate(&entry.cached_name()).err())
+        {
+            self.trace("insert_many", None, "rejected");
+            return Err(err.into());
+        }
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return Err(InsertError::Sealed.into());
+        }
+
+        let mut seen = HashSet::with_capacity(named.len());
+        if policy == InsertPolicy::Abort {
+            if let Some((key, entry)) = named
+                .iter()
+                .find(|(key, _)| map.contains_key(key) || !seen.insert(&**key))
+            {
+                let name = self.shown_key(key, entry).into_owned();
+                return Err(InsertManyError::Duplicate(DuplicateName(name)));
+            }
+        }
+        if let Some(bound) = self
+            .0
+            .bound
+            .as_ref()
+            .filter(|bound| bound.policy == EvictionPolicy::RejectNew)
+        {
+            seen.clear();
+            let mut room = bound.max_entries.saturating_sub(map.len());
+            for (key, entry) in &named {
+                if map.contains_key(key) || !seen.insert(&**key) {
+                    continue;
+                }
+                if room == 0 {
+                    drop(map);
+                    self.trace("insert_many", Some(&entry.cached_name()), "rejected");
+                    return Err(InsertError::Full {
+                        name: entry.name(),
+                        capacity: bound.max_entries,
+                    }
+                    .into());
+                }
+                room -= 1;
+            }
+        }
+        drop(seen);
+
+        let mut placed = Vec::with_capacity(named.len());
+        let mut replaced = Vec::new();
+        for (key, entry) in named {
+            let name = self.shown_key(&key, &entry).into_owned();
+            // room was checked above, so only an `Lru` bound has work to do
+            let (previous, evicted) = self.place(&mut map, key, &entry)?;
+            if previous.is_some() {
+                replaced.push(name);
+            }
+            placed.push((entry, previous, evicted));
+        }
+        drop(map);
+        let mut changes = Vec::with_capacity(placed.len());
+        for (entry, previous, evicted) in placed {
+            self.settle(entry, previous, evicted, &mut changes);
+        }
+        self.notify(changes);
+        Ok(replaced)
+    }
+
+    pub fn try_insert(&self, value: T) -> Result<Entry<T>, TryInsertError<T>> {
+        self.validate(&value.name_ref())?;
+        let key = self.stored_key(value.key());
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return Err(InsertError::Sealed.into());
+        }
+        let key = match map.entry(key) {
+            hash_map::Entry::Occupied(slot) => {
+                return Err(TryInsertError::Occupied(OccupiedError {
+                    entry: slot.get().clone(),
+                    value,
+                }))
+            }
+            hash_map::Entry::Vacant(slot) => slot.into_key(),
+        };
+        let (inserted, evicted) = self
+            .place_new(&mut map, key, value)
+            .map_err(|(err, _)| TryInsertError::Rejected(err))?;
+        drop(map);
+        self.settle_new(inserted.clone(), evicted);
+        Ok(inserted)
+    }
+
+    /// Inserts every value whose name is not registered yet, under a single
+    /// write lock, and hands back the values that collided or that a full
+    /// `RejectNew` registry refused. A sealed registry hands back every
+    /// value.
+    pub fn try_extend<I>(&self, iter: I) -> Vec<T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return iter.into_iter().collect();
+        }
+        let mut inserted = Vec::new();
+        let collided = iter
+            .into_iter()
+            .filter_map(|value| match map.entry(self.stored_key(value.key())) {
+                hash_map::Entry::Occupied(_) => Some(value),
+                hash_map::Entry::Vacant(slot) => {
+                    let key = slot.into_key();
+                    match self.place_new(&mut map, key, value) {
+                        Ok(placed) => {
+                            inserted.push(placed);
+                            None
+                        }
+                        Err((_, value)) => Some(value),
+                    }
+                }
+            })
+            .collect();
+        drop(map);
+        let mut changes = Vec::with_capacity(inserted.len());
+        for (entry, evicted) in inserted {
+            self.settle(entry, None, evicted, &mut changes);
+        }
+        self.notify(changes);
+        collided
+    }
+
+    /// Inserts `value`, or overwrites the existing value in place so that
+    /// handles already given out observe it. Once the registry is sealed only
+    /// the overwrite is made. The overwrite waits for the entry's lock after
+    /// the map's is released, as in `update`, and is refused for a frozen
+    /// entry.
+    pub fn upsert(&self, value: T) -> UpsertOutcome {
+        let mut map = self.lock();
+        let existing = match map.entry(self.stored_key(value.key())) {
+            hash_map::Entry::Occupied(slot) => slot.get().clone(),
+            hash_map::Entry::Vacant(_) if self.is_sealed() => return UpsertOutcome::Sealed,
+            hash_map::Entry::Vacant(slot) => {
+                let key = slot.into_key();
+                let Ok((inserted, evicted)) = self.place_new(&mut map, key, value) else {
+                    return UpsertOutcome::Full;
+                };
+                drop(map);
+                self.settle_new(inserted, evicted);
+                return UpsertOutcome::Inserted;
+            }
+        };
+        drop(map);
+        if existing.checked_mutate(|current| *current = value).is_err() {
+            return UpsertOutcome::Frozen;
+        }
+        self.notify([Change::Mutated(existing)]);
+        UpsertOutcome::Updated
+    }
+
+    /// Swaps `value` in under `name` and returns the displaced value, or
+    /// inserts it and returns `None` if the name was not registered. A value
+    /// whose own name keys a different entry is handed back in the error, as
+    /// is one that would be inserted into a sealed or full registry or
+    /// overwrite a frozen entry.
+    pub fn replace(&self, name: &str, value: T) -> Result<Option<T>, ReplaceError<T>> {
+        let key = self.lookup(name).into_key();
+        if self.stored_key(value.key()) != key {
+            self.trace("replace", Some(name), "mismatched");
+            return Err(ReplaceError::Mismatch {
+                name: name.to_string(),
+                value,
+            });
+        }
+        let mut map = self.lock();
+        let existing = match map.entry(key) {
+            hash_map::Entry::Occupied(slot) => slot.get().clone(),
+            hash_map::Entry::Vacant(_) if self.is_sealed() => {
+                return Err(ReplaceError::Sealed(value));
+            }
+            hash_map::Entry::Vacant(slot) => {
+                let key = slot.into_key();
+                let (inserted, evicted) =
+                    self.place_new(&mut map, key, value)
+                        .map_err(|(err, value)| match err {
+                            InsertError::Full { capacity, .. } => {
+                                ReplaceError::Full { capacity, value }
+                            }
+                            _ => unreachable!("`place` only refuses a full registry"),
+                        })?;
+                drop(map);
+                self.settle_new(inserted, evicted);
+                return Ok(None);
+            }
+        };
+        drop(map);
+        let mut value = Some(value);
+        match existing.checked_mutate(|current| std::mem::replace(current, value.take().unwrap())) {
+            Ok(previous) => {
+                self.notify([Change::Mutated(existing)]);
+                Ok(Some(previous))
+            }
+            Err(_) => Err(ReplaceError::Frozen(value.take().unwrap())),
+        }
+    }
+
+    /// Overwrites the registered value in place, so existing handles observe
+    /// the change. Fails if nothing is registered under the value's name.
+    pub fn update(&self, entry: T) -> Result<(), NotFound> {
+        let Some(existing) = self.find(&*entry.name_ref()) else {
+            self.trace("update", Some(&entry.name_ref()), "missing");
+            return Err(NotFound(entry.name()));
+        };
+        self.trace("update", Some(&existing.cached_name()), "updated");
+        existing.set(entry);
+        self.notify([Change::Mutated(existing)]);
+        Ok(())
+    }
+
+    /// Swaps `entry` with the registered value, leaving the previous value in
+    /// `entry`. Fails if nothing is registered under the value's name.
+    pub fn update_swap(&self, entry: &mut T) -> Result<(), NotFound> {
+        let existing = self
+            .find(&*entry.name_ref())
+            .ok_or_else(|| NotFound(entry.name()))?;
+        existing.update(entry);
+        self.notify([Change::Mutated(existing)]);
+        Ok(())
+    }
+
+    pub fn update_or_insert(&self, value: T) -> UpsertOutcome {
+        self.upsert(value)
+    }
+
+    pub fn get_many(&self, names: &[&str]) -> Vec<Option<Entry<T>>> {
+        let map = self.rlock();
+        names
+            .iter()
+            .map(|name| self.live(&map, *name).cloned())
+            .collect()
+    }
+
+    pub fn get_all_present(&self, names: &[&str]) -> Vec<Entry<T>> {
+        let map = self.rlock();
+        names
+            .iter()
+            .filter_map(|name| self.live(&map, *name).cloned())
+            .collect()
+    }
+
+    /// Returns the entry under `name`, constructing it with `f` if missing.
+    /// The write lock is held across the check and the insert, so `f` runs at
+    /// most once per name even under contention. A missing name fails with
+    /// `InsertError::Sealed`, without running `f`, once the registry is
+    /// sealed, with `InsertError::Full` if a full `RejectNew` registry
+    /// refuses the new value, and with `InsertError::Mismatch` if `f` builds
+    /// a value that would be keyed under another name.
+    pub fn get_or_insert_with<F>(&self, name: &str, f: F) -> Result<Entry<T>, InsertError>
+    where
+        F: FnOnce() -> T,
+    {
+        if let Some(entry) = self.find(name) {
+            return Ok(entry);
+        }
+
+        let mut map = self.lock();
+        let key = match map.entry(self.lookup(name).into_key()) {
+            hash_map::Entry::Occupied(slot) => return Ok(slot.get().clone()),
+            hash_map::Entry::Vacant(_) if self.is_sealed() => return Err(InsertError::Sealed),
+            hash_map::Entry::Vacant(slot) => slot.into_key(),
+        };
+        let value = f();
+        self.check_factory(name, &key, &value)?;
+        let (inserted, evicted) = self
+            .place_new(&mut map, key, value)
+            .map_err(|(err, _)| err)?;
+        drop(map);
+        self.settle_new(inserted.clone(), evicted);
+        Ok(inserted)
+    }
+
+    /// Blocks until an entry is registered under `name`, returning at once if
+    /// there already is one, or `None` once `timeout` has passed. A waiter
+    /// woken by a registration that is removed again before it looks keeps
+    /// waiting. Only writes that reach the hooks wake waiters; inserting
+    /// through `lock` does not.
+    pub fn wait_for(&self, name: &str, timeout: Duration) -> Option<Entry<T>> {
+        let deadline = Instant::now() + timeout;
+        let _waiting = Waiting::join(&self.0.waiters);
+        let mut guard = self.0.arrival_lock.acquire();
+        loop {
+            if let Some(entry) = self.find(name) {
+                return Some(entry);
+            }
+            let left = deadline.checked_duration_since(Instant::now())?;
+            if left.is_zero() {
+                return None;
+            }
+            guard = self.0.arrival.wait_up_to(guard, left);
+        }
+    }
+
+    /// Like `wait_for`, but yields to the executor while waiting.
+    #[cfg(feature = "tokio")]
+    pub async fn wait_for_async(&self, name: &str, timeout: Duration) -> Option<Entry<T>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let _waiting = Waiting::join(&self.0.waiters);
+        loop {
+            // enabled before the check, so a registration right after it
+            // still wakes this one
+            let arrival = self.0.arrival_async.notified();
+            tokio::pin!(arrival);
+            arrival.as_mut().enable();
+            if let Some(entry) = self.find(name) {
+                return Some(entry);
+            }
+            if tokio::time::timeout_at(deadline, arrival).await.is_err() {
+                return self.find(name);
+            }
+        }
+    }
+
+    /// Like `get_or_insert_with`, but the factory is async and runs with no
+    /// lock held. Concurrent callers for the same missing name share a
+    /// single factory run and all get its entry.
+    #[cfg(feature = "tokio")]
+    pub async fn get_or_insert_with_async<F, Fut>(
+        &self,
+        name: &str,
+        f: F,
+    ) -> Result<Entry<T>, InsertError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        self.get_or_try_insert_with_async(name, || async { Ok(f().await) })
+            .await
+    }
+
+    /// Like `get_or_insert_with_async` for a factory that can fail. A
+    /// failed or cancelled run inserts nothing, and the next caller waiting
+    /// on the name runs its own factory. A sealed or full registry fails a
+    /// missing name with an `InsertError`, converted into `E`.
+    #[cfg(feature = "tokio")]
+    pub async fn get_or_try_insert_with_async<F, Fut, E>(
+        &self,
+        name: &str,
+        f: F,
+    ) -> Result<Entry<T>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: From<InsertError>,
+    {
+        if let Some(entry) = self.find(name) {
+            return Ok(entry);
+        }
+        if self.is_sealed() {
+            return Err(InsertError::Sealed.into());
+        }
+
+        let key = self.lookup(name).into_key();
+        let boarding = {
+            let mut flights = self.0.flights.acquire();
+            let flight = Arc::clone(flights.entry(key.clone()).or_default());
+            Boarding {
+                flights: &self.0.flights,
+                key,
+                flight,
+            }
+        };
+        let _turn = boarding.flight.lock().await;
+        // the caller before us may have inserted it
+        if let Some(entry) = self.find(name) {
+            return Ok(entry);
+        }
+
+        let value = f().await?;
+        self.check_factory(name, &boarding.key, &value)?;
+        let mut map = self.lock();
+        let key = match map.entry(boarding.key.clone()) {
+            hash_map::Entry::Occupied(slot) => return Ok(slot.get().clone()),
+            hash_map::Entry::Vacant(_) if self.is_sealed() => {
+                return Err(InsertError::Sealed.into())
+            }
+            hash_map::Entry::Vacant(slot) => slot.into_key(),
+        };
+        let (inserted, evicted) = self
+            .place_new(&mut map, key, value)
+            .map_err(|(err, _)| E::from(err))?;
+        drop(map);
+        self.settle_new(inserted.clone(), evicted);
+        Ok(inserted)
+    }
+
+    /// Removes the entry under `name` and hands back its value, provided
+    /// the registry holds the only handle. The removal is reported like any
+    /// other; a hook that keeps a handle to the entry makes `take` put it
+    /// back, if the name is still free, and fail with `TakeError::Shared`.
+    pub fn take(&self, name: &str) -> Result<T, TakeError> {
+        let key = self.lookup(name).into_key();
+        let entry = {
+            let mut map = self.lock();
+            if self.is_sealed() {
+                return Err(TakeError::Sealed);
+            }
+            let entry = map
+                .remove(&key)
+                .ok_or_else(|| TakeError::NotFound(name.to_string()))?;
+            if entry.strong_count() > 1 {
+                map.insert(key, entry);
+                return Err(TakeError::Shared(name.to_string()));
+            }
+            self.ids_mut().forget(&entry);
+            entry
+        };
+        self.notify_removed([&entry]);
+
+        entry.try_unwrap().map_err(|entry| {
+            let restored = match self.lock().entry(key) {
+                hash_map::Entry::Vacant(slot) => Some(slot.insert(entry).clone()),
+                hash_map::Entry::Occupied(_) => None,
+            };
+            self.notify(restored.map(Change::Inserted));
+            TakeError::Shared(name.to_string())
+        })
+    }
+
+    /// The names of entries that something besides the registry still holds a
+    /// handle to, with the number of such handles. The counts are read one
+    /// entry at a time and can change as soon as they are read, so treat the
+    /// result as a diagnostic, not a guarantee. Soft-removed entries are not
+    /// included.
+    pub fn externally_referenced(&self) -> Vec<(String, usize)> {
+        self.rlock()
+            .values()
+            .filter_map(|entry| {
+                // the map's own handle
+                let external = entry.strong_count() - 1;
+                (external > 0).then(|| (entry.name(), external))
+            })
+            .collect()
+    }
+
+    /// Removes the named entry but keeps it aside, so `restore` can put it
+    /// back until `purge_tombstones` drops it. Returns whether an entry was
+    /// removed; a tombstone already under the name is replaced. Does nothing
+    /// once the registry is sealed.
+    pub fn soft_remove(&self, name: &str) -> bool {
+        let key = self.lookup(name);
+        let removed = {
+            let mut map = self.lock();
+            if self.is_sealed() {
+                return false;
+            }
+            let Some((key, entry)) = map.remove_entry(&*key) else {
+                return false;
+            };
+            self.ids_mut().forget(&entry);
+            let now = self.0.clock.now();
+            self.0
+                .tombstones
+                .acquire()
+                .insert(key, (entry.clone(), now));
+            entry
+        };
+        self.notify([Change::Removed(removed)]);
+        true
+    }
+
+    /// Puts a soft-removed entry back under its name. False if there is no
+    /// tombstone for it, the name has been registered again, or a full
+    /// `RejectNew` registry has no room for it.
+    pub fn restore(&self, name: &str) -> bool {
+        let key = self.lookup(name);
+        let (restored, evicted) = {
+            let mut map = self.lock();
+            if self.is_sealed() || map.contains_key(&*key) {
+                return false;
+            }
+            let Some((key, entry)) = self
+                .0
+                .tombstones
+                .acquire()
+                .get_key_value(&*key)
+                .map(|(key, (entry, _))| (key.clone(), entry.clone()))
+            else {
+                return false;
+            };
+            let Ok((_, evicted)) = self.place(&mut map, key.clone(), &entry) else {
+                return false;
+            };
+            self.0.tombstones.acquire().remove(&key);
+            (entry, evicted)
+        };
+        self.notify(
+            evicted
+                .map(Change::Removed)
+                .into_iter()
+                .chain([Change::Inserted(restored)]),
+        );
+        true
+    }
+
+    /// Drops the tombstones at least `older_than` old and returns how many.
+    pub fn purge_tombstones(&self, older_than: Duration) -> usize {
+        let now = self.0.clock.now();
+        let mut tombstones = self.0.tombstones.acquire();
+        let before = tombstones.len();
+        tombstones.retain(|_, (_, removed_at)| {
+            now.duration_since(*removed_at)
+                .map_or(true, |age| age < older_than)
+        });
+        before - tombstones.len()
+    }
+
+    /// The names of the soft-removed entries that can still be restored.
+    pub fn tombstones(&self) -> Vec<String> {
+        self.0
+            .tombstones
+            .acquire()
+            .values()
+            .map(|(entry, _)| entry.name())
+            .collect()
+    }
+
+    /// Atomically removes the entry with the lexicographically smallest key.
+    /// A sealed registry pops nothing.
+    pub fn pop_first(&self) -> Option<(String, Entry<T>)> {
+        let popped = {
+            let mut map = self.lock();
+            if self.is_sealed() {
+                return None;
+            }
+            let key = map.keys().min()?.clone();
+            map.remove_entry(&key)
+        };
+        self.notify(
+            popped
+                .iter()
+                .map(|(_, entry)| Change::Removed(entry.clone())),
+        );
+        popped.map(|pair| self.shown_pair(pair))
+    }
+
+    /// Atomically removes an arbitrary entry, without scanning for an order.
+    /// A sealed registry pops nothing.
+    pub fn pop_any(&self) -> Option<(String, Entry<T>)> {
+        let popped = {
+            let mut map = self.lock();
+            if self.is_sealed() {
+                return None;
+            }
+            let key = map.keys().next()?.clone();
+            map.remove_entry(&key)
+        };
+        self.notify(
+            popped
+                .iter()
+                .map(|(_, entry)| Change::Removed(entry.clone())),
+        );
+        popped.map(|pair| self.shown_pair(pair))
+    }
+
+    /// Empties the registry and yields the removed pairs. The map is detached
+    /// under a single write lock, so iterating does not block other threads.
+    /// A sealed registry yields nothing.
+    pub fn drain(&self) -> impl Iterator<Item = (String, Entry<T>)> + '_ {
+        let drained = self.detach();
+        self.notify_removed(drained.values());
+        drained.into_iter().map(|pair| self.shown_pair(pair))
+    }
+
+    pub fn clear_and_drop(&self) {
+        let dropped = self.detach();
+        self.notify_removed(dropped.values());
+    }
+
+    // takes the whole map, unless the registry is sealed
+    fn detach(&self) -> Map<T, Arc<str>, S> {
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return Map::default();
+        }
+        std::mem::take(&mut *map)
+    }
+
+    /// Drops every entry for which `f` returns false and returns how many were
+    /// removed. A panic in `f` is propagated after the map lock is released, so
+    /// the registry itself stays usable. `f` may write to the values it keeps,
+    /// so every kept entry is reported as mutated. A sealed registry does not
+    /// call `f` and removes nothing.
+    pub fn retain<F>(&self, mut f: F) -> usize
+    where
+        F: FnMut(&str, &mut T) -> bool,
+    {
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return 0;
+        }
+        let mut removed = Vec::new();
+        let mut kept = Vec::new();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            removed.extend(
+                map.extract_if(|key, entry| {
+                    let keep = f(&self.shown_key(key, entry), &mut entry.lock());
+                    if keep {
+                        kept.push(entry.clone());
+                    }
+                    !keep
+                })
+                .map(|(_, entry)| entry),
+            );
+        }));
+        drop(map);
+        self.notify_removed(&removed);
+        self.notify(kept.into_iter().map(Change::Mutated));
+
+        if let Err(payload) = result {
+            panic::resume_unwind(payload);
+        }
+        removed.len()
+    }
+
+    /// Moves every entry matching `pred` into a new registry. The handles are
+    /// moved under one write lock, so no reader sees an entry in both. A sealed
+    /// registry moves nothing and returns an empty one.
+    pub fn split_off<F>(&self, mut pred: F) -> Self
+    where
+        F: FnMut(&str, &T) -> bool,
+    {
+        let mut map = self.lock();
+        let split: Map<T, Arc<str>, S> = if self.is_sealed() {
+            Map::default()
+        } else {
+            map.extract_if(|key, entry| pred(&self.shown_key(key, entry), &entry.lock()))
+                .collect()
+        };
+        drop(map);
+        self.notify_removed(split.values());
+        Self::from_map(split, Arc::clone(&self.0.clock), self.0.rules.clone())
+    }
+
+    pub fn version_of(&self, name: &str) -> Option<u64> {
+        self.find(name).map(|entry| entry.version())
+    }
+
+    /// Entries whose metadata maps `key` to `value`.
+    pub fn find_by_meta(&self, key: &str, value: &str) -> Vec<Entry<T>> {
+        self.rlock()
+            .values()
+            .filter(|entry| entry.meta(key).as_deref() == Some(value))
+            .cloned()
+            .collect()
+    }
+
+    /// Names of the entries inserted or written to strictly after `since`.
+    pub fn modified_since(&self, since: SystemTime) -> Vec<String> {
+        self.rlock()
+            .iter()
+            .filter(|(_, entry)| entry.last_modified() > since)
+            .map(|(key, entry)| self.shown_key(key, entry).into_owned())
+            .collect()
+    }
+
+    /// Freezes every entry currently registered; entries inserted later are
+    /// not affected.
+    pub fn freeze_all(&self) -> FreezeToken {
+        for (_, entry) in self.snapshot_pairs() {
+            let _ = entry.freeze();
+        }
+        FreezeToken::new()
+    }
+
+    pub fn unfreeze_all(&self, token: &FreezeToken) {
+        for (_, entry) in self.snapshot_pairs() {
+            entry.unfreeze(token);
+        }
+    }
+
+    /// Whether `name` is registered to `entry` itself, not merely to an
+    /// entry with an equal value.
+    pub fn same_entry(&self, name: &str, entry: &Entry<T>) -> bool {
+        self.find(name).is_some_and(|current| current.ptr_eq(entry))
+    }
+
+    /// True when every name is registered; an empty slice yields true.
+    pub fn contains_all(&self, names: &[&str]) -> bool {
+        let map = self.rlock();
+        names.iter().all(|name| self.live(&map, *name).is_some())
+    }
+
+    /// True when at least one name is registered; an empty slice yields false.
+    pub fn contains_any(&self, names: &[&str]) -> bool {
+        let map = self.rlock();
+        names.iter().any(|name| self.live(&map, *name).is_some())
+    }
+
+    /// Names that are not registered, deduplicated, in the order first given.
+    pub fn missing_from(&self, names: &[&str]) -> Vec<String> {
+        let map = self.rlock();
+        let mut missing: Vec<String> = Vec::new();
+        for name in names {
+            if self.live(&map, *name).is_none() && !missing.iter().any(|m| m == name) {
+                missing.push(name.to_string());
+            }
+        }
+        missing
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.rlock().capacity()
+    }
+
+    pub fn reserve(&self, additional: usize) {
+        self.lock().reserve(additional);
+    }
+
+    pub fn shrink_to_fit(&self) {
+        self.lock().shrink_to_fit();
+    }
+
+    pub fn shrink_to(&self, min_capacity: usize) {
+        self.lock().shrink_to(min_capacity);
+    }
+
+    /// Same as `for_each_entry`.
+    pub fn for_each<F>(&self, f: F)
+    where
+        F: FnMut(&str, &Entry<T>),
+    {
+        self.for_each_entry(f);
+    }
+
+    /// Calls `f` for each pair in a snapshot of the registry. The map lock is
+    /// released before `f` first runs, so `f` may lock the entry or call back
+    /// into the registry, including inserting and removing entries. Those
+    /// changes do not affect which pairs are visited.
+    pub fn for_each_entry<F>(&self, mut f: F)
+    where
+        F: FnMut(&str, &Entry<T>),
+    {
+        for (key, entry) in self.snapshot_pairs() {
+            f(&self.shown_key(&key, &entry), &entry);
+        }
+    }
+
+    /// Like `for_each_entry`, stopping at the first error and returning it.
+    pub fn try_for_each_entry<F, E>(&self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&str, &Entry<T>) -> Result<(), E>,
+    {
+        for (key, entry) in self.snapshot_pairs() {
+            f(&self.shown_key(&key, &entry), &entry)?;
+        }
+        Ok(())
+    }
+
+    /// Locks both entries and passes their values to `f`, e.g. to move a
+    /// quantity from one to the other atomically. Entries are locked in
+    /// address order, so concurrent calls on the same pair in either
+    /// direction cannot deadlock. Both are locked before the map is released,
+    /// so `f` runs on the entries still registered under `a` and `b`, though
+    /// they may be removed while it runs. `a` and `b` naming the same entry
+    /// is an error. Renames are re-keyed as in `mutate`.
+    pub fn mutate_pair<F>(&self, a: &str, b: &str, f: F) -> Result<(), PairError>
+    where
+        F: FnOnce(&mut T, &mut T),
+    {
+        let map = self.rlock();
+        let resolve = |name: &str| {
+            self.live(&map, name)
+                .cloned()
+                .ok_or_else(|| PairError::NotFound(name.to_string()))
+        };
+        let (first, second) = (resolve(a)?, resolve(b)?);
+        if first.ptr_eq(&second) {
+            return Err(PairError::SameEntry(a.to_string()));
+        }
+        let old_names = [first.cached_name(), second.cached_name()];
+
+        {
+            // locked before the map is released, so neither entry can be
+            // removed or re-keyed between the lookup and the write
+            let (mut first_guard, mut second_guard) = first.lock_pair(&second);
+            drop(map);
+            f(&mut first_guard, &mut second_guard);
+        }
+
+        // both moves happen under one map lock, so swapping the two names
+        // never lets one entry overwrite the other; if either move would
+        // displace a third entry, neither is made
+        let mut moves = Vec::new();
+        let mut changes = Vec::new();
+        for ((key, entry), old_name) in [(a, first), (b, second)].into_iter().zip(old_names) {
+            let key = self.lookup(key).into_key();
+            match self.moved_key(&key, &entry) {
+                Some(new_key) => moves.push((key, new_key, entry, old_name)),
+                None => changes.push(Change::Mutated(entry)),
+            }
+        }
+        if !moves.is_empty() {
+            let mut map = self.lock();
+            let mut moved = Vec::new();
+            for (key, new_key, entry, old_name) in moves {
+                if map.get(&key).is_some_and(|current| current.ptr_eq(&entry)) {
+                    map.remove(&key);
+                    moved.push((key, new_key, entry, old_name));
+                }
+            }
+            let free = !self.is_sealed()
+                && moved.iter().enumerate().all(|(i, (_, new_key, ..))| {
+                    !map.contains_key(new_key)
+                        && moved[..i].iter().all(|(_, other, ..)| other != new_key)
+                });
+            for (key, new_key, entry, old_name) in moved {
+                if free {
+                    map.insert(new_key, entry.clone());
+                    changes.push(Change::Renamed(old_name, entry));
+                } else {
+                    map.insert(key, entry.clone());
+                    changes.push(Change::Mutated(entry));
+                }
+            }
+        }
+        self.notify(changes);
+        Ok(())
+    }
+
+    /// Registry form of `Entry::compare_and_mutate`. Renames are re-keyed as in
+    /// `mutate`.
+    pub fn cas<F>(&self, key: &str, expected: u64, f: F) -> Result<u64, CasError>
+    where
+        F: FnOnce(&mut T),
+    {
+        let entry = self
+            .find(key)
+            .ok_or_else(|| CasError::NotFound(key.to_string()))?;
+        let old_name = entry.cached_name();
+        let version = entry.compare_and_mutate(expected, f)?;
+
+        let key = self.lookup(key);
+        match self.moved_key(&key, &entry) {
+            Some(new_key) => self.rekey(&*key, new_key, &entry, old_name),
+            None => self.notify([Change::Mutated(entry)]),
+        }
+        Ok(version)
+    }
+
+    /// Like `mutate`, but gives up instead of waiting when the entry is locked.
+    /// Renames are not re-keyed.
+    pub fn try_mutate<F, R>(&self, key: &str, f: F) -> TryMutateOutcome<R>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        match self.find(key) {
+            None => TryMutateOutcome::Missing,
+            Some(entry) => match entry.try_mutate(f) {
+                Some(result) => {
+                    self.notify([Change::Mutated(entry)]);
+                    TryMutateOutcome::Applied(result)
+                }
+                None => {
+                    self.count_try_lock_failure();
+                    TryMutateOutcome::Busy
+                }
+            },
+        }
+    }
+
+    /// Like `mutate`, but waits at most `timeout` for the entry's lock. Yields
+    /// `Ok(None)` if the key is missing. Renames are not re-keyed.
+    pub fn mutate_timeout<F, R>(
+        &self,
+        key: &str,
+        timeout: Duration,
+        f: F,
+    ) -> Result<Option<R>, LockTimeout>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let Some(entry) = self.find(key) else {
+            return Ok(None);
+        };
+        let mut guard = entry
+            .lock_timeout(timeout)
+            .inspect_err(|_| self.count_try_lock_failure())?;
+        let result = f(&mut guard);
+        drop(guard);
+        self.notify([Change::Mutated(entry)]);
+        Ok(Some(result))
+    }
+
+    /// Keys whose entry reports a different name, as `(key, name)` pairs. This
+    /// happens when a value is renamed behind the registry's back, e.g. through
+    /// `Entry::lock`.
+    pub fn mismatched_names(&self) -> Vec<(String, String)> {
+        self.rlock()
+            .iter()
+            .filter(|(key, entry)| self.moved_key(key, entry).is_some())
+            .map(|(key, entry)| (key.to_string(), entry.name()))
+            .collect()
+    }
+
+    /// Applies `f` to every entry found under `keys` and returns how many were
+    /// found. Entries are resolved under one read lock and then locked one at
+    /// a time, so `f` never runs with the map lock or a second entry held.
+    pub fn batch_mutate<F>(&self, keys: &[&str], mut f: F) -> usize
+    where
+        F: FnMut(&str, &mut T),
+    {
+        let found: Vec<_> = {
+            let map = self.rlock();
+            keys.iter()
+                .filter_map(|key| self.live(&map, *key).map(|entry| (*key, entry.clone())))
+                .collect()
+        };
+
+        for (key, entry) in &found {
+            f(key, &mut entry.lock());
+        }
+        let count = found.len();
+        self.notify(found.into_iter().map(|(_, entry)| Change::Mutated(entry)));
+        count
+    }
+
+    pub fn try_access(
+        &self,
+    ) -> Result<RwLockWriteGuard<'_, Map<T, Arc<str>, S>>, PoisonedRegistry> {
+        self.0.map.exclusive_unpoisoned().ok_or(PoisonedRegistry)
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.0.map.poisoned()
+    }
+
+    /// Reports which locks are poisoned without changing anything.
+    pub fn health(&self) -> PoisonReport {
+        let map_poisoned = self.is_poisoned();
+        let mut poisoned_entries: Vec<String> = self
+            .rlock()
+            .iter()
+            .filter(|(_, entry)| entry.is_poisoned())
+            .map(|(key, entry)| self.shown_key(key, entry).into_owned())
+            .collect();
+        poisoned_entries.sort();
+        PoisonReport {
+            map_poisoned,
+            poisoned_entries,
+        }
+    }
+
+    /// Clears poisoning on the map lock and every entry, returning what was
+    /// poisoned beforehand. The panic that poisoned an entry may have left
+    /// its value half written, so healed entries are reported as mutated.
+    pub fn heal(&self) -> PoisonReport {
+        let report = self.health();
+        self.0.map.unpoison();
+        let healed: Vec<_> = self
+            .rlock()
+            .values()
+            .filter(|entry| entry.is_poisoned())
+            .cloned()
+            .collect();
+        for entry in &healed {
+            entry.clear_poison();
+        }
+        self.notify(healed.into_iter().map(Change::Mutated));
+        report
+    }
+
+    /// Like `insert`, also indexing the new entry under `id`. The id follows
+    /// the entry through renames and `replace`, and is released when the
+    /// entry leaves the registry. It may not be bound to another live entry.
+    pub fn insert_with_id(&self, id: u64, value: T) -> Result<Option<Entry<T>>, InsertIdError> {
+        self.validate(&value.name_ref())?;
+        let (key, entry) = self.keyed_entry(value);
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return Err(InsertError::Sealed.into());
+        }
+        let ids = self.ids_mut();
+        if let Some(bound) = ids.entry(id) {
+            let replaced = map.get(&key).is_some_and(|current| current.ptr_eq(&bound));
+            if self.is_live(&map, &bound) && !replaced {
+                return Err(InsertIdError::DuplicateId(id));
+            }
+        }
+        drop(ids);
+
+        let (previous, evicted) = self.place(&mut map, key, &entry)?;
+        let mut ids = self.ids_mut();
+        if let Some(bound) = ids.entry(id) {
+            ids.forget(&bound);
+        }
+        ids.by_id.remove(&id);
+        ids.bind(id, &entry);
+        drop((ids, map));
+        let mut changes = Vec::with_capacity(2);
+        self.settle(entry, previous.clone(), evicted, &mut changes);
+        self.notify(changes);
+        Ok(previous)
+    }
+
+    pub fn get_by_id(&self, id: u64) -> Option<Entry<T>> {
+        let map = self.rlock();
+        let entry = self.ids().entry(id)?;
+        self.is_live(&map, &entry).then_some(entry)
+    }
+
+    pub fn id_of(&self, name: &str) -> Option<u64> {
+        let map = self.rlock();
+        let entry = self.live(&map, name)?;
+        self.ids().id(entry)
+    }
+
+    // entries can also leave through `lock` or bulk operations that do not
+    // update the index, so lookups confirm the entry is still registered
+    fn is_live(&self, map: &HashMap<Arc<str>, Entry<T>, S>, entry: &Entry<T>) -> bool {
+        map.get(&self.stored_key(entry.cached_name()))
+            .is_some_and(|current| current.ptr_eq(entry))
+    }
+
+    /// Inserts `value` into `namespace`, keyed by its name. Namespaces are
+    /// isolated from one another; the plain API, `len` and iteration only see
+    /// `DEFAULT_NAMESPACE`.
+    pub fn insert_in(&self, namespace: &str, value: T) -> Result<Option<Entry<T>>, InsertError> {
+        if namespace == DEFAULT_NAMESPACE {
+            return self.insert(value);
+        }
+        let separator = self.0.rules.separator;
+        if namespace.contains(separator) {
+            return Err(InsertError::InvalidName {
+                name: namespace.to_string(),
+                reason: format!("namespace contains the separator `{separator}`"),
+            });
+        }
+        self.validate(&value.name_ref())?;
+
+        let (key, entry) = self.keyed_entry(value);
+        let mut namespaces = self.namespaces_mut();
+        if self.is_sealed() {
+            return Err(InsertError::Sealed);
+        }
+        Ok(namespaces
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(key, entry))
+    }
+
+    pub fn get_in(&self, namespace: &str, name: &str) -> Option<Entry<T>> {
+        if namespace == DEFAULT_NAMESPACE {
+            return self.find(name);
+        }
+        self.live(self.namespaces().get(namespace)?, name).cloned()
+    }
+
+    /// Looks up a `"namespace/name"` path, split at the first separator. A
+    /// path without one is looked up in `DEFAULT_NAMESPACE`.
+    pub fn get_path(&self, path: &str) -> Option<Entry<T>> {
+        match path.split_once(self.0.rules.separator) {
+            Some((namespace, name)) => self.get_in(namespace, name),
+            None => self.find(path),
+        }
+    }
+
+    pub fn names_in(&self, namespace: &str) -> Vec<String> {
+        if namespace == DEFAULT_NAMESPACE {
+            return self.keys().iter().map(|key| key.to_string()).collect();
+        }
+        let pairs: Vec<(Arc<str>, Entry<T>)> = match self.namespaces().get(namespace) {
+            Some(map) => map
+                .iter()
+                .map(|(key, entry)| (key.clone(), entry.clone()))
+                .collect(),
+            None => return Vec::new(),
+        };
+        pairs
+            .into_iter()
+            .map(|(key, entry)| self.shown_key(&key, &entry).into_owned())
+            .collect()
+    }
+
+    /// Namespaces holding at least one entry, besides `DEFAULT_NAMESPACE`.
+    pub fn namespace_names(&self) -> Vec<String> {
+        self.namespaces()
+            .iter()
+            .filter(|(_, map)| !map.is_empty())
+            .map(|(namespace, _)| namespace.clone())
+            .collect()
+    }
+
+    /// Detaches every entry in `namespace` at once, without touching the
+    /// others. A sealed registry detaches nothing.
+    pub fn remove_namespace(&self, namespace: &str) -> Vec<Entry<T>> {
+        if namespace == DEFAULT_NAMESPACE {
+            return self.drain().map(|(_, entry)| entry).collect();
+        }
+        let mut namespaces = self.namespaces_mut();
+        if self.is_sealed() {
+            return Vec::new();
+        }
+        let removed = namespaces.remove(namespace);
+        removed
+            .map(|map| map.into_values().collect())
+            .unwrap_or_default()
+    }
+
+    fn namespaces(&self) -> RwLockReadGuard<'_, Namespaces<T, Arc<str>, S>> {
+        self.0.namespaces.shared()
+    }
+
+    fn namespaces_mut(&self) -> RwLockWriteGuard<'_, Namespaces<T, Arc<str>, S>> {
+        self.0.namespaces.exclusive()
+    }
+
+    /// A fresh entry for `value` to store under `key`, sharing the key's
+    /// allocation as its cached name when the two match.
+    fn entry_at(&self, key: &Arc<str>, value: T) -> Entry<T> {
+        let name = if **key == *value.name_ref() {
+            Arc::clone(key)
+        } else {
+            Arc::from(&*value.name_ref())
+        };
+        Entry::named(value, name, Arc::clone(&self.0.clock))
+    }
+
+    // `place` for `value` under a key the caller found free: the new entry
+    // and whatever the bound evicted for it, or the value back if the bound
+    // refused it
+    fn place_new(
+        &self,
+        map: &mut Map<T, Arc<str>, S>,
+        key: Arc<str>,
+        value: T,
+    ) -> Result<PlacedNew<T>, (InsertError, T)> {
+        let entry = self.entry_at(&key, value);
+        match self.place(map, key, &entry) {
+            Ok((_, evicted)) => Ok((entry, evicted)),
+            Err(err) => Err((
+                err,
+                entry.into_inner().expect("a refused entry is unshared"),
+            )),
+        }
+    }
+
+    // a factory's value must be keyed where it was asked for
+    fn check_factory(&self, name: &str, key: &Arc<str>, value: &T) -> Result<(), InsertError> {
+        if self.stored_key(value.key()) == *key {
+            return Ok(());
+        }
+        self.trace("get_or_insert_with", Some(name), "mismatched");
+        Err(InsertError::Mismatch {
+            name: name.to_string(),
+            found: value.name(),
+        })
+    }
+
+    // `settle` and `notify` for an entry `place_new` put into a free slot
+    fn settle_new(&self, entry: Entry<T>, evicted: Option<Entry<T>>) {
+        let mut changes = Vec::with_capacity(2);
+        self.settle(entry, None, evicted, &mut changes);
+        self.notify(changes);
+    }
+
+    /// How `key` is reported back: as stored, or in the value's own casing
+    /// in case-insensitive registries.
+    fn shown_key<'k>(&self, key: &'k str, entry: &Entry<T>) -> Cow<'k, str> {
+        if self.0.rules.show_names {
+            Cow::Owned(entry.name())
+        } else {
+            Cow::Borrowed(key)
+        }
+    }
+
+    fn shown_pair(&self, (key, entry): (Arc<str>, Entry<T>)) -> (String, Entry<T>) {
+        (self.shown_key(&key, &entry).into_owned(), entry)
+    }
+
+    /// The key `entry` belongs under if its name no longer matches `key`.
+    fn moved_key(&self, key: &str, entry: &Entry<T>) -> Option<Arc<str>> {
+        if self.0.rules.normalizer.is_none() {
+            return (!entry.name_is(key)).then(|| entry.cached_name());
+        }
+        Some(self.stored_key(entry.cached_name())).filter(|moved| **moved != *key)
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + Clone,
+    S: BuildHasher + Default,
+{
+    /// Clones every value under one hold of the map lock, so the snapshot is
+    /// consistent across entries. Poisoned entries are recovered and listed
+    /// in `RegistrySnapshot::recovered` rather than failing the snapshot.
+    pub fn snapshot(&self) -> RegistrySnapshot<T> {
+        let map = self.rlock();
+        let mut recovered = Vec::new();
+        let entries = map
+            .iter()
+            .map(|(key, entry)| {
+                let name = self.shown_key(key, entry).into_owned();
+                if entry.is_poisoned() {
+                    recovered.push(name.clone());
+                }
+                (key.clone(), (name, entry.read()))
+            })
+            .collect();
+        recovered.sort();
+
+        RegistrySnapshot {
+            entries,
+            recovered,
+            rules: self.0.rules.clone(),
+        }
+    }
+
+    /// Copies every entry of `other` into `self`, resolving name conflicts with
+    /// `policy` (`Resolve` receives the existing value first). `other` is
+    /// snapshotted and released before `self` is locked, so two registries
+    /// merging into each other concurrently cannot deadlock. New names are
+    /// inserted as `insert` would, so a full `RejectNew` registry skips them.
+    /// Conflicting entries are updated in place after `self` is released,
+    /// and frozen ones are left alone; once `self` is sealed, that is all a
+    /// merge does.
+    pub fn merge(&self, other: &Self, policy: MergePolicy<T>) {
+        let incoming = other.cloned_values();
+
+        let mut map = self.lock();
+        let sealed = self.is_sealed();
+        let mut inserted = Vec::new();
+        let mut conflicts = Vec::new();
+        for (name, value) in incoming {
+            match map.entry(self.stored_key(name)) {
+                hash_map::Entry::Vacant(_) if sealed => {}
+                hash_map::Entry::Vacant(slot) => {
+                    let key = slot.into_key();
+                    if let Ok(placed) = self.place_new(&mut map, key, value) {
+                        inserted.push(placed);
+                    }
+                }
+                hash_map::Entry::Occupied(_) if matches!(policy, MergePolicy::KeepExisting) => {}
+                hash_map::Entry::Occupied(slot) => conflicts.push((slot.get().clone(), value)),
+            }
+        }
+        drop(map);
+        let mut changes = Vec::new();
+        for (entry, evicted) in inserted {
+            self.settle(entry, None, evicted, &mut changes);
+        }
+        for (entry, value) in conflicts {
+            let merged = entry.checked_mutate(|inner| match policy {
+                MergePolicy::Resolve(resolve) => *inner = resolve(inner, &value),
+                _ => *inner = value,
+            });
+            if merged.is_ok() {
+                changes.push(Change::Mutated(entry));
+            }
+        }
+        self.notify(changes);
+    }
+
+    pub fn get_cloned(&self, name: &str) -> Option<T> {
+        self.find(name).map(|entry| entry.read())
+    }
+
+    #[deprecated(since = "0.2.0", note = "use `update` with an owned value")]
+    pub fn update_by_ref(&self, entry: &mut T) -> Result<(), NotFound> {
+        self.update(entry.clone())
+    }
+
+    /// Builds an independent registry with freshly cloned values. Unlike
+    /// `Clone`, which hands out another handle to the same shared map, nothing
+    /// done to the copy is visible in `self` and vice versa.
+    pub fn deep_clone(&self) -> Self {
+        let map = self
+            .cloned_values()
+            .into_iter()
+            .map(|(key, value)| {
+                let entry = self.entry_at(&key, value);
+                (key, entry)
+            })
+            .collect();
+        Self::from_map(map, Arc::clone(&self.0.clock), self.0.rules.clone())
+    }
+
+    fn cloned_values(&self) -> HashMap<Arc<str>, T> {
+        self.snapshot_pairs()
+            .into_iter()
+            .map(|(name, entry)| {
+                let value = entry.read();
+                (name, value)
+            })
+            .collect()
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + SetName,
+    S: BuildHasher + Default,
+{
+    /// Moves the entry under `old` to `new` and renames the inner value, all
+    /// under one write lock so readers never observe the entry missing.
+    pub fn rename(&self, old: &str, new: &str) -> Result<(), RenameError> {
+        self.validate(new).map_err(|err| match err {
+            InsertError::Sealed => RenameError::Sealed,
+            err => RenameError::Rejected(err),
+        })?;
+        let (old_key, new_key) = (self.lookup(old), self.lookup(new));
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return Err(RenameError::Sealed);
+        }
+        let Some(entry) = map.get(&*old_key).cloned() else {
+            return Err(RenameError::NotFound(old.to_string()));
+        };
+        let frozen = |_| RenameError::Frozen(old.to_string());
+        if *old_key == *new_key {
+            // only the casing can differ, which the key does not record
+            if old != new {
+                entry
+                    .checked_mutate(|inner| inner.set_name(new))
+                    .map_err(frozen)?;
+            }
+            return Ok(());
+        }
+        if map.contains_key(&*new_key) {
+            return Err(RenameError::Occupied(new.to_string()));
+        }
+
+        // renamed before the key moves, so a frozen entry stays registered
+        let old_name = entry.cached_name();
+        entry
+            .checked_mutate(|inner| inner.set_name(new))
+            .map_err(frozen)?;
+        map.remove(&*old_key);
+        map.insert(self.stored_key(entry.cached_name()), entry.clone());
+        drop(map);
+        self.notify([Change::Renamed(old_name, entry)]);
+        Ok(())
+    }
+
+    /// Like `mutate`, but a closure that changes the value's name has the name
+    /// restored and the call fails, so keys never drift from names.
+    pub fn mutate_strict<F, R>(&self, key: &str, f: F) -> Result<R, MutateError>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let entry = self
+            .find(key)
+            .ok_or_else(|| MutateError::NotFound(key.to_string()))?;
+        let mut guard = entry.lock();
+        if entry.is_frozen() {
+            return Err(MutateError::Frozen(key.to_string()));
+        }
+        let before = guard.name();
+        let result = f(&mut guard);
+
+        if guard.name_ref() != before {
+            let after = guard.name();
+            guard.set_name(&before);
+            return Err(MutateError::Renamed {
+                from: before,
+                to: after,
+            });
+        }
+        drop(guard);
+        self.notify([Change::Mutated(entry)]);
+        Ok(result)
+    }
+
+    /// Exchanges the values registered under `a` and `b`, keeping each value's
+    /// name in line with the key it ends up under. Entries are locked in
+    /// address order, as in `mutate_pair`, so concurrent swaps and pair
+    /// mutations of the same entries cannot deadlock.
+    pub fn swap(&self, a: &str, b: &str) -> bool {
+        let map = self.rlock();
+        let (Some(first_entry), Some(second_entry)) =
+            (self.live(&map, a).cloned(), self.live(&map, b).cloned())
+        else {
+            return false;
+        };
+
+        if first_entry.ptr_eq(&second_entry) {
+            return true;
+        }
+
+        {
+            // as in `mutate_pair`, the map is held until both entries are
+            // locked
+            let (mut first_guard, mut second_guard) = first_entry.lock_pair(&second_entry);
+            drop(map);
+            let (first_name, second_name) = (first_guard.name(), second_guard.name());
+            std::mem::swap(&mut *first_guard, &mut *second_guard);
+            first_guard.set_name(&first_name);
+            second_guard.set_name(&second_name);
+        }
+        self.notify([first_entry, second_entry].map(Change::Mutated));
+        true
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + Clone + PartialEq,
+    S: BuildHasher + Default,
+{
+    /// Compares `self` against `other`, treating `other` as the desired state.
+    /// Both sides are cloned out first, so the diff stays valid once the locks
+    /// are released.
+    pub fn diff(&self, other: &Self) -> RegistryDiff<T> {
+        let current = self.cloned_values();
+        let mut desired = other.cloned_values();
+
+        let mut diff = RegistryDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        };
+        for (name, value) in current {
+            match desired.remove(&name) {
+                None => diff.removed.push((name.to_string(), value)),
+                Some(incoming) if incoming != value => {
+                    diff.changed.push((name.to_string(), value, incoming))
+                }
+                Some(_) => {}
+            }
+        }
+        diff.added.extend(
+            desired
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value)),
+        );
+
+        diff.added.sort_by(|a, b| a.0.cmp(&b.0));
+        diff.removed.sort_by(|a, b| a.0.cmp(&b.0));
+        diff.changed.sort_by(|a, b| a.0.cmp(&b.0));
+        diff
+    }
+
+    /// `diff` in the form `apply_patch` takes.
+    pub fn diff_serializable(&self, other: &Self) -> RegistryPatch<T> {
+        self.diff(other).into()
+    }
+
+    /// Applies `patch`. Its insertions and removals land under one write
+    /// lock, so readers see all of them or none; values of entries that stay
+    /// registered are overwritten once that lock is released. An operation
+    /// whose target already holds the patch's result is left alone and is
+    /// not a conflict; one whose target is neither that nor the patch's base
+    /// is handled by `policy`, as is one whose target is re-registered while
+    /// the patch is checked.
+    pub fn apply_patch(
+        &self,
+        patch: RegistryPatch<T>,
+        policy: ConflictPolicy,
+    ) -> Result<ApplyReport, PatchError> {
+        let ops = patch
+            .added
+            .into_iter()
+            .map(|value| (None, Some(value)))
+            .chain(patch.removed.into_iter().map(|base| (Some(base), None)))
+            .chain(
+                patch
+                    .changed
+                    .into_iter()
+                    .map(|change| (Some(change.base), Some(change.value))),
+            );
+
+        let map = self.rlock();
+        if self.is_sealed() {
+            return Err(PatchError::Sealed);
+        }
+        let mut report = ApplyReport::default();
+        let mut writes = Vec::new();
+        for (base, desired) in ops {
+            let name = Arc::from(
+                &*base
+                    .as_ref()
+                    .or(desired.as_ref())
+                    .expect("one side is set")
+                    .name_ref(),
+            );
+            let key = self.stored_key(name);
+            let holds = |expected: Option<&T>| match (map.get(&key), expected) {
+                (Some(entry), Some(expected)) => entry.with(|local| local == expected),
+                (local, expected) => local.is_none() && expected.is_none(),
+            };
+            if holds(desired.as_ref()) {
+                continue;
+            }
+            if !holds(base.as_ref()) {
+                let name = key.to_string();
+                report
+                    .conflicts
+                    .push(match (&base, map.contains_key(&key)) {
+                        (None, _) => PatchConflict::Exists(name),
+                        (Some(_), false) => PatchConflict::Missing(name),
+                        (Some(_), true) => PatchConflict::Diverged(name),
+                    });
+                if policy != ConflictPolicy::Force {
+                    continue;
+                }
+            }
+            let seen = map.get(&key).cloned();
+            writes.push((key, seen, desired));
+        }
+        drop(map);
+        if policy == ConflictPolicy::Fail && !report.conflicts.is_empty() {
+            return Err(PatchError::Conflicts(report.conflicts));
+        }
+
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return Err(PatchError::Sealed);
+        }
+        let (writes, moved): (Vec<_>, Vec<_>) =
+            writes
+                .into_iter()
+                .partition(|(key, seen, _)| match (seen, map.get(key)) {
+                    (Some(seen), Some(current)) => seen.ptr_eq(current),
+                    (seen, current) => seen.is_none() && current.is_none(),
+                });
+        report.conflicts.extend(
+            moved
+                .into_iter()
+                .map(|(key, _, _)| PatchConflict::Diverged(key.to_string())),
+        );
+        if policy == ConflictPolicy::Fail && !report.conflicts.is_empty() {
+            return Err(PatchError::Conflicts(report.conflicts));
+        }
+
+        let mut changes = Vec::with_capacity(writes.len());
+        let mut overwrites = Vec::new();
+        for (key, seen, desired) in writes {
+            match (seen, desired) {
+                (Some(entry), Some(value)) => overwrites.push((entry, value)),
+                (Some(_), None) => {
+                    let removed = map.remove(&key).expect("checked above");
+                    self.ids_mut().forget(&removed);
+                    changes.push(Change::Removed(removed));
+                    report.applied += 1;
+                }
+                (None, Some(value)) => {
+                    let entry = self.entry_at(&key, value);
+                    map.insert(key, entry.clone());
+                    changes.push(Change::Inserted(entry));
+                    report.applied += 1;
+                }
+                (None, None) => {}
+            }
+        }
+        drop(map);
+        for (entry, value) in overwrites {
+            entry.set(value);
+            changes.push(Change::Mutated(entry));
+            report.applied += 1;
+        }
+        self.notify(changes);
+        Ok(report)
+    }
+}
+
+impl<T, S> FromIterator<T> for NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let map = iter
+            .into_iter()
+            .map(|value| {
+                let name: Arc<str> = Arc::from(&*value.name_ref());
+                (
+                    Arc::clone(&name),
+                    Entry::named(value, name, clock::system()),
+                )
+            })
+            .collect();
+        Self::from_map(map, clock::system(), KeyRules::default())
+    }
+}
+
+impl<T, S> Extend<T> for NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName,
+    S: BuildHasher + Default,
+{
+    /// Inserts every value as `insert` does, overwriting entries with the
+    /// same name; a sealed registry drops them instead, as does a full
+    /// `RejectNew` one those with new names.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut map = self.lock();
+        if self.is_sealed() {
+            return;
+        }
+        let placed: Vec<_> = iter
+            .into_iter()
+            .filter_map(|value| {
+                let (key, entry) = self.keyed_entry(value);
+                let (previous, evicted) = self.place(&mut map, key, &entry).ok()?;
+                Some((entry, previous, evicted))
+            })
+            .collect();
+        drop(map);
+        let mut changes = Vec::with_capacity(placed.len());
+        for (entry, previous, evicted) in placed {
+            self.settle(entry, previous, evicted, &mut changes);
+        }
+        self.notify(changes);
+    }
+}
+
+impl<T, K, S> Default for NamedRegistry<T, K, S>
+where
+    T: HasName + HasKey<K>,
+    K: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    impl SetName for InnerMock {
+        fn set_name(&mut self, name: &str) {
+            self.name = name.to_string();
+        }
+    }
+
+    #[rstest]
+    fn test_insert_and_get() {
+        let reg = NamedRegistry::new();
+        let item = InnerMock {
+            name: "alpha".into(),
+            value: 10,
+        };
+
+        reg.insert(item.clone()).unwrap();
+        let fetched = reg.get("alpha").unwrap().lock().clone();
+
+        assert_eq!(fetched, item);
+        assert!(reg.contains("alpha"));
+    }
+
+    #[rstest]
+    fn test_insert_returns_previous_entry() {
+        let reg = NamedRegistry::new();
+
+        let first = reg
+            .insert(InnerMock {
+                name: "alpha".into(),
+                value: 1,
+            })
+            .unwrap();
+        let held = reg.get("alpha").unwrap();
+        let second = reg
+            .insert(InnerMock {
+                name: "alpha".into(),
+                value: 2,
+            })
+            .unwrap();
+
+        assert!(first.is_none());
+        let previous = second.unwrap();
+        assert!(previous.arc().ptr_eq(&held.arc()));
+        assert_eq!(previous.lock().value, 1);
+        assert_eq!(held.lock().value, 1);
+        assert_eq!(reg.get("alpha").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_mutate_existing_entry() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "beta".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let ok = reg.mutate("beta", |v| v.value += 41);
+
+        assert!(ok.is_some());
+        let val = reg.get("beta").unwrap().lock().value;
+        assert_eq!(val, 42);
+    }
+
+    #[rstest]
+    fn test_mutate_nonexistent_entry_returns_none() {
+        let reg = NamedRegistry::<InnerMock>::new();
+        let result = reg.mutate("nope", |_| {});
+        assert!(result.is_none());
+    }
+
+    #[rstest]
+    fn test_mutate_returns_closure_result() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "beta".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let summary = reg.mutate("beta", |v| {
+            v.value *= 3;
+            format!("{}={}", v.name, v.value)
+        });
+
+        assert_eq!(summary.as_deref(), Some("beta=3"));
+    }
+
+    #[rstest]
+    fn test_update_replaces_existing_value() {
+        let reg = NamedRegistry::new();
+
+        reg.insert(InnerMock {
+            name: "gamma".into(),
+            value: 5,
+        })
+        .unwrap();
+        let held = reg.get("gamma").unwrap();
+        let result = reg.update(InnerMock {
+            name: "gamma".into(),
+            value: 100,
+        });
+
+        assert_eq!(result, Ok(()));
+        let val = reg.get("gamma").unwrap().lock().value;
+        assert_eq!(val, 100);
+        assert_eq!(held.lock().value, 100);
+    }
+
+    #[rstest]
+    fn test_update_missing_name() {
+        let reg = NamedRegistry::new();
+
+        let result = reg.update(InnerMock {
+            name: "gamma".into(),
+            value: 100,
+        });
+
+        assert_eq!(result, Err(NotFound("gamma".into())));
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_update_swap_returns_previous() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "gamma".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let mut value = InnerMock {
+            name: "gamma".into(),
+            value: 2,
+        };
+        assert_eq!(reg.update_swap(&mut value), Ok(()));
+
+        assert_eq!(value.value, 1);
+        assert_eq!(reg.get("gamma").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    #[allow(deprecated)]
+    fn test_update_by_ref_still_clones() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "gamma".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let mut value = InnerMock {
+            name: "gamma".into(),
+            value: 2,
+        };
+        assert_eq!(reg.update_by_ref(&mut value), Ok(()));
+
+        assert_eq!(value.value, 2);
+        assert_eq!(reg.get("gamma").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_registry_without_clone() {
+        #[derive(Debug)]
+        struct Unclonable(String);
+
+        impl HasName for Unclonable {
+            fn name(&self) -> String {
+                self.0.clone()
+            }
+        }
+
+        let reg = NamedRegistry::new();
+        reg.insert(Unclonable("a".into())).unwrap();
+
+        assert_eq!(reg.update(Unclonable("a".into())), Ok(()));
+        assert_eq!(reg.take("a").unwrap().0, "a");
+    }
+
+    #[rstest]
+    fn test_update_or_insert() {
+        let reg = NamedRegistry::new();
+        let value = InnerMock {
+            name: "gamma".into(),
+            value: 1,
+        };
+
+        assert_eq!(reg.update_or_insert(value.clone()), UpsertOutcome::Inserted);
+        assert_eq!(reg.update_or_insert(value), UpsertOutcome::Updated);
+        assert_eq!(reg.len(), 1);
+    }
+
+    #[rstest]
+    fn test_contains_and_get() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "delta".into(),
+            value: 7,
+        })
+        .unwrap();
+
+        assert!(reg.contains("delta"));
+        assert!(!reg.contains("unknown"));
+
+        let val = reg.get("delta").unwrap().lock().value;
+        assert_eq!(val, 7);
+    }
+
+    #[rstest]
+    fn test_remove_returns_entry() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "kappa".into(),
+            value: 3,
+        })
+        .unwrap();
+
+        let removed = reg.remove("kappa").unwrap();
+
+        assert_eq!(removed.lock().value, 3);
+        assert!(!reg.contains("kappa"));
+        assert!(reg.remove("kappa").is_none());
+    }
+
+    #[rstest]
+    fn test_remove_nonexistent_entry_returns_none() {
+        let reg = NamedRegistry::<InnerMock>::new();
+        assert!(reg.remove("nope").is_none());
+    }
+
+    #[rstest]
+    fn test_remove_keeps_clones_alive() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "lambda".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let held = reg.get("lambda").unwrap();
+        reg.remove("lambda");
+
+        held.mutate(|v| v.value += 1);
+        assert_eq!(held.lock().value, 2);
+        assert!(reg.get("lambda").is_none());
+    }
+
+    #[rstest]
+    fn test_remove_while_entry_locked() {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "mu".into(),
+            value: 0,
+        })
+        .unwrap();
+
+        let entry = reg.get("mu").unwrap();
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let holder = thread::spawn(move || {
+            let mut guard = entry.lock();
+            locked_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            guard.value = 7;
+        });
+
+        locked_rx.recv().unwrap();
+        let removed = reg.remove("mu").unwrap();
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+
+        assert_eq!(removed.lock().value, 7);
+    }
+
+    #[rstest]
+    fn test_concurrent_remove_and_get() {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        for i in 0..100 {
+            reg.insert(InnerMock {
+                name: format!("item{i}"),
+                value: i,
+            })
+            .unwrap();
+        }
+
+        let remover = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                (0..100)
+                    .filter(|i| reg.remove(format!("item{i}").as_str()).is_some())
+                    .count()
+            })
+        };
+        let reader = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                for i in 0..100 {
+                    if let Some(entry) = reg.get(format!("item{i}").as_str()) {
+                        assert_eq!(entry.lock().value, i);
+                    }
+                }
+            })
+        };
+
+        assert_eq!(remover.join().unwrap(), 100);
+        reader.join().unwrap();
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_take_returns_inner_value() {
+        let reg = NamedRegistry::new();
+        let item = InnerMock {
+            name: "nu".into(),
+            value: 11,
+        };
+        reg.insert(item.clone()).unwrap();
+
+        assert_eq!(reg.take("nu"), Ok(item));
+        assert!(!reg.contains("nu"));
+    }
+
+    #[rstest]
+    fn test_take_nonexistent_entry() {
+        let reg = NamedRegistry::<InnerMock>::new();
+        assert_eq!(reg.take("nope"), Err(TakeError::NotFound("nope".into())));
+    }
+
+    #[rstest]
+    fn test_take_shared_entry_is_kept() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "xi".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let held = reg.get("xi").unwrap();
+
+        assert_eq!(reg.take("xi"), Err(TakeError::Shared("xi".into())));
+        assert!(reg.contains("xi"));
+
+        held.mutate(|v| v.value = 2);
+        assert_eq!(reg.get("xi").unwrap().lock().value, 2);
+
+        drop(held);
+        assert_eq!(reg.take("xi").unwrap().value, 2);
+    }
+
+    #[rstest]
+    fn test_take_is_reported_and_undone_if_a_hook_keeps_the_entry() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("xi", 1)).unwrap();
+        reg.insert(mock("pi", 2)).unwrap();
+        let events = reg.subscribe();
+        let kept = Arc::new(Mutex::new(Vec::new()));
+        {
+            let kept = Arc::clone(&kept);
+            reg.on_remove(move |name, entry| {
+                if name == "pi" {
+                    kept.acquire().push(entry.clone());
+                }
+            });
+        }
+
+        assert_eq!(reg.take("xi").unwrap().value, 1);
+        assert_eq!(reg.take("pi"), Err(TakeError::Shared("pi".into())));
+
+        assert!(reg.get("pi").unwrap().ptr_eq(&kept.acquire()[0]));
+        let removed = |name: &str| RegistryEvent::Removed { name: name.into() };
+        let inserted = |name: &str| RegistryEvent::Inserted { name: name.into() };
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [removed("xi"), removed("pi"), inserted("pi")]
+        );
+    }
+
+    #[rstest]
+    fn test_take_ignores_weak_references() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "omicron".into(),
+            value: 4,
+        })
+        .unwrap();
+
+        let weak = reg.get("omicron").unwrap().weak();
+
+        assert_eq!(reg.take("omicron").unwrap().value, 4);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[rstest]
+    fn test_weak_entry_outlives_registry_copy() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "omicron".into(),
+            value: 4,
+        })
+        .unwrap();
+        let held = reg.get("omicron").unwrap();
+        let weak = held.weak();
+
+        reg.remove("omicron");
+        let upgraded = weak.upgrade().unwrap();
+        assert_eq!(upgraded.name(), "omicron");
+        assert_eq!(upgraded.lock().value, 4);
+
+        drop((held, upgraded));
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[rstest]
+    fn test_take_poisoned_entry() {
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "pi".into(),
+            value: 8,
+        })
+        .unwrap();
+
+        let entry = reg.get("pi").unwrap();
+        let _ = thread::spawn(move || {
+            entry.mutate(|_| panic!("poison"));
+        })
+        .join();
+
+        assert_eq!(reg.take("pi").unwrap().value, 8);
+    }
+
+    #[rstest]
+    fn test_len_and_is_empty() {
+        let reg = NamedRegistry::new();
+        assert!(reg.is_empty());
+        assert_eq!(reg.len(), 0);
+
+        reg.insert(InnerMock {
+            name: "rho".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.insert(InnerMock {
+            name: "sigma".into(),
+            value: 2,
+        })
+        .unwrap();
+        assert!(!reg.is_empty());
+        assert_eq!(reg.len(), 2);
+
+        reg.remove("rho");
+        assert_eq!(reg.len(), 1);
+    }
+
+    #[rstest]
+    fn test_len_after_concurrent_inserts_and_removes() {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let reg = reg.clone();
+                thread::spawn(move || {
+                    for i in 0..50 {
+                        reg.insert(InnerMock {
+                            name: format!("t{t}-{i}"),
+                            value: i,
+                        })
+                        .unwrap();
+                    }
+                    for i in (0..50).step_by(2) {
+                        reg.remove(format!("t{t}-{i}").as_str());
+                    }
+                })
+            })
+            .collect();
+
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        assert_eq!(reg.len(), 100);
+    }
+
+    #[rstest]
+    fn test_clear_returns_removed_entries() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "tau".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.insert(InnerMock {
+            name: "upsilon".into(),
+            value: 2,
+        })
+        .unwrap();
+
+        let held = reg.get("tau").unwrap();
+        let mut removed: Vec<_> = reg.clear().iter().map(|e| e.lock().value).collect();
+        removed.sort();
+
+        assert_eq!(removed, vec![1, 2]);
+        assert_eq!(reg.len(), 0);
+
+        held.mutate(|v| v.value = 10);
+        assert_eq!(held.lock().value, 10);
+    }
+
+    #[rstest]
+    fn test_clear_and_drop() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "phi".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let held = reg.get("phi").unwrap();
+        reg.clear_and_drop();
+
+        assert!(reg.is_empty());
+        assert_eq!(held.lock().value, 1);
+    }
+
+    #[rstest]
+    fn test_keys_snapshot() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "chi".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.insert(InnerMock {
+            name: "psi".into(),
+            value: 2,
+        })
+        .unwrap();
+
+        let mut keys = reg.keys();
+        keys.sort();
+
+        reg.insert(InnerMock {
+            name: "omega".into(),
+            value: 3,
+        })
+        .unwrap();
+
+        assert_eq!(keys, vec![Arc::<str>::from("chi"), Arc::from("psi")]);
+        assert_eq!(reg.keys().len(), 3);
+    }
+
+    #[rstest]
+    fn test_keys_while_entries_mutate() {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        for i in 0..10 {
+            reg.insert(InnerMock {
+                name: format!("k{i}"),
+                value: 0,
+            })
+            .unwrap();
+        }
+
+        let writer = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    for i in 0..10 {
+                        reg.mutate(format!("k{i}").as_str(), |v| v.value += 1);
+                    }
+                }
+            })
+        };
+
+        for _ in 0..100 {
+            assert_eq!(reg.keys().len(), 10);
+        }
+        writer.join().unwrap();
+    }
+
+    #[rstest]
+    fn test_values_mutated_from_threads() {
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        for i in 0..8 {
+            reg.insert(InnerMock {
+                name: format!("v{i}"),
+                value: i,
+            })
+            .unwrap();
+        }
+
+        let handles: Vec<_> = reg
+            .values()
+            .into_iter()
+            .map(|entry| thread::spawn(move || entry.mutate(|v| v.value += 100)))
+            .collect();
+
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        for i in 0..8 {
+            assert_eq!(
+                reg.get(format!("v{i}").as_str()).unwrap().lock().value,
+                i + 100
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_values_does_not_hold_map_lock() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "v".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        for entry in reg.values() {
+            let _guard = entry.lock();
+            reg.insert(InnerMock {
+                name: "w".into(),
+                value: 2,
+            })
+            .unwrap();
+        }
+
+        assert_eq!(reg.len(), 2);
+    }
+
+    #[rstest]
+    fn test_iter_yields_snapshot() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "a".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.insert(InnerMock {
+            name: "b".into(),
+            value: 2,
+        })
+        .unwrap();
+
+        let iter = reg.iter();
+        reg.insert(InnerMock {
+            name: "c".into(),
+            value: 3,
+        })
+        .unwrap();
+
+        let mut pairs: Vec<_> = iter.map(|(name, e)| (name, e.lock().value)).collect();
+        pairs.sort();
+
+        assert_eq!(pairs, vec![(Arc::<str>::from("a"), 1), (Arc::from("b"), 2)]);
+    }
+
+    #[rstest]
+    fn test_for_each_locks_entries() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "a".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.insert(InnerMock {
+            name: "b".into(),
+            value: 2,
+        })
+        .unwrap();
+
+        let mut total = 0;
+        reg.for_each(|name, entry| {
+            assert_eq!(entry.lock().name, name);
+            total += entry.lock().value;
+        });
+
+        assert_eq!(total, 3);
+    }
+
+    #[rstest]
+    fn test_for_each_insert_does_not_deadlock() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "a".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        reg.for_each(|name, entry| {
+            reg.insert(InnerMock {
+                name: format!("{name}-copy"),
+                value: entry.lock().value,
+            })
+            .unwrap();
+        });
+
+        assert_eq!(reg.len(), 2);
+        assert_eq!(reg.get("a-copy").unwrap().lock().value, 1);
+    }
+
+    #[rstest]
+    fn test_retain_all() {
+        let reg = NamedRegistry::from_entries((0..5).map(|i| InnerMock {
+            name: format!("r{i}"),
+            value: i,
+        }));
+
+        assert_eq!(reg.retain(|_, _| true), 0);
+        assert_eq!(reg.len(), 5);
+    }
+
+    #[rstest]
+    fn test_retain_none() {
+        let reg = NamedRegistry::from_entries((0..5).map(|i| InnerMock {
+            name: format!("r{i}"),
+            value: i,
+        }));
+
+        assert_eq!(reg.retain(|_, _| false), 5);
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_retain_mutates_and_filters() {
+        let reg = NamedRegistry::from_entries((0..6).map(|i| InnerMock {
+            name: format!("r{i}"),
+            value: i,
+        }));
+
+        let removed = reg.retain(|_, v| {
+            v.value *= 10;
+            v.value % 20 == 0
+        });
+
+        assert_eq!(removed, 3);
+        let mut values: Vec<_> = reg.values().iter().map(|e| e.lock().value).collect();
+        values.sort();
+        assert_eq!(values, vec![0, 20, 40]);
+    }
+
+    #[rstest]
+    fn test_retain_panic_does_not_poison_map() {
+        let reg = NamedRegistry::from_entries((0..3).map(|i| InnerMock {
+            name: format!("r{i}"),
+            value: i,
+        }));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            reg.retain(|name, _| {
+                if name == "r1" {
+                    panic!("predicate failure");
+                }
+                true
+            })
+        }));
+
+        assert!(result.is_err());
+        reg.insert(InnerMock {
+            name: "r9".into(),
+            value: 9,
+        })
+        .unwrap();
+        assert!(reg.contains("r9"));
+        assert_eq!(reg.keys().len(), reg.len());
+    }
+
+    #[rstest]
+    fn test_drain_empties_registry() {
+        let reg = NamedRegistry::from_entries((0..4).map(|i| InnerMock {
+            name: format!("d{i}"),
+            value: i,
+        }));
+
+        let drained = reg.drain();
+        assert!(reg.is_empty());
+
+        let target = NamedRegistry::new();
+        for (name, entry) in drained {
+            assert_eq!(entry.lock().name, name);
+            target.insert(entry.lock().clone()).unwrap();
+        }
+        assert_eq!(target.len(), 4);
+    }
+
+    #[rstest]
+    fn test_drain_racing_inserts_are_not_lost() {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        let writer = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                for i in 0..500 {
+                    reg.insert(InnerMock {
+                        name: format!("d{i}"),
+                        value: i,
+                    })
+                    .unwrap();
+                }
+            })
+        };
+
+        let mut drained = Vec::new();
+        for _ in 0..50 {
+            drained.extend(reg.drain().map(|(name, _)| name));
+        }
+        writer.join().unwrap();
+        drained.extend(reg.keys().iter().map(|key| key.to_string()));
+
+        drained.sort();
+        let expected: Vec<_> = {
+            let mut names: Vec<_> = (0..500).map(|i| format!("d{i}")).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(drained, expected);
+    }
+
+    #[rstest]
+    fn test_get_or_insert_with_existing() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "g".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let entry = reg.get_or_insert_with("g", || unreachable!()).unwrap();
+        assert_eq!(entry.lock().value, 1);
+    }
+
+    #[rstest]
+    fn test_get_or_insert_with_missing() {
+        let reg = NamedRegistry::new();
+
+        let entry = reg
+            .get_or_insert_with("g", || InnerMock {
+                name: "g".into(),
+                value: 2,
+            })
+            .unwrap();
+        entry.mutate(|v| v.value += 1);
+
+        assert_eq!(reg.get("g").unwrap().lock().value, 3);
+    }
+
+    #[rstest]
+    fn test_get_or_insert_with_mismatched_name() {
+        let reg = NamedRegistry::new();
+        let built = reg.get_or_insert_with("g", || InnerMock {
+            name: "h".into(),
+            value: 0,
+        });
+
+        assert_eq!(
+            built.map(|_| ()),
+            Err(InsertError::Mismatch {
+                name: "g".into(),
+                found: "h".into(),
+            })
+        );
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_get_or_insert_with_matches_keys_not_names() {
+        let reg = case_insensitive();
+        let built = reg
+            .get_or_insert_with("alpha", || mock("ALPHA", 1))
+            .unwrap();
+
+        assert_eq!(built.name(), "ALPHA");
+        assert!(reg.get("Alpha").unwrap().ptr_eq(&built));
+    }
+
+    #[rstest]
+    fn test_get_or_insert_with_constructs_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Barrier;
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        let constructed = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(16));
+
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let reg = reg.clone();
+                let constructed = constructed.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    let entry = reg
+                        .get_or_insert_with("shared", || {
+                            constructed.fetch_add(1, Ordering::SeqCst);
+                            InnerMock {
+                                name: "shared".into(),
+                                value: 0,
+                            }
+                        })
+                        .unwrap();
+                    entry.mutate(|v| v.value += 1);
+                    entry
+                })
+            })
+            .collect();
+
+        let entries: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(constructed.load(Ordering::SeqCst), 1);
+        assert!(entries.iter().all(|e| e.arc().ptr_eq(&entries[0].arc())));
+        assert_eq!(reg.get("shared").unwrap().lock().value, 16);
+    }
+
+    #[rstest]
+    fn test_try_insert_vacant() {
+        let reg = NamedRegistry::new();
+
+        let entry = reg
+            .try_insert(InnerMock {
+                name: "t".into(),
+                value: 1,
+            })
+            .unwrap();
+        entry.mutate(|v| v.value = 2);
+
+        assert_eq!(reg.get("t").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_try_insert_occupied() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "t".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let err = reg
+            .try_insert(InnerMock {
+                name: "t".into(),
+                value: 2,
+            })
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "an entry named `t` already exists");
+        let TryInsertError::Occupied(err) = err else {
+            panic!("expected an occupied error");
+        };
+        assert_eq!(err.value.value, 2);
+        assert_eq!(err.entry.lock().value, 1);
+        assert_eq!(reg.get("t").unwrap().lock().value, 1);
+    }
+
+    #[rstest]
+    fn test_try_insert_race() {
+        use std::sync::Barrier;
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        let barrier = Arc::new(Barrier::new(2));
+
+        let handles: Vec<_> = (0..2)
+            .map(|i| {
+                let reg = reg.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    reg.try_insert(InnerMock {
+                        name: "t".into(),
+                        value: i,
+                    })
+                    .is_ok()
+                })
+            })
+            .collect();
+
+        let wins = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|won| *won)
+            .count();
+
+        assert_eq!(wins, 1);
+        assert_eq!(reg.len(), 1);
+    }
+
+    #[rstest]
+    fn test_rename_moves_entry() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "old".into(),
+            value: 1,
+        })
+        .unwrap();
+        let held = reg.get("old").unwrap();
+
+        assert_eq!(reg.rename("old", "new"), Ok(()));
+
+        assert!(!reg.contains("old"));
+        assert_eq!(reg.get("new").unwrap().lock().name, "new");
+        assert_eq!(held.lock().name, "new");
+    }
+
+    #[rstest]
+    fn test_rename_errors() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "a".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.insert(InnerMock {
+            name: "b".into(),
+            value: 2,
+        })
+        .unwrap();
+
+        assert_eq!(
+            reg.rename("missing", "c"),
+            Err(RenameError::NotFound("missing".into()))
+        );
+        assert_eq!(reg.rename("a", "b"), Err(RenameError::Occupied("b".into())));
+        assert_eq!(reg.rename("a", "a"), Ok(()));
+        assert_eq!(reg.get("a").unwrap().lock().value, 1);
+    }
+
+    #[rstest]
+    #[case::new_key("beta")]
+    #[case::same_key("ALPHA")]
+    fn test_rename_of_frozen_entry_keeps_it_registered(#[case] new: &str) {
+        let reg = case_insensitive();
+        reg.insert(mock("alpha", 1)).unwrap();
+        let _token = reg.get("alpha").unwrap().freeze();
+
+        assert_eq!(
+            reg.rename("alpha", new),
+            Err(RenameError::Frozen("alpha".into()))
+        );
+        assert_eq!(reg.len(), 1);
+        assert_eq!(reg.get("alpha").unwrap().name(), "alpha");
+    }
+
+    #[rstest]
+    fn test_rename_never_invisible() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        let names: Arc<Vec<String>> = Arc::new((0..500).map(|i| format!("n{i}")).collect());
+        let reg = Arc::new(NamedRegistry::new());
+        reg.insert(InnerMock {
+            name: names[0].clone(),
+            value: 1,
+        })
+        .unwrap();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let reader = {
+            let reg = reg.clone();
+            let names = names.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                // the entry only moves forward along `names`, so an in-order
+                // scan must observe it under one of them
+                while !done.load(Ordering::SeqCst) {
+                    assert!(names.iter().any(|name| reg.get(name.as_str()).is_some()));
+                }
+            })
+        };
+
+        for pair in names.windows(2) {
+            reg.rename(&pair[0], &pair[1]).unwrap();
+        }
+        done.store(true, Ordering::SeqCst);
+        reader.join().unwrap();
+
+        assert_eq!(reg.keys(), vec![Arc::<str>::from("n499")]);
+    }
+
+    #[rstest]
+    fn test_swap_exchanges_values() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "blue".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.insert(InnerMock {
+            name: "green".into(),
+            value: 2,
+        })
+        .unwrap();
+        let blue = reg.get("blue").unwrap();
+
+        assert!(reg.swap("blue", "green"));
+
+        assert_eq!(
+            *blue.lock(),
+            InnerMock {
+                name: "blue".into(),
+                value: 2
+            }
+        );
+        assert_eq!(
+            *reg.get("green").unwrap().lock(),
+            InnerMock {
+                name: "green".into(),
+                value: 1
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_swap_reports_both_entries() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("blue", 1)).unwrap();
+        reg.insert(mock("green", 2)).unwrap();
+        let events = reg.subscribe();
+        let generation = reg.generation();
+
+        assert!(reg.swap("blue", "green"));
+
+        let mutated = |name: &str| RegistryEvent::Mutated { name: name.into() };
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [mutated("blue"), mutated("green")]
+        );
+        assert_eq!(reg.generation(), generation + 2);
+        assert_eq!(reg.changed_since(generation).unwrap().len(), 2);
+    }
+
+    #[rstest]
+    fn test_swap_missing_or_same_key() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "blue".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        assert!(!reg.swap("blue", "missing"));
+        assert!(!reg.swap("missing", "blue"));
+        assert!(reg.swap("blue", "blue"));
+        assert_eq!(reg.get("blue").unwrap().lock().value, 1);
+    }
+
+    #[rstest]
+    fn test_concurrent_swaps_do_not_deadlock() {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        reg.insert(InnerMock {
+            name: "blue".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.insert(InnerMock {
+            name: "green".into(),
+            value: 2,
+        })
+        .unwrap();
+
+        let handles: Vec<_> = [("blue", "green"), ("green", "blue")]
+            .into_iter()
+            .map(|(a, b)| {
+                let reg = reg.clone();
+                thread::spawn(move || {
+                    for _ in 0..1_000 {
+                        assert!(reg.swap(a, b));
+                    }
+                })
+            })
+            .collect();
+
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        let mut values: Vec<_> = reg.values().iter().map(|e| e.lock().value).collect();
+        values.sort();
+        assert_eq!(values, vec![1, 2]);
+        assert_eq!(reg.get("blue").unwrap().lock().name, "blue");
+    }
+
+    #[rstest]
+    fn test_collect_last_duplicate_wins() {
+        let reg: NamedRegistry<_> = vec![
+            InnerMock {
+                name: "a".into(),
+                value: 1,
+            },
+            InnerMock {
+                name: "a".into(),
+                value: 2,
+            },
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(reg.len(), 1);
+        assert_eq!(reg.get("a").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_extend_overwrites_duplicates() {
+        let mut reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "a".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        reg.extend(vec![
+            InnerMock {
+                name: "a".into(),
+                value: 2,
+            },
+            InnerMock {
+                name: "b".into(),
+                value: 3,
+            },
+        ]);
+
+        assert_eq!(reg.len(), 2);
+        assert_eq!(reg.get("a").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_try_extend_reports_duplicates() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "a".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let rejected = reg.try_extend(vec![
+            InnerMock {
+                name: "a".into(),
+                value: 2,
+            },
+            InnerMock {
+                name: "b".into(),
+                value: 3,
+            },
+            InnerMock {
+                name: "b".into(),
+                value: 4,
+            },
+        ]);
+
+        let rejected: Vec<_> = rejected.into_iter().map(|v| v.value).collect();
+        assert_eq!(rejected, vec![2, 4]);
+        assert_eq!(reg.get("a").unwrap().lock().value, 1);
+        assert_eq!(reg.get("b").unwrap().lock().value, 3);
+    }
+
+    fn merge_fixtures() -> (NamedRegistry<InnerMock>, NamedRegistry<InnerMock>) {
+        let base = NamedRegistry::from_entries(vec![
+            InnerMock {
+                name: "shared".into(),
+                value: 1,
+            },
+            InnerMock {
+                name: "base".into(),
+                value: 2,
+            },
+        ]);
+        let overrides = NamedRegistry::from_entries(vec![
+            InnerMock {
+                name: "shared".into(),
+                value: 10,
+            },
+            InnerMock {
+                name: "extra".into(),
+                value: 20,
+            },
+        ]);
+        (base, overrides)
+    }
+
+    #[rstest]
+    fn test_merge_keep_existing() {
+        let (base, overrides) = merge_fixtures();
+
+        base.merge(&overrides, MergePolicy::KeepExisting);
+
+        assert_eq!(base.len(), 3);
+        assert_eq!(base.get("shared").unwrap().lock().value, 1);
+        assert_eq!(base.get("extra").unwrap().lock().value, 20);
+    }
+
+    #[rstest]
+    fn test_merge_take_incoming_updates_in_place() {
+        let (base, overrides) = merge_fixtures();
+        let held = base.get("shared").unwrap();
+
+        base.merge(&overrides, MergePolicy::TakeIncoming);
+
+        assert_eq!(held.lock().value, 10);
+        assert_eq!(base.get("base").unwrap().lock().value, 2);
+        overrides.mutate("extra", |v| v.value = 0);
+        assert_eq!(base.get("extra").unwrap().lock().value, 20);
+    }
+
+    #[rstest]
+    fn test_merge_resolve() {
+        let (base, overrides) = merge_fixtures();
+
+        base.merge(
+            &overrides,
+            MergePolicy::Resolve(|existing, incoming| InnerMock {
+                name: existing.name.clone(),
+                value: existing.value + incoming.value,
+            }),
+        );
+
+        assert_eq!(base.get("shared").unwrap().lock().value, 11);
+    }
+
+    #[rstest]
+    fn test_cross_merge_does_not_deadlock() {
+        use std::thread;
+
+        let (a, b) = merge_fixtures();
+        let handles: Vec<_> = [(a.clone(), b.clone()), (b.clone(), a.clone())]
+            .into_iter()
+            .map(|(this, other)| {
+                thread::spawn(move || {
+                    for _ in 0..200 {
+                        this.merge(&other, MergePolicy::KeepExisting);
+                    }
+                })
+            })
+            .collect();
+
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        assert_eq!(a.len(), 3);
+        assert_eq!(b.len(), 3);
+    }
+
+    #[rstest]
+    fn test_diff_identical() {
+        let (base, _) = merge_fixtures();
+        let copy = NamedRegistry::from_entries(base.values().iter().map(|e| e.lock().clone()));
+
+        assert!(base.diff(&copy).is_empty());
+        assert!(base.diff(&base).is_empty());
+    }
+
+    #[rstest]
+    fn test_diff_disjoint() {
+        let a = NamedRegistry::from_entries(vec![InnerMock {
+            name: "a".into(),
+            value: 1,
+        }]);
+        let b = NamedRegistry::from_entries(vec![InnerMock {
+            name: "b".into(),
+            value: 2,
+        }]);
+
+        let diff = a.diff(&b);
+
+        assert_eq!(
+            diff.removed,
+            vec![("a".into(), a.get("a").unwrap().lock().clone())]
+        );
+        assert_eq!(
+            diff.added,
+            vec![("b".into(), b.get("b").unwrap().lock().clone())]
+        );
+        assert!(diff.changed.is_empty());
+    }
+
+    #[rstest]
+    fn test_diff_changed() {
+        let (base, overrides) = merge_fixtures();
+
+        let diff = base.diff(&overrides);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.changed.len(), 1);
+        let (name, current, desired) = &diff.changed[0];
+        assert_eq!(name, "shared");
+        assert_eq!((current.value, desired.value), (1, 10));
+    }
+
+    #[rstest]
+    fn test_diff_during_concurrent_mutation() {
+        use std::thread;
+
+        let (base, overrides) = merge_fixtures();
+        let writer = {
+            let overrides = overrides.clone();
+            thread::spawn(move || {
+                for i in 0..1_000 {
+                    overrides.mutate("shared", |v| v.value = i % 2);
+                }
+            })
+        };
+
+        for _ in 0..100 {
+            let diff = base.diff(&overrides);
+            assert!(diff.changed.len() <= 1);
+        }
+        writer.join().unwrap();
+    }
+
+    #[rstest]
+    fn test_apply_patch_clean() {
+        let (base, overrides) = merge_fixtures();
+        let replica = base.deep_clone();
+        let patch = base.diff_serializable(&overrides);
+
+        let report = replica.apply_patch(patch.clone(), ConflictPolicy::Fail);
+
+        assert_eq!(
+            report,
+            Ok(ApplyReport {
+                applied: 3,
+                conflicts: Vec::new(),
+            })
+        );
+        assert!(replica.diff(&overrides).is_empty());
+        // already applied, so there is nothing left to do
+        assert_eq!(
+            replica.apply_patch(patch, ConflictPolicy::Fail).unwrap(),
+            ApplyReport::default()
+        );
+    }
+
+    #[rstest]
+    #[case::fail(ConflictPolicy::Fail, None)]
+    #[case::skip(ConflictPolicy::Skip, Some((1, [5, 99])))]
+    #[case::force(ConflictPolicy::Force, Some((3, [10, 20])))]
+    fn test_apply_patch_conflicts(
+        #[case] policy: ConflictPolicy,
+        #[case] expected: Option<(usize, [i32; 2])>,
+    ) {
+        let (base, overrides) = merge_fixtures();
+        let patch = base.diff_serializable(&overrides);
+        let replica = base.deep_clone();
+        replica.mutate("shared", |v| v.value = 5);
+        replica.insert(mock("extra", 99)).unwrap();
+
+        let conflicts = vec![
+            PatchConflict::Exists("extra".into()),
+            PatchConflict::Diverged("shared".into()),
+        ];
+        let values = || ["shared", "extra"].map(|name| replica.get_cloned(name).unwrap().value);
+        match (replica.apply_patch(patch, policy), expected) {
+            (Err(err), None) => {
+                assert_eq!(err, PatchError::Conflicts(conflicts));
+                assert!(replica.contains("base"));
+                assert_eq!(values(), [5, 99]);
+            }
+            (Ok(report), Some((applied, expected))) => {
+                assert_eq!(report, ApplyReport { applied, conflicts });
+                assert!(!replica.contains("base"));
+                assert_eq!(values(), expected);
+            }
+            (outcome, _) => panic!("unexpected outcome {outcome:?}"),
+        }
+    }
+
+    #[rstest]
+    fn test_apply_patch_sealed() {
+        let (base, overrides) = merge_fixtures();
+        base.seal();
+
+        let patch = base.diff_serializable(&overrides);
+
+        assert_eq!(
+            base.apply_patch(patch, ConflictPolicy::Force),
+            Err(PatchError::Sealed)
+        );
+        assert_eq!(base.get_cloned("shared").unwrap().value, 1);
+    }
+
+    #[rstest]
+    fn test_get_many_positional() {
+        let (base, _) = merge_fixtures();
+
+        let found = base.get_many(&["base", "missing", "shared"]);
+
+        let values: Vec<_> = found
+            .iter()
+            .map(|e| e.as_ref().map(|e| e.lock().value))
+            .collect();
+        assert_eq!(values, vec![Some(2), None, Some(1)]);
+    }
+
+    #[rstest]
+    fn test_get_many_with_duplicates() {
+        let (base, _) = merge_fixtures();
+        let names: Vec<&str> = ["shared", "missing"].into_iter().cycle().take(50).collect();
+
+        let found = base.get_many(&names);
+        let present = base.get_all_present(&names);
+
+        assert_eq!(found.len(), 50);
+        assert_eq!(found.iter().filter(|e| e.is_some()).count(), 25);
+        assert_eq!(present.len(), 25);
+        assert!(present.iter().all(|e| e.arc().ptr_eq(&present[0].arc())));
+
+        // plain handles: the map lock is not held once the call returns
+        base.insert(InnerMock {
+            name: "late".into(),
+            value: 0,
+        })
+        .unwrap();
+        assert_eq!(base.len(), 3);
+    }
+
+    #[rstest]
+    fn test_batch_mutate_partial_hits() {
+        let (base, _) = merge_fixtures();
+
+        let found = base.batch_mutate(&["shared", "missing", "base"], |name, v| {
+            assert_eq!(v.name, name);
+            v.value *= 100;
+        });
+
+        assert_eq!(found, 2);
+        assert_eq!(base.get("shared").unwrap().lock().value, 100);
+        assert_eq!(base.get("base").unwrap().lock().value, 200);
+    }
+
+    #[rstest]
+    fn test_batch_mutate_closure_reads_registry() {
+        let (base, _) = merge_fixtures();
+
+        let found = base.batch_mutate(&["shared"], |_, v| {
+            let other = base.get("base").unwrap();
+            v.value += other.lock().value;
+        });
+
+        assert_eq!(found, 1);
+        assert_eq!(base.get("shared").unwrap().lock().value, 3);
+    }
+
+    #[rstest]
+    fn test_contains_all_and_any() {
+        let (base, _) = merge_fixtures();
+
+        assert!(base.contains_all(&["shared", "base", "shared"]));
+        assert!(!base.contains_all(&["shared", "missing"]));
+        assert!(base.contains_any(&["missing", "base"]));
+        assert!(!base.contains_any(&["missing", "missing"]));
+    }
+
+    #[rstest]
+    fn test_contains_empty_slice() {
+        let (base, _) = merge_fixtures();
+
+        assert!(base.contains_all(&[]));
+        assert!(!base.contains_any(&[]));
+        assert!(base.missing_from(&[]).is_empty());
+    }
+
+    #[rstest]
+    fn test_missing_from_deduplicates() {
+        let (base, _) = merge_fixtures();
+
+        let missing = base.missing_from(&["x", "shared", "y", "x"]);
+
+        assert_eq!(missing, vec!["x".to_string(), "y".to_string()]);
+    }
+
+    #[rstest]
+    fn test_contains_all_with_concurrent_registration() {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        let names: Vec<String> = (0..20).map(|i| format!("dep{i}")).collect();
+
+        let writer = {
+            let reg = reg.clone();
+            let names = names.clone();
+            thread::spawn(move || {
+                for name in names {
+                    reg.insert(InnerMock { name, value: 0 }).unwrap();
+                }
+            })
+        };
+
+        let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut last_missing = refs.len();
+        while !reg.contains_all(&refs) {
+            let missing = reg.missing_from(&refs).len();
+            assert!(missing <= last_missing);
+            last_missing = missing;
+        }
+        writer.join().unwrap();
+        assert!(reg.missing_from(&refs).is_empty());
+    }
+
+    #[rstest]
+    fn test_with_capacity() {
+        let reg = NamedRegistry::<InnerMock>::with_capacity(64);
+
+        assert!(reg.capacity() >= 64);
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_reserve_does_not_shrink() {
+        let reg = NamedRegistry::<InnerMock>::with_capacity(256);
+        let before = reg.capacity();
+
+        reg.reserve(1);
+        assert!(reg.capacity() >= before);
+
+        reg.reserve(1_000);
+        assert!(reg.capacity() >= 1_000);
+    }
+
+    #[rstest]
+    fn test_bulk_insert_after_reserve() {
+        let reg = NamedRegistry::new();
+        reg.reserve(1_000);
+        let capacity = reg.capacity();
+
+        for i in 0..1_000 {
+            reg.insert(InnerMock {
+                name: format!("b{i}"),
+                value: i,
+            })
+            .unwrap();
+        }
+
+        assert_eq!(reg.len(), 1_000);
+        assert_eq!(reg.capacity(), capacity);
+        assert_eq!(reg.get("b999").unwrap().lock().value, 999);
+    }
+
+    #[rstest]
+    fn test_shrink_after_prune() {
+        let reg = NamedRegistry::from_entries((0..10_000).map(|i| InnerMock {
+            name: format!("s{i}"),
+            value: i,
+        }));
+        let before = reg.capacity();
+
+        reg.retain(|_, v| v.value < 10);
+
+        reg.shrink_to(100);
+        let after_shrink_to = reg.capacity();
+        assert!(after_shrink_to >= 100 && after_shrink_to < before);
+
+        reg.shrink_to_fit();
+        assert!(reg.capacity() < after_shrink_to);
+        assert_eq!(reg.len(), 10);
+    }
+
+    #[rstest]
+    fn test_split_off_empty_match() {
+        let (base, _) = merge_fixtures();
+
+        let split = base.split_off(|_, _| false);
+
+        assert!(split.is_empty());
+        assert_eq!(base.len(), 2);
+    }
+
+    #[rstest]
+    fn test_split_off_full_match() {
+        let (base, _) = merge_fixtures();
+        let held = base.get("shared").unwrap();
+
+        let split = base.split_off(|_, _| true);
+
+        assert!(base.is_empty());
+        assert_eq!(split.len(), 2);
+        assert!(split.get("shared").unwrap().arc().ptr_eq(&held.arc()));
+    }
+
+    #[rstest]
+    fn test_split_off_by_prefix_with_concurrent_get() {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::from_entries((0..200).map(|i| InnerMock {
+            name: if i % 2 == 0 {
+                format!("staging/{i}")
+            } else {
+                format!("prod/{i}")
+            },
+            value: i,
+        })));
+
+        let reader = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                for i in (1..200).step_by(2) {
+                    assert!(reg.get(format!("prod/{i}").as_str()).is_some());
+                }
+            })
+        };
+
+        let staging = reg.split_off(|name, _| name.starts_with("staging/"));
+        reader.join().unwrap();
+
+        assert_eq!(staging.len(), 100);
+        assert_eq!(reg.len(), 100);
+        assert!(staging.keys().iter().all(|name| !reg.contains(name)));
+    }
+
+    #[rstest]
+    fn test_deep_clone_is_independent() {
+        let (base, _) = merge_fixtures();
+        let copy = base.deep_clone();
+
+        copy.mutate("shared", |v| v.value = 100);
+        copy.remove("base");
+        base.mutate("base", |v| v.value = 200);
+
+        assert_eq!(base.get("shared").unwrap().lock().value, 1);
+        assert_eq!(base.len(), 2);
+        assert_eq!(copy.get("shared").unwrap().lock().value, 100);
+        assert!(!copy.contains("base"));
+    }
+
+    #[rstest]
+    fn test_clone_shares_state() {
+        let (base, _) = merge_fixtures();
+        let handle = base.clone();
+
+        handle.remove("base");
+
+        assert!(!base.contains("base"));
+    }
+
+    #[rstest]
+    fn test_pop_first_in_order() {
+        let reg = NamedRegistry::from_entries(["c", "a", "b"].map(|name| InnerMock {
+            name: name.into(),
+            value: 0,
+        }));
+
+        let popped: Vec<_> = std::iter::from_fn(|| reg.pop_first())
+            .map(|(name, _)| name)
+            .collect();
+
+        assert_eq!(popped, vec!["a", "b", "c"]);
+        assert!(reg.pop_first().is_none());
+    }
+
+    #[rstest]
+    fn test_pop_any_until_empty() {
+        let (base, _) = merge_fixtures();
+
+        let (name, entry) = base.pop_any().unwrap();
+        assert_eq!(entry.lock().name, name);
+        assert!(base.pop_any().is_some());
+        assert!(base.pop_any().is_none());
+    }
+
+    #[rstest]
+    #[case(true)]
+    #[case(false)]
+    fn test_concurrent_poppers_claim_once(#[case] ordered: bool) {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::from_entries((0..500).map(|i| InnerMock {
+            name: format!("job{i:03}"),
+            value: i,
+        })));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let reg = reg.clone();
+                thread::spawn(move || {
+                    let mut claimed = Vec::new();
+                    loop {
+                        let popped = if ordered {
+                            reg.pop_first()
+                        } else {
+                            reg.pop_any()
+                        };
+                        match popped {
+                            Some((_, entry)) => claimed.push(entry.lock().value),
+                            None => break claimed,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let mut claimed: Vec<_> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        claimed.sort();
+
+        assert_eq!(claimed, (0..500).collect::<Vec<_>>());
+    }
+
+    #[rstest]
+    fn test_upsert_inserts_then_updates() {
+        let reg = NamedRegistry::new();
+
+        let first = reg.upsert(InnerMock {
+            name: "u".into(),
+            value: 1,
+        });
+        let held = reg.get("u").unwrap();
+        let second = reg.upsert(InnerMock {
+            name: "u".into(),
+            value: 2,
+        });
+
+        assert_eq!(first, UpsertOutcome::Inserted);
+        assert_eq!(second, UpsertOutcome::Updated);
+        assert_eq!(held.lock().value, 2);
+        assert!(held.arc().ptr_eq(&reg.get("u").unwrap().arc()));
+    }
+
+    #[rstest]
+    #[case::upsert(|reg: &Mocks| assert_eq!(reg.upsert(mock("a", 2)), UpsertOutcome::Updated))]
+    #[case::replace(|reg: &Mocks| assert_eq!(reg.replace("a", mock("a", 2)).unwrap(), Some(mock("a", 1))))]
+    #[case::merge(|reg: &Mocks| {
+        reg.merge(&NamedRegistry::from_entries(vec![mock("a", 2)]), MergePolicy::TakeIncoming);
+    })]
+    #[case::apply_patch(|reg: &Mocks| {
+        let base = NamedRegistry::from_entries(vec![mock("a", 1)]);
+        let patch = base.diff_serializable(&NamedRegistry::from_entries(vec![mock("a", 2)]));
+        assert_eq!(reg.apply_patch(patch, ConflictPolicy::Fail).unwrap().applied, 1);
+    })]
+    fn test_overwrites_wait_for_the_entry_outside_the_map_lock(#[case] write: fn(&Mocks)) {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        reg.insert(mock("a", 1)).unwrap();
+        let held = reg.get("a").unwrap();
+        let guard = held.lock();
+        let writer = {
+            let reg = reg.clone();
+            thread::spawn(move || write(&reg))
+        };
+        thread::sleep(Duration::from_millis(50));
+
+        // the writer is parked on the entry, so the map must still be free
+        let (sender, receiver) = mpsc::channel();
+        {
+            let reg = reg.clone();
+            thread::spawn(move || sender.send(reg.get("a").is_some()).unwrap());
+        }
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(10)), Ok(true));
+        drop(guard);
+        writer.join().unwrap();
+        assert_eq!(reg.with("a", |v| v.value), Some(2));
+    }
+
+    #[rstest]
+    fn test_replace_returns_previous() {
+        let reg = NamedRegistry::new();
+
+        let missing = reg.replace(
+            "cfg",
+            InnerMock {
+                name: "cfg".into(),
+                value: 1,
+            },
+        );
+        let previous = reg.replace(
+            "cfg",
+            InnerMock {
+                name: "cfg".into(),
+                value: 2,
+            },
+        );
+
+        assert!(missing.unwrap().is_none());
+        assert_eq!(previous.unwrap().unwrap().value, 1);
+        assert_eq!(reg.get("cfg").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_replace_concurrent_reader() {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        reg.insert(InnerMock {
+            name: "cfg".into(),
+            value: 0,
+        })
+        .unwrap();
+
+        let reader = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..1_000 {
+                    let current = reg.get("cfg").unwrap().lock().clone();
+                    assert_eq!(current.name, "cfg");
+                    assert!(current.value >= last);
+                    last = current.value;
+                }
+            })
+        };
+
+        for i in 1..=1_000 {
+            let old = reg
+                .replace(
+                    "cfg",
+                    InnerMock {
+                        name: "cfg".into(),
+                        value: i,
+                    },
+                )
+                .unwrap()
+                .unwrap();
+            assert_eq!(old.value, i - 1);
+        }
+        reader.join().unwrap();
+    }
+
+    #[rstest]
+    fn test_insert_many_bulk() {
+        let reg = NamedRegistry::new();
+
+        let overwritten = reg
+            .insert_many(
+                (0..10_000).map(|i| InnerMock {
+                    name: format!("item{i}"),
+                    value: i,
+                }),
+                InsertPolicy::Overwrite,
+            )
+            .unwrap();
+
+        assert!(overwritten.is_empty());
+        assert_eq!(reg.len(), 10_000);
+        assert_eq!(reg.get("item9999").unwrap().lock().value, 9_999);
+    }
+
+    #[rstest]
+    fn test_insert_many_overwrite_reports_names() {
+        let (base, _) = merge_fixtures();
+
+        let overwritten = base
+            .insert_many(
+                ["shared", "new", "new"].map(|name| InnerMock {
+                    name: name.into(),
+                    value: 7,
+                }),
+                InsertPolicy::Overwrite,
+            )
+            .unwrap();
+
+        assert_eq!(overwritten, vec!["shared".to_string(), "new".to_string()]);
+        assert_eq!(base.len(), 3);
+        assert_eq!(base.get("shared").unwrap().lock().value, 7);
+    }
+
+    #[rstest]
+    #[case(&["fresh", "shared"], "shared")]
+    #[case(&["fresh", "other", "fresh"], "fresh")]
+    fn test_insert_many_abort_leaves_registry_untouched(
+        #[case] names: &[&str],
+        #[case] duplicate: &str,
+    ) {
+        let (base, _) = merge_fixtures();
+
+        let result = base.insert_many(
+            names.iter().map(|name| InnerMock {
+                name: name.to_string(),
+                value: 7,
+            }),
+            InsertPolicy::Abort,
+        );
+
+        assert_eq!(
+            result,
+            Err(InsertManyError::Duplicate(DuplicateName(duplicate.into())))
+        );
+        assert_eq!(base.len(), 2);
+        assert_eq!(base.get("shared").unwrap().lock().value, 1);
+    }
+
+    #[rstest]
+    fn test_insert_many_validates_every_name() {
+        let reg = NamedRegistry::new();
+        reg.set_name_validator(no_slashes);
+
+        let result = reg.insert_many([mock("fine", 1), mock("a/b", 2)], InsertPolicy::Overwrite);
+
+        assert!(matches!(
+            result,
+            Err(InsertManyError::Rejected(InsertError::InvalidName { name, .. })) if name == "a/b"
+        ));
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_insert_many_respects_the_bound() {
+        let lru = NamedRegistry::bounded(2, EvictionPolicy::Lru);
+        lru.insert(mock("a", 1)).unwrap();
+        let events = lru.subscribe();
+
+        lru.insert_many([mock("b", 2), mock("c", 3)], InsertPolicy::Overwrite)
+            .unwrap();
+
+        let mut keys = lru.keys();
+        keys.sort();
+        assert_eq!(keys, ["b", "c"].map(Arc::<str>::from));
+        let names: Vec<_> = events
+            .try_iter()
+            .map(|event| event.name().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["b", "a", "c"]);
+
+        let reject = NamedRegistry::bounded(2, EvictionPolicy::RejectNew);
+        reject.insert(mock("a", 1)).unwrap();
+        assert_eq!(
+            reject.insert_many(
+                [mock("a", 10), mock("b", 2), mock("c", 3)],
+                InsertPolicy::Overwrite
+            ),
+            Err(InsertManyError::Rejected(InsertError::Full {
+                name: "c".into(),
+                capacity: 2,
+            }))
+        );
+        assert_eq!(reject.len(), 1);
+        assert_eq!(reject.with("a", |v| v.value), Some(1));
+    }
+
+    #[rstest]
+    fn test_insert_many_counts_and_audits_each_insert() {
+        let reg = audited(10);
+        reg.insert_with_id(7, mock("alpha", 1)).unwrap();
+        let before = reg.stats();
+        let logged = reg.audit_log().len();
+
+        reg.insert_many([mock("alpha", 2), mock("beta", 3)], InsertPolicy::Overwrite)
+            .unwrap();
+
+        let stats = reg.stats();
+        assert_eq!(stats.inserts - before.inserts, 2);
+        assert_eq!(stats.overwrites - before.overwrites, 1);
+        let ops: Vec<_> = reg
+            .audit_log()
+            .into_iter()
+            .skip(logged)
+            .map(|record| (record.op, record.name))
+            .collect();
+        assert_eq!(
+            ops,
+            ["alpha", "beta"].map(|name| (AuditOp::Insert, name.to_string()))
+        );
+        assert!(reg.get_by_id(7).is_none());
+    }
+
+    #[rstest]
+    fn test_rename_through_lock_desyncs_keys() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "old".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        reg.get("old").unwrap().lock().name = "new".into();
+
+        assert!(reg.get("new").is_none());
+        assert_eq!(reg.get("old").unwrap().lock().name, "new");
+        assert_eq!(
+            reg.mismatched_names(),
+            vec![("old".to_string(), "new".to_string())]
+        );
+    }
+
+    #[rstest]
+    fn test_mutate_rekeys_renamed_entry() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "old".into(),
+            value: 1,
+        })
+        .unwrap();
+        let held = reg.get("old").unwrap();
+
+        reg.mutate("old", |v| v.name = "new".into());
+
+        assert!(!reg.contains("old"));
+        assert!(reg.get("new").unwrap().arc().ptr_eq(&held.arc()));
+        assert!(reg.mismatched_names().is_empty());
+    }
+
+    #[rstest]
+    #[case::occupied(false)]
+    #[case::sealed(true)]
+    fn test_mutate_never_displaces_to_rekey(#[case] sealed: bool) {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("a", 1)).unwrap();
+        reg.insert(mock("c", 3)).unwrap();
+        if sealed {
+            reg.seal();
+        } else {
+            reg.insert(mock("b", 2)).unwrap();
+        }
+        let events = reg.subscribe();
+
+        reg.mutate("a", |v| v.name = "b".into());
+        reg.cas("c", 0, |v| v.name = "b".into()).unwrap();
+
+        assert_eq!(reg.len(), if sealed { 2 } else { 3 });
+        assert_eq!(reg.with("a", |v| v.value), Some(1));
+        assert_eq!(reg.with("c", |v| v.value), Some(3));
+        if !sealed {
+            assert_eq!(reg.with("b", |v| v.value), Some(2));
+        }
+        // reported under the names the values now carry
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            ["b", "b"].map(|name| RegistryEvent::Mutated { name: name.into() })
+        );
+        let mut mismatched = reg.mismatched_names();
+        mismatched.sort();
+        assert_eq!(
+            mismatched,
+            [("a", "b"), ("c", "b")].map(|(key, name)| (key.to_string(), name.to_string()))
+        );
+    }
+
+    #[rstest]
+    fn test_mutate_pair_never_displaces_to_rekey() {
+        let reg = NamedRegistry::new();
+        for (i, name) in ["a", "b", "c"].into_iter().enumerate() {
+            reg.insert(mock(name, i as i32)).unwrap();
+        }
+
+        reg.mutate_pair("a", "b", |a, b| {
+            a.name = "b".into();
+            b.name = "c".into();
+        })
+        .unwrap();
+
+        assert_eq!(reg.len(), 3);
+        assert_eq!(reg.with("a", |v| v.value), Some(0));
+        assert_eq!(reg.with("b", |v| v.value), Some(1));
+        assert_eq!(reg.with("c", |v| v.value), Some(2));
+    }
+
+    #[rstest]
+    fn test_mutate_strict_rejects_rename() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "old".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let result = reg.mutate_strict("old", |v| {
+            v.value = 2;
+            v.name = "new".into();
+        });
+
+        assert_eq!(
+            result,
+            Err(MutateError::Renamed {
+                from: "old".into(),
+                to: "new".into()
+            })
+        );
+        let value = reg.get("old").unwrap().lock().clone();
+        assert_eq!((value.name.as_str(), value.value), ("old", 2));
+        assert_eq!(reg.mutate_strict("old", |v| v.value), Ok(2));
+        assert_eq!(
+            reg.mutate_strict("missing", |_| ()),
+            Err(MutateError::NotFound("missing".into()))
+        );
+    }
+
+    #[rstest]
+    fn test_panicking_closure_leaves_registry_usable() {
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "p".into(),
+            value: 1,
+        })
+        .unwrap();
+
+        let handle = reg.clone();
+        let result = thread::spawn(move || {
+            handle.mutate("p", |_| panic!("closure failure"));
+        })
+        .join();
+
+        assert!(result.is_err());
+        assert_eq!(
+            reg.get("p").unwrap().is_poisoned(),
+            cfg!(not(feature = "parking_lot"))
+        );
+        assert_eq!(reg.mutate("p", |v| v.value + 1), Some(2));
+        reg.insert(InnerMock {
+            name: "q".into(),
+            value: 0,
+        })
+        .unwrap();
+        assert_eq!(reg.len(), 2);
+    }
+
+    #[rstest]
+    #[cfg(not(feature = "parking_lot"))]
+    fn test_poisoned_map_lock_recovers() {
+        use std::thread;
+
+        let reg = NamedRegistry::<InnerMock>::new();
+
+        let handle = reg.clone();
+        let _ = thread::spawn(move || {
+            let _map = handle.lock();
+            panic!("panic while holding the map lock");
+        })
+        .join();
+
+        assert!(reg.is_poisoned());
+        assert_eq!(reg.try_access().err(), Some(PoisonedRegistry));
+        reg.insert(InnerMock {
+            name: "q".into(),
+            value: 0,
+        })
+        .unwrap();
+        assert!(reg.contains("q"));
+    }
+
+    #[rstest]
+    #[cfg(not(feature = "parking_lot"))]
+    fn test_heal_restores_full_operation() {
+        use std::thread;
+
+        let reg = NamedRegistry::from_entries(["a", "b"].map(|name| InnerMock {
+            name: name.into(),
+            value: 0,
+        }));
+        assert!(reg.health().is_healthy());
+
+        let handle = reg.clone();
+        let _ = thread::spawn(move || {
+            handle.mutate("b", |_| panic!("entry failure"));
+        })
+        .join();
+        let handle = reg.clone();
+        let _ = thread::spawn(move || {
+            let _map = handle.lock();
+            panic!("map failure");
+        })
+        .join();
+
+        let health = reg.health();
+        assert!(health.map_poisoned);
+        assert_eq!(health.poisoned_entries, vec!["b".to_string()]);
+        assert_eq!(reg.health(), health);
+
+        assert_eq!(reg.heal(), health);
+
+        assert!(reg.health().is_healthy());
+        assert!(reg.try_access().is_ok());
+        assert!(reg.get("b").unwrap().try_access().is_ok());
+    }
+
+    fn no_slashes(name: &str) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("empty".into());
+        }
+        if name.contains('/') {
+            return Err("contains a slash".into());
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    fn test_validator_rejects_insert() {
+        let reg = NamedRegistry::new();
+        reg.set_name_validator(no_slashes);
+
+        let result = reg.insert(InnerMock {
+            name: "a/b".into(),
+            value: 1,
+        });
+
+        assert_eq!(
+            result.unwrap_err(),
+            InsertError::InvalidName {
+                name: "a/b".into(),
+                reason: "contains a slash".into()
+            }
+        );
+        assert!(reg.is_empty());
+
+        let result = reg.try_insert(InnerMock {
+            name: "".into(),
+            value: 1,
+        });
+        assert!(matches!(
+            result,
+            Err(TryInsertError::Rejected(InsertError::InvalidName { name, .. })) if name.is_empty()
+        ));
+    }
+
+    #[rstest]
+    fn test_validator_accepts_valid_names() {
+        let reg = NamedRegistry::new();
+        reg.set_name_validator(no_slashes);
+
+        reg.insert(InnerMock {
+            name: "a".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.try_insert(InnerMock {
+            name: "b".into(),
+            value: 2,
+        })
+        .unwrap();
+
+        assert_eq!(reg.len(), 2);
+    }
+
+    #[rstest]
+    fn test_validator_rejects_rename() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "a".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.set_name_validator(no_slashes);
+
+        let result = reg.rename("a", "x/y");
+
+        assert_eq!(
+            result,
+            Err(RenameError::Rejected(InsertError::InvalidName {
+                name: "x/y".into(),
+                reason: "contains a slash".into()
+            }))
+        );
+        assert!(reg.contains("a"));
+    }
+
+    #[rstest]
+    fn test_validator_does_not_affect_get() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "a/b".into(),
+            value: 1,
+        })
+        .unwrap();
+        reg.set_name_validator(no_slashes);
+
+        assert!(reg.contains("a/b"));
+        assert_eq!(reg.get("a/b").unwrap().lock().value, 1);
+        assert!(reg.get("x/y").is_none());
+
+        reg.clear_name_validator();
+        assert!(reg
+            .insert(InnerMock {
+                name: "x/y".into(),
+                value: 2,
+            })
+            .is_ok());
+    }
+
+    #[rstest]
+    fn test_get_cloned() {
+        let (base, _) = merge_fixtures();
+
+        let value = base.get_cloned("shared").unwrap();
+        base.mutate("shared", |v| v.value = 99);
+
+        assert_eq!(value.value, 1);
+        assert_eq!(base.get_cloned("shared").unwrap().value, 99);
+        assert!(base.get_cloned("missing").is_none());
+    }
+
+    #[rstest]
+    fn test_with_reads_values() {
+        let (base, _) = merge_fixtures();
+
+        assert_eq!(base.with("shared", |v| v.value + 1), Some(2));
+        assert_eq!(base.with("missing", |v| v.value), None);
+
+        let total: i32 = base.values().iter().map(|e| e.with(|v| v.value)).sum();
+        assert_eq!(total, 3);
+    }
+
+    #[rstest]
+    fn test_get_with_reads_in_place() {
+        let (base, _) = merge_fixtures();
+
+        assert_eq!(base.get_with("shared", |v| v.value + 1), Some(2));
+        assert_eq!(base.get_with("missing", |v| v.value), None);
+        assert_eq!(base.get("shared").unwrap().strong_count(), 2);
+    }
+
+    #[rstest]
+    fn test_get_with_holds_the_map_lock() {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let (base, _) = merge_fixtures();
+        let (inserted_tx, inserted_rx) = mpsc::channel();
+
+        let value = base.get_with("shared", |v| {
+            let writer = {
+                let base = base.clone();
+                thread::spawn(move || {
+                    base.insert(mock("late", 0)).unwrap();
+                    inserted_tx.send(()).unwrap();
+                })
+            };
+            // the writer cannot get in while `f` runs
+            let blocked = inserted_rx.recv_timeout(Duration::from_millis(50)).is_err();
+            (v.value, blocked, writer)
+        });
+
+        let (value, blocked, writer) = value.unwrap();
+        writer.join().unwrap();
+        assert_eq!(value, 1);
+        assert!(blocked);
+        assert!(base.contains("late"));
+    }
+
+    #[rstest]
+    fn test_try_mutate_outcomes() {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let (base, _) = merge_fixtures();
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+
+        let holder = {
+            let entry = base.get("shared").unwrap();
+            thread::spawn(move || {
+                let _guard = entry.lock();
+                locked_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+            })
+        };
+
+        locked_rx.recv().unwrap();
+        assert_eq!(
+            base.try_mutate("shared", |v| v.value),
+            TryMutateOutcome::Busy
+        );
+        assert_eq!(
+            base.try_mutate("missing", |v| v.value),
+            TryMutateOutcome::Missing
+        );
+        assert_eq!(
+            base.try_mutate("base", |v| v.value),
+            TryMutateOutcome::Applied(2)
+        );
+
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+        assert_eq!(
+            base.try_mutate("shared", |v| v.value),
+            TryMutateOutcome::Applied(1)
+        );
+    }
+
+    #[rstest]
+    fn test_mutate_timeout() {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let (base, _) = merge_fixtures();
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+
+        let holder = {
+            let entry = base.get("shared").unwrap();
+            thread::spawn(move || {
+                let _guard = entry.lock();
+                locked_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+            })
+        };
+
+        locked_rx.recv().unwrap();
+        let timeout = Duration::from_millis(20);
+        let err = base
+            .mutate_timeout("shared", timeout, |v| v.value)
+            .unwrap_err();
+        assert!(err.waited >= timeout);
+        assert_eq!(
+            base.mutate_timeout("missing", timeout, |v| v.value),
+            Ok(None)
+        );
+
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+        assert_eq!(
+            base.mutate_timeout("shared", timeout, |v| v.value),
+            Ok(Some(1))
+        );
+    }
+
+    #[rstest]
+    fn test_find_by_meta_after_update() {
+        let (base, _) = merge_fixtures();
+        base.get("shared").unwrap().set_meta("env", "prod".into());
+        base.get("base").unwrap().set_meta("env", "dev".into());
+
+        base.update(InnerMock {
+            name: "shared".into(),
+            value: 99,
+        })
+        .unwrap();
+
+        let prod = base.find_by_meta("env", "prod");
+        assert_eq!(prod.len(), 1);
+        assert_eq!(prod[0].lock().value, 99);
+        assert!(base.find_by_meta("env", "staging").is_empty());
+        assert!(base.find_by_meta("owner", "prod").is_empty());
+    }
+
+    #[rstest]
+    fn test_eq_ignores_insertion_order() {
+        let (base, _) = merge_fixtures();
+        let mut values: Vec<_> = base.cloned_values().into_values().collect();
+        values.reverse();
+        let reordered = NamedRegistry::from_entries(values);
+
+        assert_eq!(base, base);
+        assert_eq!(base, base.clone());
+        assert_eq!(base, reordered);
+
+        reordered.mutate("base", |v| v.value += 1);
+        assert_ne!(base, reordered);
+    }
+
+    #[rstest]
+    fn test_eq_requires_same_names() {
+        let (base, overrides) = merge_fixtures();
+        let subset = base.deep_clone();
+        subset.remove("base");
+
+        assert_ne!(base, overrides);
+        assert_ne!(base, subset);
+        assert_ne!(subset, base);
+    }
+
+    #[rstest]
+    fn test_debug_lists_values() {
+        let (base, _) = merge_fixtures();
+
+        assert_eq!(
+            format!("{base:?}"),
+            r#"NamedRegistry {"base": InnerMock { name: "base", value: 2 }, "shared": InnerMock { name: "shared", value: 1 }}"#
+        );
+        assert_eq!(
+            format!("{:?}", NamedRegistry::<InnerMock>::new()),
+            "NamedRegistry {}"
+        );
+    }
+
+    #[rstest]
+    fn test_debug_while_entry_locked_elsewhere() {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let (base, _) = merge_fixtures();
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let held = base.get("shared").unwrap();
+        let holder = thread::spawn(move || {
+            let _guard = held.lock();
+            locked_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+        });
+        locked_rx.recv().unwrap();
+
+        assert_eq!(
+            format!("{base:?}"),
+            r#"NamedRegistry {"base": InnerMock { name: "base", value: 2 }, "shared": <locked>}"#
+        );
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+    }
+
+    #[rstest]
+    fn test_freeze_all() {
+        let (base, _) = merge_fixtures();
+        let token = base.freeze_all();
+
+        assert_eq!(
+            base.mutate_strict("shared", |v| v.value += 1),
+            Err(MutateError::Frozen("shared".into()))
+        );
+        assert_eq!(
+            base.cas("base", 0, |v| v.value += 1),
+            Err(CasError::Frozen("base".into()))
+        );
+        assert_eq!(base.get_cloned("shared").unwrap().value, 1);
+        assert_eq!(base.with("base", |v| v.value), Some(2));
+
+        base.unfreeze_all(&token);
+        assert_eq!(base.mutate_strict("shared", |v| v.value += 1), Ok(()));
+        assert_eq!(base.get_cloned("shared").unwrap().value, 2);
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Device {
+        id: u64,
+        tenant: &'static str,
+        name: &'static str,
+    }
+
+    impl HasName for Device {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+    }
+
+    impl HasKey<u64> for Device {
+        fn key(&self) -> u64 {
+            self.id
+        }
+    }
+
+    impl HasKey<(&'static str, &'static str)> for Device {
+        fn key(&self) -> (&'static str, &'static str) {
+            (self.tenant, self.name)
+        }
+    }
+
+    fn devices() -> Vec<Device> {
+        vec![
+            Device {
+                id: 7,
+                tenant: "acme",
+                name: "db",
+            },
+            Device {
+                id: 3,
+                tenant: "umbrella",
+                name: "db",
+            },
+        ]
+    }
+
+    #[rstest]
+    fn test_u64_keys() {
+        let reg = NamedRegistry::<Device, u64>::keyed();
+        for device in devices() {
+            reg.insert(device).unwrap();
+        }
+
+        assert!(reg.contains(&7));
+        assert_eq!(reg.with(&3, |d| d.tenant), Some("umbrella"));
+        let mut keys = reg.keys();
+        keys.sort();
+        assert_eq!(keys, vec![3, 7]);
+
+        reg.mutate(&7, |d| d.id = 8);
+        assert!(!reg.contains(&7));
+        assert_eq!(reg.get(&8).unwrap().lock().tenant, "acme");
+        assert!(reg.remove(&3).is_some());
+        assert_eq!(reg.len(), 1);
+    }
+
+    #[rstest]
+    fn test_tuple_keys() {
+        let reg: NamedRegistry<Device, (&str, &str)> = NamedRegistry::default();
+        for device in devices() {
+            reg.insert(device).unwrap();
+        }
+
+        assert_eq!(reg.len(), 2);
+        assert_eq!(reg.with(&("acme", "db"), |d| d.id), Some(7));
+        assert!(!reg.contains(&("acme", "cache")));
+        assert_eq!(
+            format!("{reg:?}"),
+            r#"NamedRegistry {("acme", "db"): Device { id: 7, tenant: "acme", name: "db" }, ("umbrella", "db"): Device { id: 3, tenant: "umbrella", name: "db" }}"#
+        );
+    }
+
+    #[rstest]
+    fn test_keyed_registry_validates_names() {
+        let reg = NamedRegistry::<Device, u64>::keyed();
+        reg.set_name_validator(no_slashes);
+
+        let err = reg
+            .insert(Device {
+                id: 1,
+                tenant: "acme",
+                name: "a/b",
+            })
+            .unwrap_err();
+        assert!(matches!(err, InsertError::InvalidName { .. }));
+    }
+
+    #[rstest]
+    fn test_same_entry() {
+        let (base, overrides) = merge_fixtures();
+        let held = base.get("shared").unwrap();
+
+        assert!(base.same_entry("shared", &held));
+        assert!(!base.same_entry("base", &held));
+        assert!(!base.same_entry("missing", &held));
+        assert!(!overrides.same_entry("shared", &held));
+
+        base.insert(held.read()).unwrap();
+        assert!(!base.same_entry("shared", &held));
+    }
+
+    #[rstest]
+    fn test_cas() {
+        let (base, _) = merge_fixtures();
+
+        assert_eq!(base.cas("shared", 0, |v| v.value += 1), Ok(1));
+        assert_eq!(
+            base.cas("shared", 0, |v| v.value += 1),
+            Err(CasError::Stale { current: 1 })
+        );
+        assert_eq!(
+            base.cas("missing", 0, |v| v.value += 1),
+            Err(CasError::NotFound("missing".into()))
+        );
+        assert_eq!(base.get_cloned("shared").unwrap().value, 2);
+    }
+
+    #[rstest]
+    fn test_cas_rekeys_rename() {
+        let (base, _) = merge_fixtures();
+
+        base.cas("base", 0, |v| v.name = "renamed".into()).unwrap();
+
+        assert!(!base.contains("base"));
+        assert_eq!(base.version_of("renamed"), Some(1));
+    }
+
+    #[derive(Debug)]
+    struct MockClock(std::sync::Mutex<SystemTime>);
+
+    impl MockClock {
+        fn advance(&self, secs: u64) {
+            *self.0.lock().unwrap() += Duration::from_secs(secs);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> SystemTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[rstest]
+    fn test_modified_since() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let clock = Arc::new(MockClock(std::sync::Mutex::new(start)));
+        let reg = NamedRegistry::with_clock(clock.clone());
+        for name in ["alpha", "beta", "gamma"] {
+            reg.insert(InnerMock {
+                name: name.into(),
+                value: 0,
+            })
+            .unwrap();
+        }
+        assert!(reg.modified_since(start).is_empty());
+
+        clock.advance(10);
+        reg.mutate("beta", |v| v.value += 1);
+        clock.advance(10);
+        reg.insert(InnerMock {
+            name: "delta".into(),
+            value: 0,
+        })
+        .unwrap();
+        reg.with("alpha", |v| v.value);
+
+        let mut since_start = reg.modified_since(start);
+        since_start.sort();
+        assert_eq!(since_start, vec!["beta", "delta"]);
+        let after_mutate = start + Duration::from_secs(10);
+        assert_eq!(reg.modified_since(after_mutate), vec!["delta"]);
+        assert_eq!(reg.get("beta").unwrap().created_at(), start);
+    }
+
+    #[rstest]
+    fn test_version_of() {
+        let (base, _) = merge_fixtures();
+
+        assert_eq!(base.version_of("shared"), Some(0));
+        base.get("shared");
+        base.get_cloned("shared");
+        assert_eq!(base.version_of("shared"), Some(0));
+
+        base.mutate("shared", |v| v.value += 1);
+        assert_eq!(base.version_of("shared"), Some(1));
+        assert_eq!(base.version_of("missing"), None);
+    }
+
+    #[rstest]
+    fn test_multiple_entries_concurrent_mutation() {
+        use std::thread;
+
+        let reg = Arc::new(NamedRegistry::new());
+        reg.insert(InnerMock {
+            name: "x".into(),
+            value: 0,
+        })
+        .unwrap();
+        reg.insert(InnerMock {
+            name: "y".into(),
+            value: 100,
+        })
+        .unwrap();
+
+        let reg1 = reg.clone();
+        let t1 = thread::spawn(move || {
+            reg1.mutate("x", |v| v.value += 10);
+        });
+
+        let reg2 = reg.clone();
+        let t2 = thread::spawn(move || {
+            reg2.mutate("y", |v| v.value -= 50);
+        });
+
+        t1.join().unwrap();
+        t2.join().unwrap();
+
+        assert_eq!(reg.get("x").unwrap().lock().value, 10);
+        assert_eq!(reg.get("y").unwrap().lock().value, 50);
+    }
+
+    #[rstest]
+    fn test_lock_and_rlock_consistency() {
+        let reg = NamedRegistry::new();
+        reg.insert(InnerMock {
+            name: "omega".into(),
+            value: 9,
+        })
+        .unwrap();
+
+        {
+            let map = reg.rlock();
+            assert!(map.contains_key("omega"));
+        }
+
+        {
+            let mut map = reg.lock();
+            map.insert(
+                "phi".into(),
+                Entry::new(InnerMock {
+                    name: "phi".into(),
+                    value: 33,
+                }),
+            );
+        }
+
+        assert!(reg.contains("phi"));
+    }
+
+    fn case_insensitive() -> NamedRegistry<InnerMock> {
+        NamedRegistry::new_with_options(RegistryOptions {
+            case_insensitive: true,
+            ..Default::default()
+        })
+    }
+
+    fn mock(name: &str, value: i32) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            value,
+        }
+    }
+
+    #[rstest]
+    fn test_case_insensitive_lookup_keeps_original_name() {
+        let reg = case_insensitive();
+        reg.insert(mock("Alpha", 1)).unwrap();
+
+        assert!(reg.contains("alpha"));
+        assert_eq!(reg.get("ALPHA").unwrap().lock().name, "Alpha");
+        assert_eq!(reg.keys(), vec![Arc::<str>::from("Alpha")]);
+        assert_eq!(&*reg.iter().next().unwrap().0, "Alpha");
+    }
+
+    #[rstest]
+    fn test_case_insensitive_insert_overwrites_other_casing() {
+        let reg = case_insensitive();
+        reg.insert(mock("Alpha", 1)).unwrap();
+
+        let previous = reg.insert(mock("alpha", 2)).unwrap().unwrap();
+
+        assert_eq!(previous.lock().name, "Alpha");
+        assert_eq!(reg.len(), 1);
+        assert_eq!(reg.keys(), vec![Arc::<str>::from("alpha")]);
+        assert_eq!(reg.get("ALPHA").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_case_insensitive_try_insert_is_occupied() {
+        let reg = case_insensitive();
+        reg.insert(mock("Alpha", 1)).unwrap();
+
+        let result = reg.try_insert(mock("ALPHA", 2));
+
+        assert!(matches!(result, Err(TryInsertError::Occupied(_))));
+        assert_eq!(reg.get("alpha").unwrap().lock().value, 1);
+    }
+
+    #[rstest]
+    fn test_case_insensitive_abort_policy_reports_original_name() {
+        let reg = case_insensitive();
+        reg.insert(mock("Alpha", 1)).unwrap();
+
+        let err = reg
+            .insert_many([mock("ALPHA", 2)], InsertPolicy::Abort)
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            InsertManyError::Duplicate(DuplicateName("ALPHA".into()))
+        );
+    }
+
+    #[rstest]
+    fn test_case_insensitive_unicode() {
+        let reg = case_insensitive();
+        reg.insert(mock("ÄRGER", 1)).unwrap();
+
+        assert!(reg.contains("ärger"));
+        assert_eq!(reg.remove("Ärger").unwrap().lock().name, "ÄRGER");
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_case_insensitive_rename_changes_casing() {
+        let reg = case_insensitive();
+        reg.insert(mock("alpha", 1)).unwrap();
+
+        reg.rename("ALPHA", "Alpha").unwrap();
+
+        assert_eq!(reg.keys(), vec![Arc::<str>::from("Alpha")]);
+        assert_eq!(reg.get("alpha").unwrap().lock().name, "Alpha");
+    }
+
+    #[rstest]
+    fn test_default_registry_is_case_sensitive() {
+        let reg = NamedRegistry::new_with_options(RegistryOptions::default());
+        reg.insert(mock("Alpha", 1)).unwrap();
+        reg.insert(mock("alpha", 2)).unwrap();
+
+        assert_eq!(reg.len(), 2);
+        assert!(reg.get("ALPHA").is_none());
+    }
+
+    #[rstest]
+    fn test_namespaces_are_isolated() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("parser", 0)).unwrap();
+        reg.insert_in("tenantA", mock("parser", 1)).unwrap();
+        reg.insert_in("tenantB", mock("parser", 2)).unwrap();
+
+        assert_eq!(reg.get("parser").unwrap().lock().value, 0);
+        assert_eq!(reg.get_in("tenantA", "parser").unwrap().lock().value, 1);
+        assert_eq!(reg.get_path("tenantB/parser").unwrap().lock().value, 2);
+        assert_eq!(
+            reg.get_in(DEFAULT_NAMESPACE, "parser")
+                .unwrap()
+                .lock()
+                .value,
+            0
+        );
+        assert!(reg.get_in("tenantA", "lexer").is_none());
+        assert!(reg.get_in("tenantC", "parser").is_none());
+        assert_eq!(reg.len(), 1);
+    }
+
+    #[rstest]
+    fn test_insert_in_replaces_within_namespace() {
+        let reg = NamedRegistry::new();
+        reg.insert_in("tenantA", mock("parser", 1)).unwrap();
+
+        let previous = reg.insert_in("tenantA", mock("parser", 2)).unwrap();
+
+        assert_eq!(previous.unwrap().lock().value, 1);
+        assert_eq!(reg.names_in("tenantA"), vec!["parser".to_string()]);
+    }
+
+    #[rstest]
+    fn test_insert_in_rejects_separator_in_namespace() {
+        let reg = NamedRegistry::new_with_options(RegistryOptions {
+            namespace_separator: ':',
+            ..Default::default()
+        });
+
+        let err = reg.insert_in("tenant:A", mock("parser", 1)).unwrap_err();
+
+        assert!(matches!(err, InsertError::InvalidName { name, .. } if name == "tenant:A"));
+        reg.insert_in("tenant/A", mock("parser", 2)).unwrap();
+        assert_eq!(reg.get_path("tenant/A:parser").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_names_in_lists_one_namespace() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("plain", 0)).unwrap();
+        reg.insert_in("tenantA", mock("parser", 1)).unwrap();
+        reg.insert_in("tenantA", mock("lexer", 2)).unwrap();
+        reg.insert_in("tenantB", mock("linker", 3)).unwrap();
+
+        let mut names = reg.names_in("tenantA");
+        names.sort();
+        let mut namespaces = reg.namespace_names();
+        namespaces.sort();
+
+        assert_eq!(names, vec!["lexer".to_string(), "parser".to_string()]);
+        assert_eq!(reg.names_in(DEFAULT_NAMESPACE), vec!["plain".to_string()]);
+        assert!(reg.names_in("tenantC").is_empty());
+        assert_eq!(
+            namespaces,
+            vec!["tenantA".to_string(), "tenantB".to_string()]
+        );
+    }
+
+    #[rstest]
+    fn test_remove_namespace_leaves_others() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("plain", 0)).unwrap();
+        reg.insert_in("tenantA", mock("parser", 1)).unwrap();
+        reg.insert_in("tenantA", mock("lexer", 2)).unwrap();
+        reg.insert_in("tenantB", mock("parser", 3)).unwrap();
+
+        let mut removed: Vec<i32> = reg
+            .remove_namespace("tenantA")
+            .iter()
+            .map(|entry| entry.lock().value)
+            .collect();
+        removed.sort();
+
+        assert_eq!(removed, vec![1, 2]);
+        assert!(reg.names_in("tenantA").is_empty());
+        assert_eq!(reg.namespace_names(), vec!["tenantB".to_string()]);
+        assert!(reg.contains("plain"));
+        assert!(reg.remove_namespace("tenantA").is_empty());
+    }
+
+    #[rstest]
+    fn test_remove_namespace_while_another_is_read() {
+        use std::sync::Barrier;
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        for value in 0..100 {
+            reg.insert_in("tenantA", mock(&format!("a{value}"), value))
+                .unwrap();
+        }
+        reg.insert_in("tenantB", mock("reader", 7)).unwrap();
+
+        // the value lock of a tenantB entry is held for the whole removal
+        let held = reg.get_in("tenantB", "reader").unwrap();
+        let guard = held.lock();
+        let barrier = Arc::new(Barrier::new(2));
+
+        let reader = {
+            let (reg, barrier) = (reg.clone(), Arc::clone(&barrier));
+            thread::spawn(move || {
+                barrier.wait();
+                (0..100)
+                    .filter(|_| reg.get_in("tenantB", "reader").is_some())
+                    .count()
+            })
+        };
+
+        barrier.wait();
+        let removed = reg.remove_namespace("tenantA");
+
+        assert_eq!(reader.join().unwrap(), 100);
+        assert_eq!(removed.len(), 100);
+        assert_eq!(guard.value, 7);
+        drop(guard);
+        assert_eq!(reg.names_in("tenantB"), vec!["reader".to_string()]);
+    }
+
+    #[rstest]
+    fn test_namespaces_respect_case_insensitivity() {
+        let reg = case_insensitive();
+        reg.insert_in("tenantA", mock("Parser", 1)).unwrap();
+
+        assert_eq!(reg.get_in("tenantA", "PARSER").unwrap().lock().value, 1);
+        assert_eq!(reg.names_in("tenantA"), vec!["Parser".to_string()]);
+    }
+
+    trait Plugin: HasName {
+        fn run(&self) -> i32;
+        fn bump(&mut self);
+    }
+
+    impl Plugin for InnerMock {
+        fn run(&self) -> i32 {
+            self.value
+        }
+
+        fn bump(&mut self) {
+            self.value += 1;
+        }
+    }
+
+    #[rstest]
+    fn test_trait_object_registry() {
+        let reg: NamedRegistry<Box<dyn Plugin>> = NamedRegistry::new();
+        reg.insert(Box::new(mock("alpha", 1))).unwrap();
+        reg.insert(Box::new(mock("beta", 10))).unwrap();
+
+        reg.mutate("alpha", |plugin| plugin.bump());
+
+        assert_eq!(reg.with("alpha", |plugin| plugin.run()), Some(2));
+        assert_eq!(reg.get("beta").unwrap().lock().run(), 10);
+        assert_eq!(reg.get("beta").unwrap().name(), "beta");
+        assert_eq!(reg.len(), 2);
+    }
+
+    fn trimmed() -> NamedRegistry<InnerMock> {
+        NamedRegistry::new_with_options(RegistryOptions {
+            key_normalizer: Some(Arc::new(|name: &str| name.trim().replace('_', "-"))),
+            ..Default::default()
+        })
+    }
+
+    #[rstest]
+    fn test_key_normalizer_applies_on_lookup() {
+        let reg = trimmed();
+        reg.insert(mock("foo-bar", 1)).unwrap();
+
+        assert!(reg.contains(" foo_bar "));
+        assert_eq!(reg.get("foo_bar ").unwrap().lock().value, 1);
+        assert_eq!(reg.mutate(" foo-bar", |v| v.value += 1), Some(()));
+        assert_eq!(reg.remove("\tfoo_bar").unwrap().lock().value, 2);
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_key_normalizer_applies_on_insert() {
+        let reg = trimmed();
+        reg.insert(mock("  foo_bar\n", 1)).unwrap();
+
+        let previous = reg.insert(mock("foo-bar", 2)).unwrap();
+
+        assert_eq!(previous.unwrap().lock().name, "  foo_bar\n");
+        assert_eq!(reg.keys(), vec![Arc::<str>::from("foo-bar")]);
+        assert_eq!(reg.get("foo-bar").unwrap().lock().value, 2);
+    }
+
+    #[rstest]
+    fn test_key_normalizer_applies_on_rename() {
+        let reg = trimmed();
+        reg.insert(mock("foo", 1)).unwrap();
+        reg.insert(mock("baz", 2)).unwrap();
+
+        reg.rename(" foo ", "bar_qux ").unwrap();
+
+        assert_eq!(reg.get("bar-qux").unwrap().lock().name, "bar_qux ");
+        assert!(!reg.contains("foo"));
+        assert_eq!(
+            reg.rename("bar-qux", " baz"),
+            Err(RenameError::Occupied(" baz".into()))
+        );
+    }
+
+    #[rstest]
+    fn test_validator_checks_name_before_normalizing() {
+        let reg = trimmed();
+        reg.set_name_validator(|name| {
+            if name.trim() == name {
+                Ok(())
+            } else {
+                Err("surrounding whitespace".into())
+            }
+        });
+
+        assert!(reg.insert(mock(" foo ", 1)).is_err());
+        reg.insert(mock("foo_bar", 2)).unwrap();
+
+        assert_eq!(reg.keys(), vec![Arc::<str>::from("foo-bar")]);
+        assert!(matches!(
+            reg.rename("foo-bar", "baz "),
+            Err(RenameError::Rejected(_))
+        ));
+    }
+
+    #[rstest]
+    fn test_key_normalizer_combines_with_case_insensitivity() {
+        let reg = NamedRegistry::new_with_options(RegistryOptions {
+            case_insensitive: true,
+            key_normalizer: Some(Arc::new(|name: &str| name.trim().to_string())),
+            ..Default::default()
+        });
+        reg.insert(mock("Alpha", 1)).unwrap();
+
+        assert_eq!(reg.get(" ALPHA ").unwrap().lock().value, 1);
+        assert_eq!(reg.keys(), vec![Arc::<str>::from("Alpha")]);
+    }
+
+    #[rstest]
+    fn test_insert_with_id_indexes_both_ways() {
+        let reg = NamedRegistry::new();
+        reg.insert_with_id(7, mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+
+        assert!(reg.get_by_id(7).unwrap().ptr_eq(&reg.get("alpha").unwrap()));
+        assert_eq!(reg.id_of("alpha"), Some(7));
+        assert_eq!(reg.id_of("beta"), None);
+        assert!(reg.get_by_id(8).is_none());
+    }
+
+    #[rstest]
+    fn test_insert_with_id_rejects_duplicate_id() {
+        let reg = NamedRegistry::new();
+        reg.insert_with_id(7, mock("alpha", 1)).unwrap();
+
+        let err = reg.insert_with_id(7, mock("beta", 2)).unwrap_err();
+
+        assert_eq!(err, InsertIdError::DuplicateId(7));
+        assert!(!reg.contains("beta"));
+        assert_eq!(reg.get_by_id(7).unwrap().name(), "alpha");
+    }
+
+    #[rstest]
+    fn test_insert_with_id_over_same_name_rebinds() {
+        let reg = NamedRegistry::new();
+        reg.insert_with_id(7, mock("alpha", 1)).unwrap();
+
+        reg.insert_with_id(7, mock("alpha", 2)).unwrap();
+        reg.insert_with_id(8, mock("alpha", 3)).unwrap();
+
+        assert!(reg.get_by_id(7).is_none());
+        assert_eq!(reg.get_by_id(8).unwrap().lock().value, 3);
+        assert_eq!(reg.id_of("alpha"), Some(8));
+    }
+
+    #[rstest]
+    fn test_ids_follow_rename() {
+        let reg = NamedRegistry::new();
+        reg.insert_with_id(7, mock("alpha", 1)).unwrap();
+
+        reg.rename("alpha", "omega").unwrap();
+        reg.mutate("omega", |v| v.name = "psi".into());
+
+        assert_eq!(reg.get_by_id(7).unwrap().name(), "psi");
+        assert_eq!(reg.id_of("psi"), Some(7));
+        assert_eq!(reg.id_of("alpha"), None);
+    }
+
+    #[rstest]
+    fn test_ids_released_on_remove() {
+        let reg = NamedRegistry::new();
+        reg.insert_with_id(7, mock("alpha", 1)).unwrap();
+
+        reg.remove("alpha").unwrap();
+
+        assert!(reg.get_by_id(7).is_none());
+        reg.insert_with_id(7, mock("beta", 2)).unwrap();
+        assert_eq!(reg.get_by_id(7).unwrap().name(), "beta");
+        assert_eq!(reg.take("beta").unwrap().value, 2);
+        assert!(reg.get_by_id(7).is_none());
+    }
+
+    #[rstest]
+    fn test_replace_hands_back_a_mismatched_value() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+
+        let err = reg.replace("alpha", mock("beta", 2)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ReplaceError::Mismatch { name, value } if name == "alpha" && value == mock("beta", 2)
+        ));
+        assert_eq!(reg.with("alpha", |v| v.value), Some(1));
+        assert!(!reg.contains("beta"));
+
+        let folded = case_insensitive();
+        folded.insert(mock("Alpha", 1)).unwrap();
+        let previous = folded.replace("ALPHA", mock("alpha", 2)).unwrap();
+        assert_eq!(previous.map(|v| v.value), Some(1));
+        assert_eq!(folded.len(), 1);
+    }
+
+    #[rstest]
+    fn test_ids_through_replace() {
+        let reg = NamedRegistry::new();
+        reg.insert_with_id(7, mock("alpha", 1)).unwrap();
+
+        reg.replace("alpha", mock("alpha", 2)).unwrap();
+        assert_eq!(reg.get_by_id(7).unwrap().lock().value, 2);
+
+        reg.insert(mock("alpha", 3)).unwrap();
+        assert!(reg.get_by_id(7).is_none());
+        assert_eq!(reg.id_of("alpha"), None);
+    }
+
+    #[rstest]
+    fn test_ids_survive_raw_map_changes() {
+        let reg = NamedRegistry::new();
+        reg.insert_with_id(7, mock("alpha", 1)).unwrap();
+
+        reg.lock().clear();
+
+        assert!(reg.get_by_id(7).is_none());
+        reg.insert_with_id(7, mock("beta", 2)).unwrap();
+        assert_eq!(reg.id_of("beta"), Some(7));
+    }
+
+    #[rstest]
+    #[cfg(feature = "parking_lot")]
+    fn test_parking_lot_locks_never_poison() {
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        reg.insert(mock("p", 1)).unwrap();
+
+        let handle = reg.clone();
+        let result = thread::spawn(move || {
+            let entry = handle.get("p").unwrap();
+            let _map = handle.lock();
+            entry.mutate(|_| panic!("entry failure"));
+        })
+        .join();
+
+        assert!(result.is_err());
+        assert!(reg.health().is_healthy());
+        assert!(reg.try_access().is_ok());
+        assert!(reg.get("p").unwrap().try_access().is_ok());
+    }
+
+    #[rstest]
+    fn test_snapshot_ignores_later_mutations() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+
+        let snapshot = reg.snapshot();
+        reg.mutate("alpha", |v| v.value = 10);
+        reg.remove("beta");
+        reg.insert(mock("gamma", 3)).unwrap();
+
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot.get("alpha").unwrap().value, 1);
+        assert_eq!(snapshot.get("beta").unwrap().value, 2);
+        assert!(!snapshot.contains("gamma"));
+        let mut keys: Vec<&str> = snapshot.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["alpha", "beta"]);
+        assert!(snapshot.recovered().is_empty());
+    }
+
+    #[rstest]
+    fn test_snapshot_reads_do_not_lock() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        let snapshot = reg.snapshot();
+
+        let entry = reg.get("alpha").unwrap();
+        let _value = entry.lock();
+        let _map = reg.lock();
+
+        assert_eq!(snapshot.iter().map(|(_, v)| v.value).sum::<i32>(), 1);
+        assert_eq!(
+            format!("{snapshot:?}"),
+            format!("RegistrySnapshot {{\"alpha\": {:?}}}", mock("alpha", 1))
+        );
+    }
+
+    #[rstest]
+    #[cfg(not(feature = "parking_lot"))]
+    fn test_snapshot_recovers_poisoned_entries() {
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+
+        let handle = reg.clone();
+        let _ = thread::spawn(move || {
+            handle.mutate("beta", |v| {
+                v.value = 20;
+                panic!("mid-update");
+            })
+        })
+        .join();
+
+        let snapshot = reg.snapshot();
+
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot.get("beta").unwrap().value, 20);
+        assert_eq!(snapshot.recovered(), ["beta".to_string()]);
+    }
+
+    #[rstest]
+    fn test_snapshot_follows_key_rules() {
+        let reg = case_insensitive();
+        reg.insert(mock("Alpha", 1)).unwrap();
+
+        let snapshot = reg.snapshot();
+
+        assert_eq!(snapshot.get("ALPHA").unwrap().value, 1);
+        assert_eq!(snapshot.keys().collect::<Vec<_>>(), vec!["Alpha"]);
+    }
+
+    #[rstest]
+    fn test_for_each_entry_allows_reentrant_writes() {
+        let reg = NamedRegistry::new();
+        for name in ["a", "b", "c"] {
+            reg.insert(mock(name, 1)).unwrap();
+        }
+
+        let mut visited = Vec::new();
+        reg.for_each_entry(|name, entry| {
+            // every registry call here would deadlock under a held map lock
+            reg.insert(mock(&format!("{name}-copy"), entry.lock().value))
+                .unwrap();
+            reg.remove(if name == "a" { "b" } else { "a" });
+            visited.push(name.to_string());
+        });
+        visited.sort();
+
+        // pairs removed mid-iteration are still visited from the snapshot
+        assert_eq!(visited, vec!["a", "b", "c"]);
+        assert!(reg.contains("a-copy") && reg.contains("b-copy") && reg.contains("c-copy"));
+        assert!(!reg.contains("a") && !reg.contains("b") && reg.contains("c"));
+    }
+
+    #[rstest]
+    fn test_try_for_each_entry_short_circuits() {
+        let reg = NamedRegistry::new();
+        for (name, value) in [("a", 1), ("b", -1), ("c", 2)] {
+            reg.insert(mock(name, value)).unwrap();
+        }
+
+        let mut calls = 0;
+        let result = reg.try_for_each_entry(|name, entry| {
+            calls += 1;
+            reg.mutate(name, |v| v.value *= 10);
+            match entry.lock().value {
+                value if value < 0 => Err(name.to_string()),
+                _ => Ok(()),
+            }
+        });
+
+        assert_eq!(result, Err("b".into()));
+        assert!(calls <= 3);
+        assert_eq!(reg.with("b", |v| v.value), Some(-10));
+        assert_eq!(reg.try_for_each_entry(|_, _| Ok::<_, ()>(())), Ok(()));
+    }
+
+    #[rstest]
+    fn test_mutate_pair_transfers() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("from", 10)).unwrap();
+        reg.insert(mock("to", 0)).unwrap();
+
+        reg.mutate_pair("from", "to", |from, to| {
+            from.value -= 4;
+            to.value += 4;
+        })
+        .unwrap();
+
+        assert_eq!(reg.with("from", |v| v.value), Some(6));
+        assert_eq!(reg.with("to", |v| v.value), Some(4));
+    }
+
+    #[rstest]
+    fn test_mutate_pair_errors() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("a", 1)).unwrap();
+
+        assert_eq!(
+            reg.mutate_pair("a", "a", |_, _| unreachable!()),
+            Err(PairError::SameEntry("a".into()))
+        );
+        assert_eq!(
+            reg.mutate_pair("a", "missing", |_, _| unreachable!()),
+            Err(PairError::NotFound("missing".into()))
+        );
+        assert_eq!(
+            reg.mutate_pair("missing", "a", |_, _| unreachable!()),
+            Err(PairError::NotFound("missing".into()))
+        );
+    }
+
+    #[rstest]
+    fn test_mutate_pair_rekeys_renames() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("a", 1)).unwrap();
+        reg.insert(mock("b", 2)).unwrap();
+
+        reg.mutate_pair("a", "b", |a, b| std::mem::swap(&mut a.name, &mut b.name))
+            .unwrap();
+
+        assert_eq!(reg.with("a", |v| v.value), Some(2));
+        assert_eq!(reg.with("b", |v| v.value), Some(1));
+    }
+
+    #[rstest]
+    fn test_mutate_pair_stress_conserves_total() {
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        for i in 0..8 {
+            reg.insert(mock(&format!("acct-{i}"), 1_000)).unwrap();
+        }
+
+        let workers: Vec<_> = (0..16u64)
+            .map(|seed| {
+                let reg = reg.clone();
+                thread::spawn(move || {
+                    // xorshift, so each worker walks its own pair sequence
+                    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) + 1;
+                    let mut next = move || {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        state % 8
+                    };
+                    for _ in 0..2_000 {
+                        let (a, b) = (next(), next());
+                        if a == b {
+                            continue;
+                        }
+                        reg.mutate_pair(&format!("acct-{a}"), &format!("acct-{b}"), |a, b| {
+                            let amount = a.value.min(7);
+                            a.value -= amount;
+                            b.value += amount;
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let total: i32 = reg.values().iter().map(|entry| entry.lock().value).sum();
+        assert_eq!(total, 8 * 1_000);
+    }
+
+    #[rstest]
+    fn test_swap_and_mutate_pair_share_a_lock_order() {
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        reg.insert(mock("a", 1)).unwrap();
+        reg.insert(mock("b", 2)).unwrap();
+
+        let (done, finished) = mpsc::channel();
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let (reg, done) = (reg.clone(), done.clone());
+                thread::spawn(move || {
+                    for _ in 0..2_000 {
+                        match worker {
+                            0 => assert!(reg.swap("a", "b")),
+                            1 => assert!(reg.swap("b", "a")),
+                            2 => reg
+                                .mutate_pair("a", "b", |a, b| {
+                                    std::mem::swap(&mut a.value, &mut b.value)
+                                })
+                                .unwrap(),
+                            _ => reg
+                                .mutate_pair("b", "a", |b, a| {
+                                    std::mem::swap(&mut b.value, &mut a.value)
+                                })
+                                .unwrap(),
+                        }
+                    }
+                    done.send(()).unwrap();
+                })
+            })
+            .collect();
+
+        for _ in 0..4 {
+            finished
+                .recv_timeout(Duration::from_secs(10))
+                .expect("swap and mutate_pair deadlocked");
+        }
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        let mut values = [reg.with("a", |v| v.value), reg.with("b", |v| v.value)];
+        values.sort();
+        assert_eq!(values, [Some(1), Some(2)]);
+    }
+
+    #[rstest]
+    #[case::swap(false)]
+    #[case::mutate_pair(true)]
+    fn test_pair_writes_keep_their_entries_registered(#[case] pair: bool) {
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        reg.insert(mock("a", 1)).unwrap();
+        reg.insert(mock("b", 2)).unwrap();
+        let held = reg.get("a").unwrap();
+        let guard = held.lock();
+
+        let writer = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                if pair {
+                    reg.mutate_pair("a", "b", |a, b| std::mem::swap(&mut a.value, &mut b.value))
+                        .is_ok()
+                } else {
+                    reg.swap("a", "b")
+                }
+            })
+        };
+        thread::sleep(Duration::from_millis(50));
+        // the writer has found both entries and waits for `a`; removing it
+        // now must wait for the writer rather than pull it out from under it
+        let remover = {
+            let reg = reg.clone();
+            thread::spawn(move || reg.remove("a").map(|entry| entry.read().value))
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert!(!remover.is_finished());
+
+        drop(guard);
+        assert!(writer.join().unwrap());
+        assert_eq!(remover.join().unwrap(), Some(2));
+        assert_eq!(reg.with("b", |v| v.value), Some(1));
+    }
+
+    fn instrumented() -> NamedRegistry<InnerMock> {
+        NamedRegistry::new_with_options(RegistryOptions {
+            collect_metrics: true,
+            ..Default::default()
+        })
+    }
+
+    #[rstest]
+    fn test_metrics_count_lock_acquisitions() {
+        let reg = instrumented();
+        reg.insert(mock("alpha", 1)).unwrap();
+        let before = reg.metrics();
+
+        reg.get("alpha");
+        reg.contains("alpha");
+        reg.remove("alpha");
+
+        let after = reg.metrics();
+        assert_eq!(after.read_locks - before.read_locks, 2);
+        assert_eq!(after.write_locks - before.write_locks, 1);
+    }
+
+    #[rstest]
+    fn test_metrics_measure_wait_under_contention() {
+        use std::thread;
+
+        let reg = instrumented();
+        reg.insert(mock("alpha", 1)).unwrap();
+
+        let map = reg.lock();
+        let reader = {
+            let reg = reg.clone();
+            thread::spawn(move || reg.get("alpha").is_some())
+        };
+        thread::sleep(Duration::from_millis(50));
+        drop(map);
+        assert!(reader.join().unwrap());
+
+        assert!(reg.metrics().lock_wait >= Duration::from_millis(40));
+    }
+
+    #[rstest]
+    fn test_metrics_count_try_lock_failures() {
+        let reg = instrumented();
+        reg.insert(mock("alpha", 1)).unwrap();
+        let entry = reg.get("alpha").unwrap();
+        let guard = entry.lock();
+
+        assert_eq!(reg.try_mutate("alpha", |v| v.value), TryMutateOutcome::Busy);
+        assert!(reg
+            .mutate_timeout("alpha", Duration::from_millis(5), |v| v.value)
+            .is_err());
+        drop(guard);
+        assert_eq!(
+            reg.try_mutate("alpha", |v| v.value),
+            TryMutateOutcome::Applied(1)
+        );
+
+        assert_eq!(reg.metrics().try_lock_failures, 2);
+    }
+
+    #[rstest]
+    fn test_metrics_stay_zero_when_off() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        let entry = reg.get("alpha").unwrap();
+        let guard = entry.lock();
+        reg.try_mutate("alpha", |v| v.value);
+        drop(guard);
+        reg.remove("alpha");
+
+        assert_eq!(reg.metrics(), RegistryMetrics::default());
+    }
+
+    // the core operations, run once per hasher
+    fn exercise_hasher<S: BuildHasher + Default>(mut reg: NamedRegistry<InnerMock, Arc<str>, S>) {
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.extend([mock("beta", 2), mock("gamma", 3)]);
+        assert_eq!(reg.len(), 3);
+        assert_eq!(reg.with("beta", |v| v.value), Some(2));
+
+        reg.mutate("alpha", |v| v.name = "delta".into());
+        assert!(!reg.contains("alpha"));
+        reg.rename("delta", "alpha").unwrap();
+        assert!(reg.swap("alpha", "gamma"));
+        assert_eq!(reg.with("alpha", |v| v.value), Some(3));
+
+        let copy = reg.deep_clone();
+        copy.mutate("beta", |v| v.value = 20);
+        let diff = reg.diff(&copy);
+        assert_eq!(diff.changed.len(), 1);
+        reg.merge(&copy, MergePolicy::TakeIncoming);
+        assert_eq!(reg.with("beta", |v| v.value), Some(20));
+
+        let split = reg.split_off(|_, v| v.value > 10);
+        assert_eq!(split.keys(), vec![Arc::<str>::from("beta")]);
+        assert!(reg.remove("gamma").is_some());
+        assert_eq!(reg.keys(), vec![Arc::<str>::from("alpha")]);
+
+        let collected: NamedRegistry<InnerMock, Arc<str>, S> =
+            [mock("alpha", 1), mock("beta", 2)].into_iter().collect();
+        assert_eq!(collected.len(), 2);
+    }
+
+    #[rstest]
+    fn test_default_hasher() {
+        exercise_hasher(NamedRegistry::new());
+    }
+
+    #[rstest]
+    fn test_custom_hasher() {
+        use std::hash::{BuildHasherDefault, DefaultHasher};
+
+        exercise_hasher(NamedRegistry::with_hasher(BuildHasherDefault::<
+            DefaultHasher,
+        >::default()));
+        exercise_hasher(NamedRegistry::<_, _, BuildHasherDefault<DefaultHasher>>::default());
+    }
+
+    #[cfg(feature = "ahash")]
+    #[rstest]
+    fn test_fast_registry() {
+        exercise_hasher(FastRegistry::default());
+        exercise_hasher(FastRegistry::with_hasher(ahash::RandomState::new()));
+    }
+
+    #[rstest]
+    fn test_keys_share_the_entry_name() {
+        let mut reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.try_insert(mock("beta", 2)).unwrap();
+        reg.extend([mock("gamma", 3)]);
+        reg.rename("gamma", "delta").unwrap();
+
+        for (key, entry) in reg.iter() {
+            assert!(
+                Arc::ptr_eq(&key, &entry.cached_name()),
+                "{key} is duplicated"
+            );
+        }
+        assert_eq!(reg.len(), 3);
+    }
+
+    #[rstest]
+    fn test_lookups_take_str() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        let name = String::from("alpha");
+
+        assert!(reg.contains(name.as_str()));
+        assert!(reg.get(&*name).is_some());
+        assert_eq!(reg.keys(), vec![Arc::<str>::from("alpha")]);
+    }
+
+    // records every hook call as "event:name"
+    fn hooked() -> (
+        NamedRegistry<InnerMock>,
+        Arc<crate::sync::Mutex<Vec<String>>>,
+    ) {
+        use crate::sync::MutexExt;
+
+        let reg = NamedRegistry::<InnerMock>::new();
+        let log = Arc::new(crate::sync::Mutex::new(Vec::new()));
+        let events = Arc::clone(&log);
+        reg.on_insert(move |name, _| events.acquire().push(format!("insert:{name}")));
+        let events = Arc::clone(&log);
+        reg.on_remove(move |name, _| events.acquire().push(format!("remove:{name}")));
+        let events = Arc::clone(&log);
+        reg.on_replace(move |name, previous, current| {
+            let (from, to) = (previous.lock().value, current.lock().value);
+            events
+                .acquire()
+                .push(format!("replace:{name}:{from}->{to}"));
+        });
+        (reg, log)
+    }
+
+    #[rstest]
+    fn test_hooks_fire_on_insert_replace_and_remove() {
+        use crate::sync::MutexExt;
+
+        let (reg, log) = hooked();
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("alpha", 2)).unwrap();
+        reg.try_insert(mock("beta", 3)).unwrap();
+        reg.upsert(mock("beta", 4));
+        reg.remove("alpha");
+        reg.remove("missing");
+        reg.rename("beta", "gamma").unwrap();
+        reg.clear();
+
+        assert_eq!(
+            *log.acquire(),
+            [
+                "insert:alpha",
+                "replace:alpha:1->2",
+                "insert:beta",
+                "remove:alpha",
+                "remove:beta",
+                "insert:gamma",
+                "remove:gamma",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_hooks_run_in_registration_order_until_unsubscribed() {
+        use crate::sync::{Mutex, MutexExt};
+
+        let reg = NamedRegistry::new();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let ids: Vec<_> = (0..3)
+            .map(|i| {
+                let order = Arc::clone(&order);
+                reg.on_insert(move |_, _| order.acquire().push(i))
+            })
+            .collect();
+
+        reg.insert(mock("alpha", 1)).unwrap();
+        assert!(reg.unsubscribe(ids[1]));
+        assert!(!reg.unsubscribe(ids[1]));
+        reg.insert(mock("beta", 2)).unwrap();
+
+        assert_eq!(*order.acquire(), [0, 1, 2, 0, 2]);
+    }
+
+    #[rstest]
+    fn test_hooks_may_write_to_the_registry() {
+        let reg = NamedRegistry::<InnerMock>::new();
+        let inner = reg.clone();
+        reg.on_insert(move |name, entry| {
+            if !name.ends_with("-shadow") {
+                let value = entry.lock().value;
+                inner
+                    .insert(mock(&format!("{name}-shadow"), value))
+                    .unwrap();
+            }
+        });
+        let inner = reg.clone();
+        reg.on_remove(move |name, _| {
+            inner.remove(&*format!("{name}-shadow"));
+        });
+
+        reg.insert(mock("alpha", 7)).unwrap();
+        assert_eq!(reg.with("alpha-shadow", |v| v.value), Some(7));
+
+        reg.remove("alpha");
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_subscriber_sees_each_change_in_order() {
+        let mut reg = NamedRegistry::new();
+        let events = reg.subscribe();
+
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.mutate("alpha", |v| v.value += 1);
+        reg.update(mock("alpha", 5)).unwrap();
+        reg.replace("alpha", mock("alpha", 6)).unwrap();
+        reg.extend([mock("beta", 2)]);
+        reg.mutate("beta", |v| v.name = "gamma".into());
+        reg.remove("alpha");
+        reg.remove("missing");
+
+        let inserted = |name: &str| RegistryEvent::Inserted { name: name.into() };
+        let removed = |name: &str| RegistryEvent::Removed { name: name.into() };
+        let mutated = |name: &str| RegistryEvent::Mutated { name: name.into() };
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [
+                inserted("alpha"),
+                mutated("alpha"),
+                mutated("alpha"),
+                mutated("alpha"),
+                inserted("beta"),
+                removed("beta"),
+                inserted("gamma"),
+                removed("alpha"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_dropped_subscriber_is_pruned() {
+        let reg = NamedRegistry::new();
+        let kept = reg.subscribe();
+        drop(reg.subscribe());
+
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.mutate("alpha", |v| v.value += 1);
+
+        assert_eq!(reg.0.subscribers.acquire().len(), 1);
+        assert_eq!(kept.try_iter().count(), 2);
+        drop(kept);
+        assert!(reg.remove("alpha").is_some());
+        assert!(reg.0.subscribers.acquire().is_empty());
     }
 
-    pub fn insert(&self, entry: T) -> bool {
-        self.lock()
-            .insert(entry.name(), Entry::new(entry))
-            .is_some()
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_subscribers_each_see_an_insert() {
+        let reg = NamedRegistry::new();
+        let mut first = reg.subscribe_async();
+        let mut second = reg.subscribe_async();
+
+        reg.insert(mock("alpha", 1)).unwrap();
+
+        let inserted = RegistryEvent::Inserted {
+            name: "alpha".into(),
+        };
+        assert_eq!(first.recv().await.unwrap(), inserted);
+        assert_eq!(second.recv().await.unwrap(), inserted);
+        assert_eq!(inserted.name(), Some("alpha"));
     }
 
-    pub fn update(&self, entry: &mut T) {
-        if let Some(existing) = self.get(&entry.name()) {
-            existing.mutate(|inner| *inner = entry.clone());
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_lagged_async_subscriber_does_not_stall_writers() {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let reg = NamedRegistry::new();
+        let mut lagging = reg.subscribe_async();
+
+        for i in 0..BROADCAST_CAPACITY + 10 {
+            reg.insert(mock(&format!("item-{i}"), 0)).unwrap();
         }
+
+        assert_eq!(lagging.recv().await, Err(RecvError::Lagged(10)));
+        assert_eq!(
+            lagging.recv().await.unwrap(),
+            RegistryEvent::Inserted {
+                name: "item-10".into()
+            }
+        );
+        assert_eq!(reg.len(), BROADCAST_CAPACITY + 10);
     }
 
-    pub fn get(&self, name: &str) -> Option<Entry<T>> {
-        self.rlock().get(name).cloned()
+    #[rstest]
+    fn test_wait_for_an_existing_or_missing_name() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+
+        assert!(reg.wait_for("alpha", Duration::ZERO).is_some());
+        let start = Instant::now();
+        assert!(reg.wait_for("missing", Duration::from_millis(30)).is_none());
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        assert_eq!(reg.0.waiters.load(Ordering::SeqCst), 0);
     }
 
-    pub fn contains(&self, name: &str) -> bool {
-        self.rlock().contains_key(name)
+    #[rstest]
+    fn test_many_waiters_wake_on_one_registration() {
+        use std::thread;
+
+        let reg = NamedRegistry::new();
+        let waiters: Vec<_> = (0..8)
+            .map(|_| {
+                let reg = reg.clone();
+                thread::spawn(move || reg.wait_for("alpha", Duration::from_secs(5)))
+            })
+            .collect();
+        while reg.0.waiters.load(Ordering::SeqCst) < 8 {
+            thread::yield_now();
+        }
+
+        reg.insert(mock("alpha", 1)).unwrap();
+
+        let held = reg.get("alpha").unwrap();
+        for waiter in waiters {
+            assert!(waiter.join().unwrap().unwrap().ptr_eq(&held));
+        }
     }
 
-    pub fn mutate<F>(&self, key: &str, f: F) -> bool
-    where
-        F: FnOnce(&mut T),
-    {
-        if let Some(entry) = self.get(key) {
-            entry.mutate(f);
-            return true;
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_waiter_resolves_on_the_surviving_registration() {
+        let reg = NamedRegistry::new();
+        let waiter = {
+            let reg = reg.clone();
+            tokio::spawn(async move { reg.wait_for_async("alpha", Duration::from_secs(5)).await })
+        };
+        tokio::task::yield_now().await;
+
+        // gone again before the waiter gets to look
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.remove("alpha");
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        reg.insert(mock("alpha", 2)).unwrap();
+
+        assert_eq!(waiter.await.unwrap().unwrap().lock().value, 2);
+        assert_eq!(reg.0.waiters.load(Ordering::SeqCst), 0);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_waiters_return_existing_time_out_and_all_wake() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        assert!(reg.wait_for_async("alpha", Duration::ZERO).await.is_some());
+        assert!(reg
+            .wait_for_async("missing", Duration::from_millis(20))
+            .await
+            .is_none());
+
+        let waiters: Vec<_> = (0..8)
+            .map(|_| {
+                let reg = reg.clone();
+                tokio::spawn(
+                    async move { reg.wait_for_async("beta", Duration::from_secs(5)).await },
+                )
+            })
+            .collect();
+        tokio::task::yield_now().await;
+        reg.insert(mock("beta", 2)).unwrap();
+
+        for waiter in waiters {
+            assert_eq!(waiter.await.unwrap().unwrap().lock().value, 2);
         }
-        false
     }
 
-    fn rlock(&self) -> RwLockReadGuard<'_, HashMap<String, Entry<T>>> {
-        self.0.read().unwrap()
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_racing_async_factories_run_once() {
+        use std::sync::atomic::AtomicUsize;
+
+        let reg = NamedRegistry::new();
+        let runs = AtomicUsize::new(0);
+        let factory = || async {
+            runs.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            mock("alpha", 1)
+        };
+
+        let (first, second) = tokio::join!(
+            reg.get_or_insert_with_async("alpha", factory),
+            reg.get_or_insert_with_async("alpha", factory),
+        );
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(first.unwrap().ptr_eq(&second.unwrap()));
+        assert!(reg.0.flights.acquire().is_empty());
+        let again = reg
+            .get_or_insert_with_async("alpha", || async { mock("alpha", 2) })
+            .await
+            .unwrap();
+        assert_eq!(again.lock().value, 1);
     }
 
-    pub fn lock(&self) -> RwLockWriteGuard<'_, HashMap<String, Entry<T>>> {
-        self.0.write().unwrap()
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_failed_async_factory_lets_the_next_caller_retry() {
+        // boxes `Sealed` as readily as the factory's own errors
+        type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+        let reg = NamedRegistry::new();
+        let failing = || async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Err::<InnerMock, BoxError>("unreachable host".into())
+        };
+        let working = || async { Ok::<_, BoxError>(mock("alpha", 1)) };
+
+        let (failed, retried) = tokio::join!(
+            reg.get_or_try_insert_with_async("alpha", failing),
+            reg.get_or_try_insert_with_async("alpha", working),
+        );
+
+        assert_eq!(failed.unwrap_err().to_string(), "unreachable host");
+        assert_eq!(retried.unwrap().lock().value, 1);
+        assert!(reg.0.flights.acquire().is_empty());
+
+        let alone = NamedRegistry::<InnerMock>::new();
+        assert!(alone
+            .get_or_try_insert_with_async("beta", failing)
+            .await
+            .is_err());
+        assert!(!alone.contains("beta"));
+        assert!(alone.0.flights.acquire().is_empty());
+        let beta = alone
+            .get_or_try_insert_with_async("beta", || async { Ok::<_, BoxError>(mock("beta", 2)) })
+            .await;
+        assert_eq!(beta.unwrap().lock().value, 2);
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use rstest::rstest;
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_changes_stream_follows_a_script() {
+        use futures::StreamExt;
 
-    #[derive(Debug, Clone, PartialEq)]
-    struct InnerMock {
-        name: String,
-        value: i32,
+        let reg = NamedRegistry::new();
+        let stream = reg.changes();
+
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+        reg.mutate("alpha", |v| v.value += 1);
+        reg.remove("beta");
+        drop(reg);
+
+        let events: Vec<_> = stream.collect().await;
+        assert_eq!(
+            events,
+            [
+                RegistryEvent::Inserted {
+                    name: "alpha".into()
+                },
+                RegistryEvent::Inserted {
+                    name: "beta".into()
+                },
+                RegistryEvent::Mutated {
+                    name: "alpha".into()
+                },
+                RegistryEvent::Removed {
+                    name: "beta".into()
+                },
+            ]
+        );
     }
 
-    impl HasName for InnerMock {
-        fn name(&self) -> String {
-            self.name.clone()
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_changes_stream_reports_lag() {
+        use futures::StreamExt;
+
+        let reg = NamedRegistry::new();
+        let mut tiny = reg.changes_with_depth(2);
+        let mut roomy = reg.changes();
+        for i in 0..5 {
+            reg.insert(mock(&format!("item-{i}"), i)).unwrap();
         }
+
+        let inserted = |name: &str| RegistryEvent::Inserted { name: name.into() };
+        assert_eq!(tiny.next().await, Some(inserted("item-0")));
+        // a slot is free again, but nothing is queued behind the gap
+        reg.insert(mock("item-5", 5)).unwrap();
+        assert_eq!(tiny.next().await, Some(inserted("item-1")));
+        assert_eq!(tiny.next().await, Some(RegistryEvent::Lagged(4)));
+        reg.insert(mock("item-6", 6)).unwrap();
+        assert_eq!(tiny.next().await, Some(inserted("item-6")));
+
+        let all: Vec<_> = (&mut roomy).take(7).collect().await;
+        assert_eq!(all.last(), Some(&inserted("item-6")));
+        assert!(all.iter().all(|event| event.name().is_some()));
     }
 
     #[rstest]
-    fn test_insert_and_get() {
+    fn test_stats_count_a_scripted_workload() {
         let reg = NamedRegistry::new();
-        let item = InnerMock {
-            name: "alpha".into(),
-            value: 10,
-        };
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+        reg.insert(mock("alpha", 3)).unwrap();
 
-        reg.insert(item.clone());
-        let fetched = reg.get("alpha").unwrap().lock().clone();
+        assert!(reg.get("alpha").is_some());
+        assert!(reg.get("missing").is_none());
+        assert!(reg.get("gone").is_none());
+        assert_eq!(reg.mutate("beta", |v| v.value += 1), Some(()));
+        assert_eq!(reg.mutate("missing", |v| v.value += 1), None);
+        // other reads are not counted
+        assert_eq!(reg.with("alpha", |v| v.value), Some(3));
+        assert!(reg.remove("beta").is_some());
+        assert!(reg.remove("beta").is_none());
 
-        assert_eq!(fetched, item);
-        assert!(reg.contains("alpha"));
+        assert_eq!(
+            reg.stats(),
+            RegistryStats {
+                gets: 3,
+                hits: 2,
+                misses: 3,
+                inserts: 3,
+                overwrites: 1,
+                removes: 1,
+                mutates: 2,
+            }
+        );
+
+        reg.reset_stats();
+        assert_eq!(reg.stats(), RegistryStats::default());
+        reg.get("alpha");
+        assert_eq!(reg.stats().hits, 1);
+    }
+
+    // records spans and events as `kind name field=value ...` lines
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<String>>>);
+
+    #[cfg(feature = "tracing")]
+    #[derive(Default)]
+    struct Fields(String);
+
+    #[cfg(feature = "tracing")]
+    impl tracing::field::Visit for Fields {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0 += &format!(" {field}={value}");
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+            self.0 += &format!(" {field}={value:?}");
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for Captured {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut fields = Fields::default();
+            span.record(&mut fields);
+            let line = format!("span {}{}", span.metadata().name(), fields.0);
+            self.0.acquire().push(line);
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            self.0.acquire().push(format!("event{}", fields.0));
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
     }
 
+    #[cfg(feature = "tracing")]
     #[rstest]
-    fn test_mutate_existing_entry() {
-        let reg = NamedRegistry::new();
-        reg.insert(InnerMock {
-            name: "beta".into(),
-            value: 1,
+    fn test_operations_emit_tracing_events() {
+        let reg = NamedRegistry::new_with_options(RegistryOptions {
+            label: Some("orders".into()),
+            ..RegistryOptions::default()
         });
+        let captured = Captured::default();
 
-        let ok = reg.mutate("beta", |v| v.value += 41);
+        tracing::subscriber::with_default(captured.clone(), || {
+            reg.insert(mock("alpha", 1)).unwrap();
+            reg.insert(mock("alpha", 2)).unwrap();
+            reg.mutate("alpha", |v| v.value += 1);
+            reg.mutate("missing", |v| v.value += 1);
+            reg.update(mock("alpha", 5)).unwrap();
+            reg.update(mock("beta", 5)).unwrap_err();
+            reg.remove("alpha");
+            reg.remove("alpha");
+        });
 
-        assert!(ok);
-        let val = reg.get("beta").unwrap().lock().value;
-        assert_eq!(val, 42);
+        let lines = captured.0.acquire();
+        let events: Vec<_> = lines
+            .iter()
+            .filter_map(|line| line.strip_prefix("event registry=orders "))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                "op=insert name=alpha outcome=inserted",
+                "op=insert name=alpha outcome=overwrote",
+                "op=mutate name=alpha outcome=mutated",
+                "op=mutate outcome=missing",
+                "op=update name=alpha outcome=updated",
+                "op=update name=beta outcome=missing",
+                "op=remove name=alpha outcome=removed",
+                "op=remove outcome=missing",
+            ]
+        );
+        assert!(lines.contains(&"span registry_lock registry=orders mode=exclusive".to_string()));
+        assert!(lines.contains(&"span registry_lock registry=orders mode=shared".to_string()));
+        assert_eq!(reg.label(), Some("orders"));
+    }
+
+    fn audited(capacity: usize) -> NamedRegistry<InnerMock> {
+        NamedRegistry::new_with_options(RegistryOptions {
+            audit: Some(AuditConfig { capacity }),
+            ..RegistryOptions::default()
+        })
     }
 
     #[rstest]
-    fn test_mutate_nonexistent_entry_returns_false() {
-        let reg = NamedRegistry::<InnerMock>::new();
-        let result = reg.mutate("nope", |_| {});
-        assert!(!result);
+    fn test_audit_log_keeps_the_latest_records() {
+        let reg = audited(3);
+        reg.insert_as("alice", mock("alpha", 1)).unwrap();
+        reg.mutate_as("bob", "alpha", |v| v.value += 1);
+        reg.mutate("alpha", |v| v.name = "omega".into());
+        reg.mutate("missing", |v| v.value += 1);
+        audit::with_actor("carol", || reg.remove("omega"));
+
+        let log: Vec<_> = reg
+            .audit_log()
+            .into_iter()
+            .map(|record| (record.op, record.name, record.actor))
+            .collect();
+        assert_eq!(
+            log,
+            [
+                (
+                    AuditOp::Mutate,
+                    "alpha".to_string(),
+                    Some("bob".to_string())
+                ),
+                (AuditOp::Mutate, "omega".to_string(), None),
+                (
+                    AuditOp::Remove,
+                    "omega".to_string(),
+                    Some("carol".to_string())
+                ),
+            ]
+        );
+        assert_eq!(reg.audit_for("omega").len(), 2);
+        assert!(NamedRegistry::<InnerMock>::new().audit_log().is_empty());
     }
 
     #[rstest]
-    fn test_update_replaces_existing_value() {
+    fn test_concurrent_writers_are_all_audited() {
+        use std::thread;
+
+        let reg = audited(10_000);
+        let writers: Vec<_> = (0..8)
+            .map(|t| {
+                let reg = reg.clone();
+                thread::spawn(move || {
+                    let actor = format!("writer-{t}");
+                    for i in 0..100 {
+                        let name = format!("t{t}-{i}");
+                        reg.insert_as(&actor, mock(&name, i)).unwrap();
+                        reg.mutate_as(&actor, name.as_str(), |v| v.value += 1);
+                        reg.remove_as(&actor, name.as_str());
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let log = reg.audit_log();
+        assert_eq!(log.len(), 8 * 100 * 3);
+        for t in 0..8 {
+            let actor = format!("writer-{t}");
+            for i in 0..100 {
+                // each writer's records stay in order among the others'
+                let ops: Vec<_> = reg
+                    .audit_for(&format!("t{t}-{i}"))
+                    .into_iter()
+                    .map(|record| {
+                        assert_eq!(record.actor.as_deref(), Some(actor.as_str()));
+                        record.op
+                    })
+                    .collect();
+                assert_eq!(ops, [AuditOp::Insert, AuditOp::Mutate, AuditOp::Remove]);
+            }
+        }
+    }
+
+    fn ttl_registry() -> (Arc<MockClock>, NamedRegistry<InnerMock>) {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let clock = Arc::new(MockClock(std::sync::Mutex::new(start)));
+        (clock.clone(), NamedRegistry::with_clock(clock))
+    }
+
+    #[rstest]
+    fn test_expired_entries_are_absent_but_held_handles_work() {
+        let (clock, reg) = ttl_registry();
+        reg.insert_with_ttl(mock("session", 1), Duration::from_secs(30))
+            .unwrap();
+        reg.insert(mock("forever", 2)).unwrap();
+        let held = reg.get("session").unwrap();
+        assert_eq!(
+            held.expires_at(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(130))
+        );
+
+        clock.advance(29);
+        assert!(reg.contains("session"));
+        clock.advance(1);
+        assert!(reg.get("session").is_none());
+        assert!(!reg.contains("session"));
+        assert_eq!(reg.mutate("session", |v| v.value += 1), None);
+        assert!(reg.contains("forever"));
+        assert_eq!(reg.len(), 2);
+
+        held.mutate(|v| v.value += 1);
+        assert_eq!(held.with(|v| v.value), 2);
+        assert!(held.is_expired());
+    }
+
+    #[rstest]
+    fn test_multi_key_reads_skip_expired_entries() {
+        let (clock, reg) = ttl_registry();
+        reg.insert_with_ttl(mock("session", 1), Duration::from_secs(30))
+            .unwrap();
+        reg.insert(mock("forever", 2)).unwrap();
+        clock.advance(30);
+        let both = ["session", "forever"];
+
+        assert!(matches!(&reg.get_many(&both)[..], [None, Some(_)]));
+        assert_eq!(reg.get_all_present(&both).len(), 1);
+        assert!(!reg.contains_all(&both));
+        assert!(!reg.contains_any(&["session"]));
+        assert_eq!(reg.missing_from(&both), ["session"]);
+        assert_eq!(reg.batch_mutate(&both, |_, v| v.value += 10), 1);
+        assert_eq!(
+            reg.mutate_pair("session", "forever", |_, _| {}),
+            Err(PairError::NotFound("session".into()))
+        );
+        assert!(!reg.swap("session", "forever"));
+        assert_eq!(reg.with("forever", |v| v.value), Some(12));
+    }
+
+    #[rstest]
+    fn test_purge_expired_drops_only_expired_entries() {
+        let (clock, reg) = ttl_registry();
+        reg.insert_with_ttl(mock("short", 1), Duration::from_secs(10))
+            .unwrap();
+        reg.insert_with_ttl(mock("long", 2), Duration::from_secs(60))
+            .unwrap();
+        reg.insert(mock("forever", 3)).unwrap();
+        let removed = reg.subscribe();
+
+        assert!(reg.purge_expired().is_empty());
+        clock.advance(10);
+        assert_eq!(reg.purge_expired(), ["short"]);
+        assert_eq!(reg.len(), 2);
+        assert_eq!(
+            removed.try_iter().collect::<Vec<_>>(),
+            [RegistryEvent::Removed {
+                name: "short".into()
+            }]
+        );
+
+        clock.advance(3600);
+        assert_eq!(reg.purge_expired(), ["long"]);
+        assert_eq!(reg.keys(), [Arc::<str>::from("forever")]);
+    }
+
+    #[rstest]
+    fn test_touch_restarts_the_ttl() {
+        let (clock, reg) = ttl_registry();
+        reg.insert_with_ttl(mock("session", 1), Duration::from_secs(30))
+            .unwrap();
+        reg.insert(mock("forever", 2)).unwrap();
+
+        clock.advance(20);
+        assert!(reg.touch("session"));
+        clock.advance(20);
+        assert!(reg.contains("session"));
+        assert!(!reg.touch("forever"));
+        assert!(!reg.touch("missing"));
+
+        clock.advance(10);
+        assert!(!reg.touch("session"));
+        assert!(!reg.contains("session"));
+    }
+
+    #[rstest]
+    fn test_bounded_registry_evicts_least_recently_used() {
+        let reg = NamedRegistry::bounded(3, EvictionPolicy::Lru);
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&evicted);
+        reg.on_remove(move |name, _| log.acquire().push(name.to_string()));
+
+        for (i, name) in ["a", "b", "c"].into_iter().enumerate() {
+            reg.insert(mock(name, i as i32)).unwrap();
+        }
+        let held = reg.get("a").unwrap();
+        reg.insert(mock("d", 3)).unwrap();
+        reg.with("c", |v| v.value);
+        reg.insert(mock("e", 4)).unwrap();
+        // overwriting a present key needs no room
+        reg.insert(mock("e", 5)).unwrap();
+
+        assert_eq!(*evicted.acquire(), ["b", "a"]);
+        let mut keys = reg.keys();
+        keys.sort();
+        assert_eq!(keys, ["c", "d", "e"].map(Arc::<str>::from));
+
+        held.mutate(|v| v.value += 10);
+        assert_eq!(held.with(|v| v.value), 10);
+        assert!(!reg.contains("a"));
+    }
+
+    #[rstest]
+    fn test_bounded_registry_can_reject_new_entries() {
+        let reg = NamedRegistry::bounded(2, EvictionPolicy::RejectNew);
+        reg.insert(mock("a", 1)).unwrap();
+        reg.insert(mock("b", 2)).unwrap();
+
+        assert_eq!(
+            reg.insert(mock("c", 3)),
+            Err(InsertError::Full {
+                name: "c".into(),
+                capacity: 2,
+            })
+        );
+        assert!(reg.insert(mock("a", 10)).unwrap().is_some());
+        assert_eq!(reg.len(), 2);
+        assert!(!reg.contains("c"));
+    }
+
+    #[rstest]
+    #[case::insert_with_id(|reg: &Mocks| reg.insert_with_id(7, mock("b", 2)).is_ok())]
+    #[case::get_or_insert_with(|reg: &Mocks| reg.get_or_insert_with("b", || mock("b", 2)).is_ok())]
+    #[case::upsert(|reg: &Mocks| reg.upsert(mock("b", 2)) == UpsertOutcome::Inserted)]
+    #[case::try_insert(|reg: &Mocks| reg.try_insert(mock("b", 2)).is_ok())]
+    #[case::try_extend(|reg: &Mocks| reg.try_extend([mock("b", 2)]).is_empty())]
+    #[case::replace(|reg: &Mocks| reg.replace("b", mock("b", 2)).is_ok())]
+    #[case::extend(|reg: &Mocks| {
+        reg.clone().extend([mock("b", 2)]);
+        reg.contains("b")
+    })]
+    #[case::merge(|reg: &Mocks| {
+        reg.merge(&NamedRegistry::from_entries(vec![mock("b", 2)]), MergePolicy::KeepExisting);
+        reg.contains("b")
+    })]
+    fn test_every_insert_respects_the_bound(#[case] insert_b: fn(&Mocks) -> bool) {
+        let reject = NamedRegistry::bounded(1, EvictionPolicy::RejectNew);
+        reject.insert(mock("a", 1)).unwrap();
+        assert!(!insert_b(&reject));
+        assert_eq!(reject.keys(), ["a"].map(Arc::<str>::from));
+
+        let lru = NamedRegistry::bounded(1, EvictionPolicy::Lru);
+        lru.insert(mock("a", 1)).unwrap();
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&removed);
+        lru.on_remove(move |name, _| log.acquire().push(name.to_string()));
+        assert!(insert_b(&lru));
+        assert_eq!(lru.keys(), ["b"].map(Arc::<str>::from));
+        assert_eq!(*removed.acquire(), ["a"]);
+        assert_eq!(lru.stats().inserts, 2);
+    }
+
+    #[rstest]
+    #[should_panic(expected = "room for an entry")]
+    fn test_zero_bound_panics() {
+        NamedRegistry::<InnerMock>::bounded(0, EvictionPolicy::Lru);
+    }
+
+    #[rstest]
+    fn test_generation_counts_changes_made_through_the_registry() {
         let reg = NamedRegistry::new();
+        assert_eq!(reg.generation(), 0);
 
-        reg.insert(InnerMock {
-            name: "gamma".into(),
-            value: 5,
-        });
-        reg.update(&mut InnerMock {
-            name: "gamma".into(),
-            value: 100,
-        });
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+        let start = reg.generation();
+        assert_eq!(start, 2);
+        assert_eq!(reg.changed_since(start), Some(vec![]));
 
-        let val = reg.get("gamma").unwrap().lock().value;
-        assert_eq!(val, 100);
+        reg.mutate("alpha", |v| v.value += 1);
+        reg.with("beta", |v| v.value);
+        reg.mutate("missing", |v| v.value += 1);
+        reg.mutate("beta", |v| v.name = "gamma".into());
+        reg.remove("alpha");
+
+        assert_eq!(reg.generation(), start + 3);
+        assert_eq!(
+            reg.changed_since(start),
+            Some(vec!["alpha".into(), "beta".into(), "gamma".into()])
+        );
+        assert_eq!(
+            reg.changed_since(start + 2),
+            Some(vec!["alpha".to_string()])
+        );
+        assert_eq!(reg.changed_since(0).unwrap().len(), 3);
     }
 
     #[rstest]
-    fn test_contains_and_get() {
+    fn test_changed_since_is_none_once_the_journal_wraps() {
         let reg = NamedRegistry::new();
-        reg.insert(InnerMock {
-            name: "delta".into(),
-            value: 7,
-        });
+        reg.insert(mock("alpha", 0)).unwrap();
+        let start = reg.generation();
 
-        assert!(reg.contains("delta"));
-        assert!(!reg.contains("unknown"));
+        for _ in 0..JOURNAL_CAPACITY {
+            reg.mutate("alpha", |v| v.value += 1);
+        }
+        assert_eq!(reg.changed_since(start), Some(vec!["alpha".to_string()]));
+        assert_eq!(reg.changed_since(start - 1), None);
 
-        let val = reg.get("delta").unwrap().lock().value;
-        assert_eq!(val, 7);
+        reg.mutate("alpha", |v| v.value += 1);
+        assert_eq!(reg.changed_since(start), None);
+        assert!(reg.changed_since(start + 1).is_some());
     }
 
     #[rstest]
-    fn test_multiple_entries_concurrent_mutation() {
-        use std::thread;
+    fn test_sealed_registry_rejects_structural_writes() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        let held = reg.get("alpha").unwrap();
+        reg.seal();
+        assert!(reg.is_sealed());
 
-        let reg = Arc::new(NamedRegistry::new());
-        reg.insert(InnerMock {
-            name: "x".into(),
-            value: 0,
-        });
-        reg.insert(InnerMock {
-            name: "y".into(),
-            value: 100,
-        });
+        assert_eq!(reg.insert(mock("beta", 2)), Err(InsertError::Sealed));
+        assert!(matches!(
+            reg.try_insert(mock("beta", 2)),
+            Err(TryInsertError::Rejected(InsertError::Sealed))
+        ));
+        assert_eq!(reg.rename("alpha", "omega"), Err(RenameError::Sealed));
+        assert_eq!(reg.try_remove("alpha").map(|_| ()), Err(Sealed));
+        assert!(reg.remove("alpha").is_none());
+        assert_eq!(reg.try_clear().map(|_| ()), Err(Sealed));
+        assert!(reg.clear().is_empty());
 
-        let reg1 = reg.clone();
-        let t1 = thread::spawn(move || {
-            reg1.mutate("x", |v| v.value += 10);
-        });
+        assert_eq!(reg.keys(), [Arc::<str>::from("alpha")]);
+        assert_eq!(reg.mutate("alpha", |v| v.value += 1), Some(()));
+        held.mutate(|v| v.value += 1);
+        assert_eq!(reg.with("alpha", |v| v.value), Some(3));
+        assert_eq!(Sealed.to_string(), "the registry is sealed");
+    }
 
-        let reg2 = reg.clone();
-        let t2 = thread::spawn(move || {
-            reg2.mutate("y", |v| v.value -= 50);
-        });
+    // the registry the sealed-write cases below run against
+    type Mocks = NamedRegistry<InnerMock>;
 
-        t1.join().unwrap();
-        t2.join().unwrap();
+    #[rstest]
+    #[case::insert_many(|reg: &Mocks| assert_eq!(
+        reg.insert_many([mock("beta", 2)], InsertPolicy::Overwrite),
+        Err(InsertManyError::Rejected(InsertError::Sealed))
+    ))]
+    #[case::try_extend(|reg: &Mocks| assert_eq!(reg.try_extend([mock("beta", 2)]), [mock("beta", 2)]))]
+    #[case::upsert(|reg: &Mocks| assert_eq!(reg.upsert(mock("beta", 2)), UpsertOutcome::Sealed))]
+    #[case::update_or_insert(|reg: &Mocks| assert_eq!(
+        reg.update_or_insert(mock("beta", 2)),
+        UpsertOutcome::Sealed
+    ))]
+    #[case::replace(|reg: &Mocks| assert!(matches!(
+        reg.replace("beta", mock("beta", 2)),
+        Err(ReplaceError::Sealed(value)) if value == mock("beta", 2)
+    )))]
+    #[case::get_or_insert_with(|reg: &Mocks| assert_eq!(
+        reg.get_or_insert_with("beta", || unreachable!()).map(|_| ()),
+        Err(InsertError::Sealed)
+    ))]
+    #[case::extend(|reg: &Mocks| reg.clone().extend([mock("beta", 2), mock("alpha", 3)]))]
+    #[case::merge(|reg: &Mocks| {
+        let other = NamedRegistry::new();
+        other.insert(mock("beta", 2)).unwrap();
+        reg.merge(&other, MergePolicy::TakeIncoming);
+    })]
+    #[case::insert_in(|reg: &Mocks| assert_eq!(
+        reg.insert_in("ns", mock("beta", 2)).map(|_| ()),
+        Err(InsertError::Sealed)
+    ))]
+    #[case::insert_with_id(|reg: &Mocks| assert_eq!(
+        reg.insert_with_id(7, mock("beta", 2)).map(|_| ()),
+        Err(InsertIdError::Rejected(InsertError::Sealed))
+    ))]
+    #[case::take(|reg: &Mocks| assert_eq!(reg.take("alpha"), Err(TakeError::Sealed)))]
+    #[case::pop_first(|reg: &Mocks| assert!(reg.pop_first().is_none()))]
+    #[case::pop_any(|reg: &Mocks| assert!(reg.pop_any().is_none()))]
+    #[case::drain(|reg: &Mocks| assert_eq!(reg.drain().count(), 0))]
+    #[case::clear_and_drop(|reg: &Mocks| reg.clear_and_drop())]
+    #[case::retain(|reg: &Mocks| assert_eq!(reg.retain(|_, _| unreachable!()), 0))]
+    #[case::split_off(|reg: &Mocks| assert!(reg.split_off(|_, _| true).is_empty()))]
+    #[case::purge_expired(|reg: &Mocks| assert!(reg.purge_expired().is_empty()))]
+    #[case::remove_namespace(|reg: &Mocks| assert!(reg.remove_namespace("ns").is_empty()))]
+    fn test_sealed_registry_keeps_its_names(#[case] write: fn(&Mocks)) {
+        let (clock, reg) = ttl_registry();
+        reg.insert_with_ttl(mock("alpha", 1), Duration::from_secs(10))
+            .unwrap();
+        reg.insert_in("ns", mock("alpha", 1)).unwrap();
+        clock.advance(10);
+        reg.seal();
 
-        assert_eq!(reg.get("x").unwrap().lock().value, 10);
-        assert_eq!(reg.get("y").unwrap().lock().value, 50);
+        write(&reg);
+
+        assert_eq!(reg.keys(), [Arc::<str>::from("alpha")]);
+        assert_eq!(reg.names_in("ns"), ["alpha"]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_factory_must_build_the_requested_key() {
+        let reg = case_insensitive();
+
+        let built = reg
+            .get_or_insert_with_async("alpha", || async { mock("Alpha", 1) })
+            .await;
+        assert!(built.is_ok());
+        let mismatched = reg
+            .get_or_insert_with_async("beta", || async { mock("gamma", 2) })
+            .await;
+
+        assert!(
+            matches!(mismatched, Err(InsertError::Mismatch { name, found })
+            if name == "beta" && found == "gamma")
+        );
+        assert_eq!(reg.len(), 1);
+        assert!(!reg.contains("gamma"));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_sealed_registry_refuses_async_inserts() {
+        let reg = NamedRegistry::<InnerMock>::new();
+        reg.seal();
+
+        let plain = reg
+            .get_or_insert_with_async("alpha", || async { unreachable!() })
+            .await;
+        let fallible = reg
+            .get_or_try_insert_with_async("alpha", || async {
+                unreachable!() as Result<InnerMock, Box<dyn std::error::Error + Send + Sync>>
+            })
+            .await;
+
+        assert_eq!(plain.map(|_| ()), Err(InsertError::Sealed));
+        assert_eq!(fallible.unwrap_err().to_string(), Sealed.to_string());
+        assert!(reg.is_empty());
     }
 
     #[rstest]
-    fn test_lock_and_rlock_consistency() {
+    fn test_seal_deep_freezes_entries() {
         let reg = NamedRegistry::new();
-        reg.insert(InnerMock {
-            name: "omega".into(),
-            value: 9,
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert_in("ns", mock("alpha", 1)).unwrap();
+        let held = reg.get("alpha").unwrap();
+        reg.seal_deep();
+
+        assert!(reg.is_sealed());
+        assert!(held.is_frozen());
+        assert!(reg.get_in("ns", "alpha").unwrap().is_frozen());
+        assert_eq!(reg.upsert(mock("alpha", 5)), UpsertOutcome::Frozen);
+        assert!(matches!(
+            reg.replace("alpha", mock("alpha", 5)),
+            Err(ReplaceError::Frozen(value)) if value == mock("alpha", 5)
+        ));
+        reg.merge(
+            &NamedRegistry::from_entries(vec![mock("alpha", 5)]),
+            MergePolicy::TakeIncoming,
+        );
+        assert!(held.checked_mutate(|v| v.value += 1).is_err());
+        assert_eq!(reg.with("alpha", |v| v.value), Some(1));
+        assert_eq!(reg.insert(mock("beta", 2)), Err(InsertError::Sealed));
+        assert_eq!(
+            reg.insert_in("ns", mock("beta", 2)).map(|_| ()),
+            Err(InsertError::Sealed)
+        );
+    }
+
+    #[rstest]
+    fn test_soft_removed_entries_can_be_restored() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+        let held = reg.get("alpha").unwrap();
+
+        assert!(reg.soft_remove("alpha"));
+        assert!(!reg.soft_remove("alpha"));
+        assert!(reg.get("alpha").is_none());
+        assert!(!reg.contains("alpha"));
+        assert_eq!(reg.keys(), [Arc::<str>::from("beta")]);
+        assert_eq!(reg.tombstones(), ["alpha"]);
+
+        held.mutate(|v| v.value += 1);
+        assert!(reg.restore("alpha"));
+        assert!(!reg.restore("alpha"));
+        assert!(reg.get("alpha").unwrap().ptr_eq(&held));
+        assert_eq!(reg.with("alpha", |v| v.value), Some(2));
+        assert!(reg.tombstones().is_empty());
+    }
+
+    #[rstest]
+    fn test_purge_tombstones_drops_old_ones() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let clock = Arc::new(MockClock(std::sync::Mutex::new(start)));
+        let reg = NamedRegistry::with_clock(clock.clone());
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+
+        reg.soft_remove("alpha");
+        clock.advance(30);
+        reg.soft_remove("beta");
+        clock.advance(30);
+
+        assert_eq!(reg.purge_tombstones(Duration::from_secs(45)), 1);
+        assert_eq!(reg.tombstones(), ["beta"]);
+        assert!(!reg.restore("alpha"));
+        assert_eq!(reg.purge_tombstones(Duration::from_secs(30)), 1);
+        assert!(!reg.restore("beta"));
+    }
+
+    #[rstest]
+    #[case::discard(TombstonePolicy::Discard, false)]
+    #[case::keep(TombstonePolicy::Keep, true)]
+    fn test_reinsert_over_tombstone(#[case] policy: TombstonePolicy, #[case] kept: bool) {
+        let reg = NamedRegistry::new_with_options(RegistryOptions {
+            tombstones: policy,
+            ..RegistryOptions::default()
         });
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.soft_remove("alpha");
 
-        {
-            let map = reg.rlock();
-            assert!(map.contains_key("omega"));
+        reg.insert(mock("alpha", 2)).unwrap();
+        assert!(!reg.restore("alpha"));
+        assert_eq!(reg.tombstones().len(), usize::from(kept));
+
+        reg.remove("alpha");
+        assert_eq!(reg.restore("alpha"), kept);
+        if kept {
+            assert_eq!(reg.with("alpha", |v| v.value), Some(1));
         }
+    }
 
-        {
-            let mut map = reg.lock();
-            map.insert(
-                "phi".into(),
-                Entry::new(InnerMock {
-                    name: "phi".into(),
-                    value: 33,
-                }),
-            );
+    #[rstest]
+    fn test_externally_referenced_lists_held_entries() {
+        let reg = NamedRegistry::new();
+        for name in ["alpha", "beta", "gamma"] {
+            reg.insert(mock(name, 0)).unwrap();
         }
+        assert!(reg.externally_referenced().is_empty());
 
-        assert!(reg.contains("phi"));
+        let leaked = [reg.get("alpha").unwrap(), reg.get("alpha").unwrap()];
+        let arc = reg.get("gamma").unwrap().arc();
+        let weak = reg.get("beta").unwrap().weak();
+        // a temporary handle is gone again once dropped
+        reg.with("beta", |v| v.value);
+
+        let mut held = reg.externally_referenced();
+        held.sort();
+        assert_eq!(held, [("alpha".to_string(), 2), ("gamma".to_string(), 1)]);
+
+        drop((leaked, arc, weak));
+        assert!(reg.externally_referenced().is_empty());
+    }
+
+    #[rstest]
+    fn test_on_mutate_sees_in_place_writes() {
+        let reg = NamedRegistry::<InnerMock>::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        let id = reg.on_mutate(move |name, entry| {
+            log.acquire().push(format!("{name}={}", entry.lock().value));
+        });
+
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.mutate("alpha", |v| v.value += 1);
+        reg.update(mock("alpha", 5)).unwrap();
+        reg.insert(mock("alpha", 6)).unwrap();
+        assert!(reg.unsubscribe(id));
+        reg.mutate("alpha", |v| v.value += 1);
+
+        assert_eq!(*seen.acquire(), ["alpha=2", "alpha=5"]);
     }
 }
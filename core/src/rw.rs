@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use crate::entry::HasName;
+use crate::registry::NamedRegistry;
+use crate::sync::{RwLock, RwLockExt, RwLockReadGuard, RwLockWriteGuard};
+
+/// A shared value behind an `RwLock`, for values that are read far more often
+/// than they are written. Readers do not serialize each other.
+#[derive(Debug)]
+pub struct RwEntry<T>(Arc<RwLock<T>>);
+
+impl<T> Clone for RwEntry<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> RwEntry<T> {
+    pub fn new(inner: T) -> Self {
+        Self(Arc::new(RwLock::new(inner)))
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.0.shared()
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.0.exclusive()
+    }
+
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.read())
+    }
+
+    pub fn mutate<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut self.write())
+    }
+
+    /// The shared value, in the same backend-independent form as
+    /// `Entry::arc`.
+    pub fn arc(&self) -> SharedRwValue<T> {
+        SharedRwValue(Arc::clone(&self.0))
+    }
+}
+
+impl<T> From<SharedRwValue<T>> for RwEntry<T> {
+    fn from(SharedRwValue(inner): SharedRwValue<T>) -> Self {
+        Self(inner)
+    }
+}
+
+/// A bare shared value, as handed out by `RwEntry::arc`. Its API is the same
+/// whichever lock backend the crate is built with.
+pub struct SharedRwValue<T>(Arc<RwLock<T>>);
+
+impl<T> SharedRwValue<T> {
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(RwLock::new(value)))
+    }
+
+    /// Read-locks the value, recovering it from a poisoned lock.
+    pub fn read(&self) -> ValueReadGuard<'_, T> {
+        ValueReadGuard(self.0.shared())
+    }
+
+    /// Write-locks the value, recovering it from a poisoned lock.
+    pub fn write(&self) -> ValueWriteGuard<'_, T> {
+        ValueWriteGuard(self.0.exclusive())
+    }
+
+    /// Whether both handles share one value.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T> Clone for SharedRwValue<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedRwValue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedRwValue").field(&self.0).finish()
+    }
+}
+
+/// A read lock on a `SharedRwValue`.
+pub struct ValueReadGuard<'a, T>(RwLockReadGuard<'a, T>);
+
+impl<T> Deref for ValueReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// A write lock on a `SharedRwValue`.
+pub struct ValueWriteGuard<'a, T>(RwLockWriteGuard<'a, T>);
+
+impl<T> Deref for ValueWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ValueWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Takes a read lock, so it must not be called while the same thread holds
+/// `write()`.
+impl<T> HasName for RwEntry<T>
+where
+    T: HasName,
+{
+    fn name(&self) -> String {
+        self.read().name()
+    }
+}
+
+/// The full `NamedRegistry` API over `RwEntry` values. Each registry entry
+/// only guards the handle, which `get_cloned` copies out; readers of the value
+/// itself share its `RwLock`. Renames made through `RwEntry::write` are not
+/// seen by the registry, so rename through `mutate` on the registry instead.
+pub type RwNamedRegistry<T> = NamedRegistry<RwEntry<T>>;
+
+/// A leaner registry of `RwEntry` values, with no per-entry lock besides the
+/// value's own. It offers the core lookup and mutation API of
+/// `NamedRegistry`, which stays the default, Mutex-backed choice.
+#[derive(Debug)]
+pub struct RwRegistry<T>(Arc<RwLock<HashMap<String, RwEntry<T>>>>);
+
+impl<T> Clone for RwRegistry<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> RwRegistry<T>
+where
+    T: HasName,
+{
+    pub fn new() -> Self {
+        Self(Arc::new(RwLock::new(HashMap::new())))
+    }
+
+    pub fn insert(&self, value: T) -> Option<RwEntry<T>> {
+        self.lock().insert(value.name(), RwEntry::new(value))
+    }
+
+    pub fn get(&self, name: &str) -> Option<RwEntry<T>> {
+        self.rlock().get(name).cloned()
+    }
+
+    pub fn remove(&self, name: &str) -> Option<RwEntry<T>> {
+        self.lock().remove(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.rlock().contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rlock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rlock().is_empty()
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.rlock().keys().cloned().collect()
+    }
+
+    pub fn with<F, R>(&self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.get(name).map(|entry| entry.with(f))
+    }
+
+    /// Runs `f` on the value under `name`. A value renamed by `f` is re-keyed
+    /// under its new name, replacing any entry already there, as in
+    /// `NamedRegistry::mutate`.
+    pub fn mutate<F, R>(&self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let entry = self.get(name)?;
+        let (result, renamed) = {
+            let mut value = entry.write();
+            let result = f(&mut value);
+            (result, value.name())
+        };
+        if renamed != name {
+            let mut map = self.lock();
+            // re-keyed only if no one removed or replaced it meanwhile
+            if map
+                .get(name)
+                .is_some_and(|current| Arc::ptr_eq(&current.0, &entry.0))
+            {
+                map.remove(name);
+                map.insert(renamed, entry);
+            }
+        }
+        Some(result)
+    }
+
+    fn rlock(&self) -> RwLockReadGuard<'_, HashMap<String, RwEntry<T>>> {
+        self.0.shared()
+    }
+
+    fn lock(&self) -> RwLockWriteGuard<'_, HashMap<String, RwEntry<T>>> {
+        self.0.exclusive()
+    }
+}
+
+impl<T> Default for RwRegistry<T>
+where
+    T: HasName,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct InnerMock {
+        name: String,
+        left: i64,
+        right: i64,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn mock(name: &str) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            left: 0,
+            right: 0,
+        }
+    }
+
+    #[rstest]
+    fn test_entry_read_write() {
+        let entry = RwEntry::new(mock("alpha"));
+
+        entry.write().left = 1;
+        entry.mutate(|v| v.right = 2);
+
+        let (left, right) = entry.with(|v| (v.left, v.right));
+        assert_eq!((left, right), (1, 2));
+        assert_eq!(entry.read().name, "alpha");
+        assert_eq!(entry.name(), "alpha");
+    }
+
+    #[rstest]
+    fn test_arc_shares_the_value() {
+        let entry = RwEntry::new(mock("alpha"));
+        let shared = entry.arc();
+
+        shared.write().left = 1;
+        assert_eq!(entry.with(|v| v.left), 1);
+        assert!(shared.ptr_eq(&entry.arc()));
+
+        let adopted = RwEntry::from(shared.clone());
+        adopted.mutate(|v| v.right = 2);
+        assert_eq!(shared.read().right, 2);
+    }
+
+    #[rstest]
+    fn test_concurrent_readers() {
+        let entry = RwEntry::new(mock("alpha"));
+
+        let first = entry.read();
+        let second = entry.read();
+
+        assert_eq!(first.left, second.left);
+    }
+
+    #[rstest]
+    fn test_registry_basics() {
+        let reg = RwRegistry::new();
+        assert!(reg.insert(mock("alpha")).is_none());
+        assert!(reg.insert(mock("beta")).is_none());
+
+        assert_eq!(reg.mutate("alpha", |v| v.left += 1), Some(()));
+        assert_eq!(reg.with("alpha", |v| v.left), Some(1));
+        assert_eq!(reg.with("missing", |v| v.left), None);
+        assert_eq!(reg.len(), 2);
+
+        assert!(reg.remove("beta").is_some());
+        assert!(!reg.contains("beta"));
+        assert_eq!(reg.keys(), vec!["alpha".to_string()]);
+    }
+
+    #[rstest]
+    fn test_registry_mutate_rekeys_renamed_values() {
+        let reg = RwRegistry::new();
+        reg.insert(mock("alpha"));
+        reg.insert(mock("beta"));
+        let held = reg.get("alpha").unwrap();
+
+        reg.mutate("alpha", |v| v.name = "gamma".into());
+        reg.mutate("gamma", |v| v.name = "beta".into());
+
+        assert_eq!(reg.keys(), ["beta"]);
+        assert!(Arc::ptr_eq(&reg.get("beta").unwrap().0, &held.0));
+    }
+
+    #[rstest]
+    fn test_named_registry_over_rw_entries() {
+        let reg = RwNamedRegistry::new();
+        reg.insert(RwEntry::new(mock("alpha"))).unwrap();
+
+        let handle = reg.get_cloned("alpha").unwrap();
+        let (first, second) = (handle.read(), handle.read());
+        assert_eq!(first.left, second.left);
+        drop((first, second));
+
+        handle.mutate(|v| v.left = 1);
+        assert_eq!(reg.get_cloned("alpha").unwrap().with(|v| v.left), 1);
+        reg.mutate("alpha", |entry| entry.write().name = "omega".into());
+        assert!(reg.contains("omega"));
+        assert!(!reg.contains("alpha"));
+    }
+
+    #[rstest]
+    fn test_many_readers_one_writer_see_no_torn_values() {
+        use std::thread;
+
+        let reg = RwRegistry::new();
+        reg.insert(mock("pair"));
+
+        let writer = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                for i in 1..=1_000 {
+                    reg.mutate("pair", |v| {
+                        v.left = i;
+                        v.right = -i;
+                    });
+                }
+            })
+        };
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let reg = reg.clone();
+                thread::spawn(move || {
+                    for _ in 0..1_000 {
+                        let (left, right) = reg.with("pair", |v| (v.left, v.right)).unwrap();
+                        assert_eq!(left, -right);
+                    }
+                })
+            })
+            .collect();
+
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(reg.with("pair", |v| v.left), Some(1_000));
+    }
+}
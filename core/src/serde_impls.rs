@@ -0,0 +1,187 @@
+//! `Serialize`/`Deserialize` impls, enabled by the `serde` feature.
+
+use std::fmt;
+use std::hash::BuildHasher;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::entry::{Entry, HasName};
+use crate::registry::NamedRegistry;
+
+/// Serializes a snapshot of the current value, taken under the entry's lock.
+impl<T> Serialize for Entry<T>
+where
+    T: HasName + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.lock().serialize(serializer)
+    }
+}
+
+/// Builds a fresh entry. Identity is not preserved: handles that shared one
+/// entry before serializing come back as independent entries.
+impl<'de, T> Deserialize<'de> for Entry<T>
+where
+    T: HasName + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Entry::new)
+    }
+}
+
+/// A map of name to value, sorted by name and written under the map's read
+/// lock, each value under its entry's lock. Only the default namespace is
+/// written; metadata, versions and hooks are not.
+impl<T, S> Serialize for NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + Serialize,
+    S: BuildHasher + Default,
+{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let map = self.rlock();
+        let mut entries: Vec<_> = map.values().map(|entry| (entry.name(), entry)).collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut out = serializer.serialize_map(Some(entries.len()))?;
+        for (name, entry) in entries {
+            out.serialize_entry(&name, &*entry.lock())?;
+        }
+        out.end()
+    }
+}
+
+/// Reads the map `Serialize` writes. Each key must equal its value's name
+/// and appear once; values are not re-keyed.
+impl<'de, T, S> Deserialize<'de> for NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RegistryVisitor<T, S>(PhantomData<(T, S)>);
+
+        impl<'de, T, S> Visitor<'de> for RegistryVisitor<T, S>
+        where
+            T: HasName + Deserialize<'de>,
+            S: BuildHasher + Default,
+        {
+            type Value = NamedRegistry<T, Arc<str>, S>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of names to values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let registry = NamedRegistry::with_hasher(S::default());
+                while let Some(name) = access.next_key::<String>()? {
+                    let value: T = access
+                        .next_value()
+                        .map_err(|err| de::Error::custom(format_args!("`{name}`: {err}")))?;
+                    if *value.name_ref() != name {
+                        return Err(de::Error::custom(format_args!(
+                            "key `{name}` does not match the value's name `{}`",
+                            value.name_ref()
+                        )));
+                    }
+                    if registry.contains(name.as_str()) {
+                        return Err(de::Error::custom(format_args!("duplicate name `{name}`")));
+                    }
+                    registry.insert(value).map_err(de::Error::custom)?;
+                }
+                Ok(registry)
+            }
+        }
+
+        deserializer.deserialize_map(RegistryVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    #[rstest]
+    fn test_entry_round_trip() {
+        let entry = Entry::new(InnerMock {
+            name: "alpha".into(),
+            value: 3,
+        });
+
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(json, r#"{"name":"alpha","value":3}"#);
+
+        let back: Entry<InnerMock> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.name(), "alpha");
+        assert_eq!(*back.lock(), *entry.lock());
+    }
+
+    #[rstest]
+    fn test_deserialized_entries_are_independent() {
+        let entry = Entry::new(InnerMock {
+            name: "alpha".into(),
+            value: 3,
+        });
+        let json = serde_json::to_string(&vec![entry.clone(), entry]).unwrap();
+
+        let back: Vec<Entry<InnerMock>> = serde_json::from_str(&json).unwrap();
+        assert!(!back[0].ptr_eq(&back[1]));
+
+        back[0].mutate(|v| v.value = 4);
+        assert_eq!(back[1].lock().value, 3);
+    }
+
+    fn mock(name: &str, value: i32) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            value,
+        }
+    }
+
+    #[rstest]
+    fn test_registry_round_trip() {
+        let reg = NamedRegistry::new();
+        reg.insert(mock("beta", 2)).unwrap();
+        reg.insert(mock("alpha", 1)).unwrap();
+
+        let json = serde_json::to_string(&reg).unwrap();
+        assert_eq!(
+            json,
+            r#"{"alpha":{"name":"alpha","value":1},"beta":{"name":"beta","value":2}}"#
+        );
+
+        let back: NamedRegistry<InnerMock> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.len(), 2);
+        assert_eq!(back.with("beta", |v| v.clone()), Some(mock("beta", 2)));
+    }
+
+    #[rstest]
+    #[case::mismatch(
+        r#"{"alpha":{"name":"beta","value":1}}"#,
+        "key `alpha` does not match the value's name `beta`"
+    )]
+    #[case::duplicate(
+        r#"{"alpha":{"name":"alpha","value":1},"alpha":{"name":"alpha","value":2}}"#,
+        "duplicate name `alpha`"
+    )]
+    fn test_registry_rejects_bad_input(#[case] json: &str, #[case] message: &str) {
+        let err = serde_json::from_str::<NamedRegistry<InnerMock>>(json).unwrap_err();
+        assert!(err.to_string().starts_with(message), "{err}");
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::Arc;
+
+use crate::entry::{Entry, HasName};
+use crate::sync::{RwLock, RwLockExt, RwLockReadGuard, RwLockWriteGuard};
+
+/// Shard count used by `ShardedRegistry::new`.
+pub const DEFAULT_SHARDS: usize = 16;
+
+/// A registry split into independently locked shards, so writes to one name
+/// only block readers and writers of names hashed to the same shard.
+///
+/// Operations spanning every shard (`len`, `keys`, ...) lock the shards one
+/// at a time. Under concurrent writes they see each shard at a different
+/// moment and are not a globally atomic snapshot.
+#[derive(Debug)]
+pub struct ShardedRegistry<T>(Arc<Shards<T>>);
+
+type Shard<T> = RwLock<HashMap<String, Entry<T>>>;
+
+#[derive(Debug)]
+struct Shards<T> {
+    maps: Box<[Shard<T>]>,
+    hasher: RandomState,
+}
+
+impl<T> Clone for ShardedRegistry<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> ShardedRegistry<T>
+where
+    T: HasName,
+{
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// # Panics
+    ///
+    /// If `shards` is zero.
+    pub fn with_shards(shards: usize) -> Self {
+        assert!(shards > 0, "a sharded registry needs at least one shard");
+        Self(Arc::new(Shards {
+            maps: (0..shards).map(|_| RwLock::default()).collect(),
+            hasher: RandomState::new(),
+        }))
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.0.maps.len()
+    }
+
+    pub fn insert(&self, value: T) -> Option<Entry<T>> {
+        let name = value.name();
+        self.lock(&name).insert(name, Entry::new(value))
+    }
+
+    pub fn get(&self, name: &str) -> Option<Entry<T>> {
+        self.rlock(name).get(name).cloned()
+    }
+
+    pub fn remove(&self, name: &str) -> Option<Entry<T>> {
+        self.lock(name).remove(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.rlock(name).contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards().map(|shard| shard.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards().all(|shard| shard.is_empty())
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.shards()
+            .flat_map(|shard| shard.keys().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn values(&self) -> Vec<Entry<T>> {
+        self.shards()
+            .flat_map(|shard| shard.values().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn clear(&self) -> Vec<Entry<T>> {
+        self.0
+            .maps
+            .iter()
+            .flat_map(|shard| {
+                let mut shard = shard.exclusive();
+                shard.drain().map(|(_, entry)| entry).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub fn with<F, R>(&self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.get(name).map(|entry| entry.with(f))
+    }
+
+    /// Runs `f` on the named value, moving the entry to its new shard if `f`
+    /// renamed it. The move is not atomic: the entry is briefly absent from
+    /// both shards.
+    pub fn mutate<F, R>(&self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let entry = self.get(name)?;
+        let result = entry.mutate(f);
+
+        if !entry.name_is(name) {
+            let moved = {
+                let mut shard = self.lock(name);
+                match shard.get(name) {
+                    Some(current) if current.ptr_eq(&entry) => shard.remove(name),
+                    _ => None,
+                }
+            };
+            if let Some(moved) = moved {
+                let new_name = moved.name();
+                self.lock(&new_name).insert(new_name, moved);
+            }
+        }
+        Some(result)
+    }
+
+    fn shard(&self, name: &str) -> &Shard<T> {
+        let index = self.0.hasher.hash_one(name) as usize % self.0.maps.len();
+        &self.0.maps[index]
+    }
+
+    // read-locks each shard in turn, releasing it before taking the next
+    fn shards(&self) -> impl Iterator<Item = RwLockReadGuard<'_, HashMap<String, Entry<T>>>> {
+        self.0.maps.iter().map(|shard| shard.shared())
+    }
+
+    fn rlock(&self, name: &str) -> RwLockReadGuard<'_, HashMap<String, Entry<T>>> {
+        self.shard(name).shared()
+    }
+
+    fn lock(&self, name: &str) -> RwLockWriteGuard<'_, HashMap<String, Entry<T>>> {
+        self.shard(name).exclusive()
+    }
+}
+
+impl<T> Default for ShardedRegistry<T>
+where
+    T: HasName,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn mock(name: &str, value: i32) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            value,
+        }
+    }
+
+    #[rstest]
+    fn test_registry_basics() {
+        let reg = ShardedRegistry::with_shards(4);
+        assert!(reg.insert(mock("alpha", 1)).is_none());
+        assert!(reg.insert(mock("beta", 2)).is_none());
+
+        assert_eq!(reg.mutate("alpha", |v| v.value += 1), Some(()));
+        assert_eq!(reg.with("alpha", |v| v.value), Some(2));
+        assert_eq!(reg.with("missing", |v| v.value), None);
+        assert_eq!(reg.len(), 2);
+
+        assert!(reg.remove("beta").is_some());
+        assert!(!reg.contains("beta"));
+        assert_eq!(reg.keys(), vec!["alpha".to_string()]);
+        assert_eq!(reg.clear().len(), 1);
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_mutate_moves_renamed_entry() {
+        let reg = ShardedRegistry::with_shards(8);
+        for i in 0..32 {
+            reg.insert(mock(&format!("item-{i}"), i));
+        }
+
+        for i in 0..32 {
+            reg.mutate(&format!("item-{i}"), |v| v.name = format!("renamed-{i}"));
+        }
+
+        assert_eq!(reg.len(), 32);
+        assert!(!reg.contains("item-0"));
+        assert_eq!(reg.with("renamed-31", |v| v.value), Some(31));
+    }
+
+    #[rstest]
+    #[should_panic(expected = "at least one shard")]
+    fn test_zero_shards_panics() {
+        ShardedRegistry::<InnerMock>::with_shards(0);
+    }
+
+    #[rstest]
+    fn test_concurrent_writers_across_shards() {
+        use std::thread;
+
+        let reg = ShardedRegistry::new();
+        reg.insert(mock("shared", 0));
+
+        let writers: Vec<_> = (0..50)
+            .map(|t| {
+                let reg = reg.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        let name = format!("t{t}-{i}");
+                        reg.insert(mock(&name, i));
+                        reg.mutate("shared", |v| v.value += 1);
+                        if i % 2 == 0 {
+                            assert!(reg.remove(&name).is_some());
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        // `len` visits the shards one after another, so while writers run it
+        // only falls somewhere between the start and end states
+        for _ in 0..100 {
+            assert!((1..=1 + 50 * 100).contains(&reg.len()));
+        }
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(reg.with("shared", |v| v.value), Some(50 * 100));
+        assert_eq!(reg.len(), 1 + 50 * 50);
+        assert!(reg.contains("t49-99"));
+        assert!(!reg.contains("t49-98"));
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+use crate::entry::{Entry, HasName};
+use crate::sync::{Mutex, MutexExt};
+
+/// A registry for read-mostly workloads. The map is published as an immutable
+/// snapshot, so `get` and `contains` are an atomic load and a hash lookup with
+/// no lock. Every write clones the map and swaps the copy in, which makes
+/// writes O(n); `NamedRegistry` stays the default choice.
+///
+/// Mutating an entry's value goes through the entry's own lock and does not
+/// copy the map.
+pub struct SnapshotRegistry<T>(Arc<SnapshotInner<T>>);
+
+struct SnapshotInner<T> {
+    map: ArcSwap<HashMap<String, Entry<T>>>,
+    // serializes writers so no copy-on-write update is lost
+    writer: Mutex<()>,
+}
+
+impl<T> Clone for SnapshotRegistry<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> SnapshotRegistry<T>
+where
+    T: HasName,
+{
+    pub fn new() -> Self {
+        Self(Arc::new(SnapshotInner {
+            map: ArcSwap::from_pointee(HashMap::new()),
+            writer: Mutex::new(()),
+        }))
+    }
+
+    pub fn insert(&self, value: T) -> Option<Entry<T>> {
+        let name = value.name();
+        self.write(|map| map.insert(name, Entry::new(value)))
+    }
+
+    pub fn get(&self, name: &str) -> Option<Entry<T>> {
+        self.0.map.load().get(name).cloned()
+    }
+
+    pub fn remove(&self, name: &str) -> Option<Entry<T>> {
+        if !self.contains(name) {
+            return None;
+        }
+        self.write(|map| map.remove(name))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.map.load().contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.map.load().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.map.load().is_empty()
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.0.map.load().keys().cloned().collect()
+    }
+
+    /// The currently published map. It never changes; later writes publish a
+    /// new one.
+    pub fn snapshot(&self) -> Arc<HashMap<String, Entry<T>>> {
+        self.0.map.load_full()
+    }
+
+    pub fn with<F, R>(&self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.get(name).map(|entry| entry.with(f))
+    }
+
+    /// Runs `f` on the named value. Only a rename publishes a new map.
+    pub fn mutate<F, R>(&self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let entry = self.get(name)?;
+        let result = entry.mutate(f);
+
+        if !entry.name_is(name) {
+            self.write(|map| {
+                if map.get(name).is_some_and(|current| current.ptr_eq(&entry)) {
+                    let moved = map.remove(name).expect("checked above");
+                    map.insert(moved.name(), moved);
+                }
+            });
+        }
+        Some(result)
+    }
+
+    fn write<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut HashMap<String, Entry<T>>) -> R,
+    {
+        let _writer = self.0.writer.acquire();
+        let mut map = HashMap::clone(&self.0.map.load());
+        let result = f(&mut map);
+        self.0.map.store(Arc::new(map));
+        result
+    }
+}
+
+impl<T> Default for SnapshotRegistry<T>
+where
+    T: HasName,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn mock(name: &str, value: i32) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            value,
+        }
+    }
+
+    #[rstest]
+    fn test_registry_basics() {
+        let reg = SnapshotRegistry::new();
+        assert!(reg.insert(mock("alpha", 1)).is_none());
+        assert!(reg.insert(mock("beta", 2)).is_none());
+
+        assert_eq!(reg.mutate("alpha", |v| v.value += 1), Some(()));
+        assert_eq!(reg.with("alpha", |v| v.value), Some(2));
+        assert_eq!(reg.with("missing", |v| v.value), None);
+        assert_eq!(reg.len(), 2);
+
+        assert!(reg.remove("beta").is_some());
+        assert!(reg.remove("beta").is_none());
+        assert!(!reg.contains("beta"));
+        assert_eq!(reg.keys(), vec!["alpha".to_string()]);
+    }
+
+    #[rstest]
+    fn test_mutate_rekeys_renamed_entry() {
+        let reg = SnapshotRegistry::new();
+        reg.insert(mock("alpha", 1));
+
+        reg.mutate("alpha", |v| v.name = "omega".into());
+
+        assert!(!reg.contains("alpha"));
+        assert_eq!(reg.with("omega", |v| v.value), Some(1));
+    }
+
+    #[rstest]
+    fn test_snapshot_is_unaffected_by_later_writes() {
+        let reg = SnapshotRegistry::new();
+        reg.insert(mock("alpha", 1));
+
+        let snapshot = reg.snapshot();
+        reg.insert(mock("beta", 2));
+        reg.remove("alpha");
+
+        assert_eq!(snapshot.len(), 1);
+        assert!(snapshot.contains_key("alpha"));
+        assert_eq!(reg.keys(), vec!["beta".to_string()]);
+    }
+
+    #[rstest]
+    fn test_readers_see_whole_writes() {
+        let reg = SnapshotRegistry::new();
+
+        let writer = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                for i in 0..200 {
+                    reg.insert(mock(&format!("item-{i}"), i));
+                }
+            })
+        };
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let reg = reg.clone();
+                thread::spawn(move || {
+                    let mut seen = 0;
+                    while seen < 200 {
+                        // inserts are published in order, so a snapshot of
+                        // `n` entries holds exactly the first `n`
+                        let snapshot = reg.snapshot();
+                        assert!(snapshot.len() >= seen);
+                        seen = snapshot.len();
+                        if let Some(last) = seen.checked_sub(1) {
+                            assert!(snapshot.contains_key(&format!("item-{last}")));
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(reg.len(), 200);
+    }
+
+    #[rstest]
+    fn test_readers_do_not_wait_for_writers() {
+        let reg = SnapshotRegistry::new();
+        reg.insert(mock("alpha", 1));
+
+        // a writer stalled mid-update, and the value itself locked
+        let writer = reg.0.writer.acquire();
+        let held = reg.get("alpha").unwrap();
+        let guard = held.lock();
+
+        let (done, finished) = mpsc::channel();
+        let reader = {
+            let reg = reg.clone();
+            thread::spawn(move || {
+                let found = (0..1_000).all(|_| reg.get("alpha").is_some() && reg.contains("alpha"));
+                done.send((found, reg.len())).unwrap();
+            })
+        };
+
+        assert_eq!(finished.recv_timeout(Duration::from_secs(5)), Ok((true, 1)));
+        reader.join().unwrap();
+        drop((guard, writer));
+    }
+}
@@ -0,0 +1,213 @@
+//! The lock types used throughout the crate: `std::sync` by default, or
+//! `parking_lot` with the `parking_lot` feature. The registry's map guards
+//! name these aliases; `Entry::arc` and `RwEntry::arc` hide them, handing out
+//! the same `SharedValue` and `SharedRwValue` in either build. `Entry` still
+//! adopts a raw `Arc` of the backend's own mutex.
+//!
+//! `parking_lot` locks are never poisoned: with that feature, poison checks
+//! report healthy and clearing poison does nothing.
+
+#[cfg(feature = "parking_lot")]
+pub use parking_lot::{Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(not(feature = "parking_lot"))]
+pub use std::sync::{Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use std::time::Duration;
+
+/// Uniform access to either `Mutex`; poisoning is recovered from unless a
+/// method says otherwise.
+pub(crate) trait MutexExt<T: ?Sized> {
+    fn acquire(&self) -> MutexGuard<'_, T>;
+
+    /// `None` if acquiring would block.
+    fn try_acquire(&self) -> Option<MutexGuard<'_, T>>;
+
+    /// `None` if the mutex is poisoned.
+    fn acquire_unpoisoned(&self) -> Option<MutexGuard<'_, T>>;
+
+    /// `None` if the lock was not acquired within `timeout`.
+    fn acquire_for(&self, timeout: Duration) -> Option<MutexGuard<'_, T>>;
+
+    fn poisoned(&self) -> bool;
+
+    fn unpoison(&self);
+}
+
+/// Uniform access to either `Condvar`, recovering from poisoning.
+pub(crate) trait CondvarExt {
+    /// Waits for a notification, a spurious wakeup, or `timeout` to pass.
+    fn wait_up_to<'a, T>(&self, guard: MutexGuard<'a, T>, timeout: Duration) -> MutexGuard<'a, T>;
+}
+
+/// Uniform access to either `RwLock`, recovering from poisoning.
+pub(crate) trait RwLockExt<T: ?Sized> {
+    fn shared(&self) -> RwLockReadGuard<'_, T>;
+
+    fn exclusive(&self) -> RwLockWriteGuard<'_, T>;
+
+    /// `None` if the lock is poisoned.
+    fn exclusive_unpoisoned(&self) -> Option<RwLockWriteGuard<'_, T>>;
+
+    fn poisoned(&self) -> bool;
+
+    fn unpoison(&self);
+}
+
+#[cfg(not(feature = "parking_lot"))]
+mod imp {
+    use std::sync::{PoisonError, TryLockError};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    impl<T: ?Sized> MutexExt<T> for Mutex<T> {
+        fn acquire(&self) -> MutexGuard<'_, T> {
+            self.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        fn try_acquire(&self) -> Option<MutexGuard<'_, T>> {
+            match self.try_lock() {
+                Ok(guard) => Some(guard),
+                Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+                Err(TryLockError::WouldBlock) => None,
+            }
+        }
+
+        fn acquire_unpoisoned(&self) -> Option<MutexGuard<'_, T>> {
+            self.lock().ok()
+        }
+
+        // std has no timed acquisition, so poll with a short backoff
+        fn acquire_for(&self, timeout: Duration) -> Option<MutexGuard<'_, T>> {
+            let start = Instant::now();
+            let mut backoff = Duration::from_micros(10);
+            loop {
+                if let Some(guard) = self.try_acquire() {
+                    return Some(guard);
+                }
+                let waited = start.elapsed();
+                if waited >= timeout {
+                    return None;
+                }
+                thread::sleep(backoff.min(timeout - waited));
+                backoff = (backoff * 2).min(Duration::from_millis(1));
+            }
+        }
+
+        fn poisoned(&self) -> bool {
+            self.is_poisoned()
+        }
+
+        fn unpoison(&self) {
+            self.clear_poison();
+        }
+    }
+
+    impl<T: ?Sized> RwLockExt<T> for RwLock<T> {
+        fn shared(&self) -> RwLockReadGuard<'_, T> {
+            self.read().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        fn exclusive(&self) -> RwLockWriteGuard<'_, T> {
+            self.write().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        fn exclusive_unpoisoned(&self) -> Option<RwLockWriteGuard<'_, T>> {
+            self.write().ok()
+        }
+
+        fn poisoned(&self) -> bool {
+            self.is_poisoned()
+        }
+
+        fn unpoison(&self) {
+            self.clear_poison();
+        }
+    }
+
+    impl CondvarExt for Condvar {
+        fn wait_up_to<'a, T>(
+            &self,
+            guard: MutexGuard<'a, T>,
+            timeout: Duration,
+        ) -> MutexGuard<'a, T> {
+            match self.wait_timeout(guard, timeout) {
+                Ok((guard, _)) => guard,
+                Err(err) => err.into_inner().0,
+            }
+        }
+    }
+
+    pub(crate) fn into_inner<T>(mutex: Mutex<T>) -> T {
+        mutex.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+mod imp {
+    use std::time::Duration;
+
+    use super::*;
+
+    impl<T: ?Sized> MutexExt<T> for Mutex<T> {
+        fn acquire(&self) -> MutexGuard<'_, T> {
+            self.lock()
+        }
+
+        fn try_acquire(&self) -> Option<MutexGuard<'_, T>> {
+            self.try_lock()
+        }
+
+        fn acquire_unpoisoned(&self) -> Option<MutexGuard<'_, T>> {
+            Some(self.lock())
+        }
+
+        fn acquire_for(&self, timeout: Duration) -> Option<MutexGuard<'_, T>> {
+            self.try_lock_for(timeout)
+        }
+
+        fn poisoned(&self) -> bool {
+            false
+        }
+
+        fn unpoison(&self) {}
+    }
+
+    impl<T: ?Sized> RwLockExt<T> for RwLock<T> {
+        fn shared(&self) -> RwLockReadGuard<'_, T> {
+            self.read()
+        }
+
+        fn exclusive(&self) -> RwLockWriteGuard<'_, T> {
+            self.write()
+        }
+
+        fn exclusive_unpoisoned(&self) -> Option<RwLockWriteGuard<'_, T>> {
+            Some(self.write())
+        }
+
+        fn poisoned(&self) -> bool {
+            false
+        }
+
+        fn unpoison(&self) {}
+    }
+
+    impl CondvarExt for Condvar {
+        fn wait_up_to<'a, T>(
+            &self,
+            mut guard: MutexGuard<'a, T>,
+            timeout: Duration,
+        ) -> MutexGuard<'a, T> {
+            self.wait_for(&mut guard, timeout);
+            guard
+        }
+    }
+
+    pub(crate) fn into_inner<T>(mutex: Mutex<T>) -> T {
+        mutex.into_inner()
+    }
+}
+
+pub(crate) use imp::into_inner;
@@ -0,0 +1,453 @@
+//! Write-ahead logging, enabled by the `binary` feature.
+//!
+//! A log is the magic bytes and a format version, then one frame per write:
+//! the body's length (little-endian `u32`) and FNV-1a checksum (`u64`), then
+//! the body, a tag byte followed by the bincode-encoded value or name.
+
+use std::fs::{self, File, OpenOptions};
+use std::hash::BuildHasher;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::binary::checksum;
+use crate::entry::{Entry, HasName};
+use crate::error::SnapshotError;
+use crate::registry::NamedRegistry;
+use crate::sync::{Mutex, MutexExt};
+
+const MAGIC: &[u8; 4] = b"NWAL";
+const VERSION: u8 = 1;
+const HEADER_LEN: u64 = MAGIC.len() as u64 + 1;
+const FRAME_LEN: usize = 4 + 8;
+
+const PUT: u8 = 0;
+const REMOVE: u8 = 1;
+
+/// When `NamedRegistry::open_wal` forces appended records to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// After every record.
+    EveryWrite,
+    /// On the first record at least this long after the previous sync, and
+    /// on `flush_wal` and `checkpoint`. Records written since the last sync
+    /// can be lost in a crash.
+    Interval(Duration),
+}
+
+/// The open log of a registry.
+pub(crate) struct WalFile {
+    file: BufWriter<File>,
+    policy: SyncPolicy,
+    last_sync: Instant,
+    // hooks cannot return errors, so the first one waits here for
+    // `flush_wal` or `checkpoint`
+    failed: Option<io::Error>,
+}
+
+impl WalFile {
+    fn open(path: &Path, policy: SyncPolicy) -> Result<Self, SnapshotError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(MAGIC)?;
+            file.write_all(&[VERSION])?;
+            file.sync_data()?;
+        } else {
+            check_header(&fs::read(path)?)?;
+        }
+        Ok(Self {
+            file: BufWriter::new(file),
+            policy,
+            last_sync: Instant::now(),
+            failed: None,
+        })
+    }
+
+    fn append(&mut self, body: io::Result<Vec<u8>>) {
+        if self.failed.is_some() {
+            return;
+        }
+        let written = body.and_then(|body| {
+            let len = u32::try_from(body.len()).map_err(io::Error::other)?;
+            self.file.write_all(&len.to_le_bytes())?;
+            self.file.write_all(&checksum(&body).to_le_bytes())?;
+            self.file.write_all(&body)?;
+            match self.policy {
+                SyncPolicy::EveryWrite => self.sync(),
+                SyncPolicy::Interval(every) if self.last_sync.elapsed() >= every => self.sync(),
+                SyncPolicy::Interval(_) => Ok(()),
+            }
+        });
+        self.failed = written.err();
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(err) = self.failed.take() {
+            return Err(err);
+        }
+        self.sync()
+    }
+
+    // drops every record, keeping the header
+    fn truncate(&mut self) -> io::Result<()> {
+        self.flush()?;
+        let file = self.file.get_mut();
+        file.set_len(HEADER_LEN)?;
+        file.seek(SeekFrom::End(0))?;
+        file.sync_data()
+    }
+}
+
+fn check_header(bytes: &[u8]) -> Result<(), SnapshotError> {
+    if bytes.len() < HEADER_LEN as usize || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(SnapshotError::NotASnapshot);
+    }
+    match bytes[MAGIC.len()] {
+        VERSION => Ok(()),
+        version => Err(SnapshotError::UnsupportedVersion(version)),
+    }
+}
+
+fn body<V: Serialize + ?Sized>(tag: u8, value: &V) -> io::Result<Vec<u8>> {
+    let mut body = vec![tag];
+    bincode::serialize_into(&mut body, value).map_err(io::Error::other)?;
+    Ok(body)
+}
+
+fn put<T: HasName + Serialize>(entry: &Entry<T>) -> io::Result<Vec<u8>> {
+    body(PUT, &*entry.lock())
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + Serialize + Send + 'static,
+    S: BuildHasher + Default + Send + Sync + 'static,
+{
+    /// Appends a record to the log at `path` for every later change the
+    /// hooks see: the value now registered under the changed name, or the
+    /// name alone once nothing is, so a rename logs both. Records are read
+    /// back from the registry under the log's lock rather than taken from
+    /// the change, so however concurrent writes to a name race their hooks,
+    /// its last record is its current state. Replay the log with
+    /// `replay_wal` before opening it, or the replayed writes are logged
+    /// again. A failed append stops logging and is returned by the next
+    /// `flush_wal` or `checkpoint`.
+    pub fn open_wal(&self, path: &Path, policy: SyncPolicy) -> Result<(), SnapshotError> {
+        let mut slot = self.wal().acquire();
+        if slot.is_some() {
+            return Err(SnapshotError::Io(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "a write-ahead log is already open",
+            )));
+        }
+        let wal = Arc::new(Mutex::new(WalFile::open(path, policy)?));
+        let registered = Arc::new(self.registered_by());
+        let log = || {
+            let (wal, registered) = (Arc::clone(&wal), Arc::clone(&registered));
+            move |name: &str| {
+                let mut wal = wal.acquire();
+                match registered(name) {
+                    Some(Some(entry)) => wal.append(put(&entry)),
+                    Some(None) => wal.append(body(REMOVE, name)),
+                    None => {}
+                }
+            }
+        };
+        let inserted = log();
+        self.on_insert(move |name, _| inserted(name));
+        let mutated = log();
+        self.on_mutate(move |name, _| mutated(name));
+        let removed = log();
+        self.on_remove(move |name, _| removed(name));
+        let replaced = log();
+        self.on_replace(move |name, _, _| replaced(name));
+        *slot = Some(wal);
+        Ok(())
+    }
+
+    /// Forces logged records to disk.
+    pub fn flush_wal(&self) -> io::Result<()> {
+        match &*self.wal().acquire() {
+            Some(wal) => wal.acquire().flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Saves a snapshot to `snapshot` and empties the log, so recovery is
+    /// `load_from` followed by `replay_wal`. Writes made meanwhile wait to be
+    /// logged until it is done; replaying them over the snapshot is harmless.
+    pub fn checkpoint(&self, snapshot: &Path) -> Result<(), SnapshotError> {
+        let slot = self.wal().acquire();
+        let mut wal = slot.as_ref().map(|wal| wal.acquire());
+        if let Some(wal) = &mut wal {
+            wal.flush()?;
+        }
+        self.save_to(snapshot)?;
+        if let Some(wal) = &mut wal {
+            wal.truncate()?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, S> NamedRegistry<T, Arc<str>, S>
+where
+    T: HasName + DeserializeOwned,
+    S: BuildHasher + Default,
+{
+    /// Applies the records of the log at `path` in order and returns how
+    /// many. Stops cleanly at the first incomplete or damaged frame, which is
+    /// what a crash mid-append leaves behind.
+    pub fn replay_wal(&self, path: &Path) -> Result<usize, SnapshotError> {
+        let bytes = fs::read(path)?;
+        check_header(&bytes)?;
+        let corrupt = |err: bincode::Error| SnapshotError::Corrupt(err.to_string());
+
+        let mut rest = &bytes[HEADER_LEN as usize..];
+        let mut applied = 0;
+        while rest.len() >= FRAME_LEN {
+            let len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+            let sum = u64::from_le_bytes(rest[4..FRAME_LEN].try_into().unwrap());
+            let Some(body) = rest[FRAME_LEN..].get(..len) else {
+                break;
+            };
+            if body.is_empty() || checksum(body) != sum {
+                break;
+            }
+            match body[0] {
+                PUT => {
+                    let value: T = bincode::deserialize(&body[1..]).map_err(corrupt)?;
+                    self.insert(value)
+                        .map_err(|err| SnapshotError::Corrupt(err.to_string()))?;
+                }
+                REMOVE => {
+                    let name: String = bincode::deserialize(&body[1..]).map_err(corrupt)?;
+                    self.remove(name.as_str());
+                }
+                tag => return Err(SnapshotError::Corrupt(format!("unknown record tag {tag}"))),
+            }
+            applied += 1;
+            rest = &rest[FRAME_LEN + len..];
+        }
+        Ok(applied)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use serde::Deserialize;
+    use std::path::PathBuf;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    impl crate::entry::SetName for InnerMock {
+        fn set_name(&mut self, name: &str) {
+            self.name = name.into();
+        }
+    }
+
+    fn mock(name: &str, value: i32) -> InnerMock {
+        InnerMock {
+            name: name.into(),
+            value,
+        }
+    }
+
+    fn scratch(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("core-wal-{}-{test}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn contents(reg: &NamedRegistry<InnerMock>) -> Vec<(String, i32)> {
+        let mut values: Vec<_> = reg
+            .values()
+            .iter()
+            .map(|entry| entry.with(|v| (v.name.clone(), v.value)))
+            .collect();
+        values.sort();
+        values
+    }
+
+    // a registry logging to `log` after a scripted series of writes
+    fn logged(log: &Path) -> NamedRegistry<InnerMock> {
+        let reg = NamedRegistry::new();
+        reg.open_wal(log, SyncPolicy::EveryWrite).unwrap();
+        reg.insert(mock("alpha", 1)).unwrap();
+        reg.insert(mock("beta", 2)).unwrap();
+        reg.mutate("alpha", |v| v.value += 10);
+        reg.insert(mock("beta", 20)).unwrap();
+        reg.rename("beta", "gamma").unwrap();
+        reg.insert(mock("delta", 4)).unwrap();
+        reg.remove("delta");
+        reg
+    }
+
+    #[rstest]
+    fn test_replay_reconstructs_state() {
+        let log = scratch("replay").join("registry.wal");
+        let reg = logged(&log);
+        reg.flush_wal().unwrap();
+
+        let replayed = NamedRegistry::new();
+        assert_eq!(replayed.replay_wal(&log).unwrap(), 8);
+        assert_eq!(contents(&replayed), contents(&reg));
+        assert_eq!(
+            contents(&replayed),
+            [("alpha".to_string(), 11), ("gamma".to_string(), 20)]
+        );
+    }
+
+    #[rstest]
+    fn test_replay_sees_swap_take_and_retain() {
+        let log = scratch("swap-take").join("registry.wal");
+        let reg = NamedRegistry::new();
+        reg.open_wal(&log, SyncPolicy::EveryWrite).unwrap();
+        for (name, value) in [("a", 1), ("b", 2), ("c", 3), ("d", 4)] {
+            reg.insert(mock(name, value)).unwrap();
+        }
+
+        assert!(reg.swap("a", "b"));
+        assert_eq!(reg.take("c").unwrap().value, 3);
+        reg.retain(|_, v| {
+            v.value *= 10;
+            v.value != 40
+        });
+        reg.flush_wal().unwrap();
+
+        let replayed = NamedRegistry::new();
+        replayed.replay_wal(&log).unwrap();
+        assert_eq!(contents(&replayed), contents(&reg));
+        assert_eq!(
+            contents(&reg),
+            [("a".to_string(), 20), ("b".to_string(), 10)]
+        );
+    }
+
+    #[rstest]
+    fn test_replay_matches_racing_writes_to_one_name() {
+        use std::thread;
+
+        let dir = scratch("race");
+        for round in 0..40 {
+            let log = dir.join(format!("registry-{round}.wal"));
+            let reg = NamedRegistry::new();
+            // a slow hook ahead of the log's widens the gap after each insert
+            reg.on_insert(|_, _| thread::sleep(Duration::from_micros(50)));
+            reg.open_wal(&log, SyncPolicy::Interval(Duration::from_secs(60)))
+                .unwrap();
+            thread::scope(|scope| {
+                for worker in 0..4 {
+                    let reg = &reg;
+                    scope.spawn(move || {
+                        for step in 0..50 {
+                            if (worker + step) % 2 == 0 {
+                                reg.insert(mock("k", worker * 100 + step)).unwrap();
+                            } else {
+                                reg.remove("k");
+                            }
+                        }
+                    });
+                }
+            });
+            reg.flush_wal().unwrap();
+
+            let replayed = NamedRegistry::new();
+            replayed.replay_wal(&log).unwrap();
+            assert_eq!(contents(&replayed), contents(&reg), "round {round}");
+        }
+    }
+
+    #[rstest]
+    fn test_replay_stops_at_a_torn_record() {
+        let log = scratch("torn").join("registry.wal");
+        drop(logged(&log));
+        let full = fs::read(&log).unwrap();
+
+        // every cut short of the end loses only the records it splits
+        let mut applied = Vec::new();
+        for cut in HEADER_LEN as usize..full.len() {
+            fs::write(&log, &full[..cut]).unwrap();
+            applied.push(NamedRegistry::<InnerMock>::new().replay_wal(&log).unwrap());
+        }
+        assert!(applied.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(applied.last(), Some(&7));
+
+        let mut damaged = full.clone();
+        *damaged.last_mut().unwrap() ^= 0xff;
+        fs::write(&log, damaged).unwrap();
+        assert_eq!(
+            NamedRegistry::<InnerMock>::new().replay_wal(&log).unwrap(),
+            7
+        );
+    }
+
+    #[rstest]
+    fn test_checkpoint_then_replay_matches_the_live_registry() {
+        let dir = scratch("checkpoint");
+        let (log, snapshot) = (dir.join("registry.wal"), dir.join("registry.snap"));
+        let reg = logged(&log);
+
+        reg.checkpoint(&snapshot).unwrap();
+        assert_eq!(fs::metadata(&log).unwrap().len(), HEADER_LEN);
+        reg.mutate("gamma", |v| v.value += 1);
+        reg.insert(mock("epsilon", 5)).unwrap();
+        reg.flush_wal().unwrap();
+
+        let recovered = NamedRegistry::<InnerMock>::load_from(&snapshot).unwrap();
+        assert_eq!(recovered.replay_wal(&log).unwrap(), 2);
+        assert_eq!(contents(&recovered), contents(&reg));
+    }
+
+    #[rstest]
+    fn test_other_files_are_not_opened_as_logs() {
+        let dir = scratch("header");
+        let path = dir.join("not-a.wal");
+        fs::write(&path, b"hello").unwrap();
+
+        let reg = NamedRegistry::<InnerMock>::new();
+        assert!(matches!(
+            reg.open_wal(&path, SyncPolicy::Interval(Duration::from_secs(1))),
+            Err(SnapshotError::NotASnapshot)
+        ));
+        assert!(matches!(
+            reg.replay_wal(&path),
+            Err(SnapshotError::NotASnapshot)
+        ));
+
+        let log = dir.join("registry.wal");
+        reg.open_wal(&log, SyncPolicy::EveryWrite).unwrap();
+        assert!(matches!(
+            reg.open_wal(&log, SyncPolicy::EveryWrite),
+            Err(SnapshotError::Io(err)) if err.kind() == io::ErrorKind::AlreadyExists
+        ));
+    }
+}
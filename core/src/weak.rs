@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::entry::{Entry, HasName, WeakEntry};
+use crate::sync::{RwLock, RwLockExt};
+
+/// Slot count below which writes never prune.
+const MIN_PRUNE_AT: usize = 16;
+
+/// An index of entries that does not keep them alive. Owners hold the
+/// `Entry`; once the last handle is dropped, `get` stops finding it and the
+/// slot is pruned, either by `prune` or by a later write.
+///
+/// Entries are indexed under their name at insertion. Renaming one through
+/// its handle does not move it.
+#[derive(Debug)]
+pub struct WeakRegistry<T>(Arc<RwLock<Slots<T>>>);
+
+#[derive(Debug)]
+struct Slots<T> {
+    map: HashMap<String, WeakEntry<T>>,
+    // writes prune once the map reaches this many slots, so pruning stays
+    // amortized O(1) per write
+    prune_at: usize,
+}
+
+impl<T> Slots<T> {
+    fn prune(&mut self) -> usize {
+        let before = self.map.len();
+        self.map.retain(|_, entry| entry.is_alive());
+        self.prune_at = (self.map.len() * 2).max(MIN_PRUNE_AT);
+        before - self.map.len()
+    }
+}
+
+impl<T> Clone for WeakRegistry<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> WeakRegistry<T>
+where
+    T: HasName,
+{
+    pub fn new() -> Self {
+        Self(Arc::new(RwLock::new(Slots {
+            map: HashMap::new(),
+            prune_at: MIN_PRUNE_AT,
+        })))
+    }
+
+    /// Indexes `entry` under its name, returning the live entry it displaced.
+    pub fn insert(&self, entry: &Entry<T>) -> Option<Entry<T>> {
+        let mut slots = self.0.exclusive();
+        if slots.map.len() >= slots.prune_at {
+            slots.prune();
+        }
+        slots
+            .map
+            .insert(entry.name(), entry.weak())
+            .and_then(|previous| previous.upgrade())
+    }
+
+    /// The entry under `name`, if any handle to it is still alive.
+    pub fn get(&self, name: &str) -> Option<Entry<T>> {
+        self.0.shared().map.get(name)?.upgrade()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0
+            .shared()
+            .map
+            .get(name)
+            .is_some_and(WeakEntry::is_alive)
+    }
+
+    pub fn remove(&self, name: &str) -> Option<Entry<T>> {
+        self.0.exclusive().map.remove(name)?.upgrade()
+    }
+
+    /// Drops the slots of dead entries and returns how many there were.
+    pub fn prune(&self) -> usize {
+        self.0.exclusive().prune()
+    }
+
+    /// Live entries; dead slots awaiting a prune are not counted.
+    pub fn len(&self) -> usize {
+        self.0
+            .shared()
+            .map
+            .values()
+            .filter(|entry| entry.is_alive())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Names of the live entries.
+    pub fn keys(&self) -> Vec<String> {
+        self.0
+            .shared()
+            .map
+            .iter()
+            .filter(|(_, entry)| entry.is_alive())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+impl<T> Default for WeakRegistry<T>
+where
+    T: HasName,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct InnerMock {
+        name: String,
+        value: i32,
+    }
+
+    impl HasName for InnerMock {
+        fn name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    fn mock(name: &str, value: i32) -> Entry<InnerMock> {
+        Entry::new(InnerMock {
+            name: name.into(),
+            value,
+        })
+    }
+
+    fn slots(reg: &WeakRegistry<InnerMock>) -> usize {
+        reg.0.shared().map.len()
+    }
+
+    #[rstest]
+    fn test_dropped_entries_disappear() {
+        let reg = WeakRegistry::new();
+        let alpha = mock("alpha", 1);
+        let beta = mock("beta", 2);
+        reg.insert(&alpha);
+        reg.insert(&beta);
+
+        let found = reg.get("alpha").unwrap();
+        assert!(found.ptr_eq(&alpha));
+        drop((alpha, found));
+
+        assert!(reg.get("alpha").is_none());
+        assert!(!reg.contains("alpha"));
+        assert_eq!(reg.len(), 1);
+        assert_eq!(slots(&reg), 2);
+        assert_eq!(reg.prune(), 1);
+        assert_eq!(slots(&reg), 1);
+        assert_eq!(reg.keys(), vec!["beta".to_string()]);
+    }
+
+    #[rstest]
+    fn test_name_can_be_reused_after_its_entry_dies() {
+        let reg = WeakRegistry::new();
+        drop(reg.insert(&mock("alpha", 1)));
+        assert!(reg.get("alpha").is_none());
+
+        let revived = mock("alpha", 2);
+        assert!(reg.insert(&revived).is_none());
+        assert_eq!(reg.get("alpha").unwrap().with(|v| v.value), 2);
+
+        let replacement = mock("alpha", 3);
+        assert!(reg.insert(&replacement).unwrap().ptr_eq(&revived));
+        assert!(reg.remove("alpha").unwrap().ptr_eq(&replacement));
+        assert!(reg.is_empty());
+    }
+
+    #[rstest]
+    fn test_writes_prune_dead_slots() {
+        let reg = WeakRegistry::new();
+        let kept = mock("kept", 0);
+        reg.insert(&kept);
+        for i in 0..1_000 {
+            reg.insert(&mock(&format!("temp-{i}"), i));
+        }
+
+        assert!(slots(&reg) <= MIN_PRUNE_AT);
+        assert_eq!(reg.len(), 1);
+        assert!(reg.contains("kept"));
+    }
+}
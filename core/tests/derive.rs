@@ -0,0 +1,72 @@
+#![cfg(feature = "derive")]
+
+use std::borrow::Cow;
+
+use core::{HasName, NamedRegistry};
+use rstest::rstest;
+
+#[derive(HasName)]
+struct Plain {
+    name: String,
+}
+
+#[derive(HasName)]
+#[has_name(field = "id")]
+struct ById {
+    id: u32,
+}
+
+fn label(value: &WithFn) -> String {
+    format!("{}-{}", value.tenant, value.service)
+}
+
+#[derive(HasName)]
+#[has_name(with = "label")]
+struct WithFn {
+    tenant: &'static str,
+    service: &'static str,
+}
+
+#[derive(HasName)]
+struct Generic<T> {
+    name: String,
+    #[allow(dead_code)]
+    value: T,
+}
+
+#[rstest]
+fn test_derive_name_field() {
+    let plain = Plain {
+        name: "alpha".into(),
+    };
+
+    assert_eq!(plain.name(), "alpha");
+    assert!(matches!(plain.name_ref(), Cow::Borrowed("alpha")));
+}
+
+#[rstest]
+fn test_derive_custom_field() {
+    assert_eq!(ById { id: 7 }.name(), "7");
+}
+
+#[rstest]
+fn test_derive_with_fn() {
+    let value = WithFn {
+        tenant: "acme",
+        service: "db",
+    };
+
+    assert_eq!(value.name(), "acme-db");
+}
+
+#[rstest]
+fn test_derive_generic_in_registry() {
+    let reg = NamedRegistry::new();
+    reg.insert(Generic {
+        name: "alpha".into(),
+        value: 1u8,
+    })
+    .unwrap();
+
+    assert!(reg.contains("alpha"));
+}
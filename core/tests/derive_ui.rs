@@ -0,0 +1,7 @@
+#![cfg(feature = "derive")]
+
+#[test]
+fn test_derive_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,54 @@
+use core::{HasName, NamedRegistry};
+use rstest::rstest;
+
+#[derive(Debug, Clone, PartialEq)]
+struct InnerMock {
+    name: String,
+    value: i32,
+}
+
+impl HasName for InnerMock {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+fn mock(name: &str, value: i32) -> InnerMock {
+    InnerMock {
+        name: name.into(),
+        value,
+    }
+}
+
+#[rstest]
+fn test_new_is_public() {
+    let reg = NamedRegistry::<InnerMock>::new();
+    reg.insert(mock("alpha", 1)).unwrap();
+
+    assert!(reg.contains("alpha"));
+}
+
+#[rstest]
+fn test_default_is_empty() {
+    let reg: NamedRegistry<InnerMock> = NamedRegistry::default();
+
+    assert!(!reg.contains("alpha"));
+    assert!(reg.is_empty());
+}
+
+#[rstest]
+fn test_from_entries_prepopulates() {
+    let reg = NamedRegistry::from_entries(vec![mock("alpha", 1), mock("beta", 2)]);
+
+    assert_eq!(reg.get("alpha").unwrap().lock().value, 1);
+    assert_eq!(reg.get("beta").unwrap().lock().value, 2);
+    assert_eq!(reg.len(), 2);
+}
+
+#[rstest]
+fn test_from_entries_last_duplicate_wins() {
+    let reg = NamedRegistry::from_entries(vec![mock("alpha", 1), mock("alpha", 2)]);
+
+    assert_eq!(reg.get("alpha").unwrap().lock().value, 2);
+    assert_eq!(reg.len(), 1);
+}
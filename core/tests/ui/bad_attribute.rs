@@ -0,0 +1,9 @@
+use core::HasName;
+
+#[derive(HasName)]
+#[has_name(rename = "id")]
+struct Service {
+    id: String,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use core::HasName;
+
+#[derive(HasName)]
+enum Service {
+    Parser,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use core::HasName;
+
+#[derive(HasName)]
+struct Service {
+    id: u32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use core::HasName;
+
+#[derive(HasName)]
+#[has_name(field = "label")]
+struct Service {
+    name: String,
+}
+
+fn main() {}